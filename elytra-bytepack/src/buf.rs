@@ -1,4 +1,4 @@
-use core::{ops::{Add, AddAssign, Deref}, usize};
+use core::ops::{Add, AddAssign, Deref};
 
 pub struct Buf<const N: usize> {
     inner: [u8; N],
@@ -80,6 +80,12 @@ impl <const N: usize> From<[u8; N]> for Buf<N> {
     }
 }
 
+impl<const N: usize> Default for Buf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl <const N: usize> Buf<N> {
     pub fn new() -> Self {
         Self {
@@ -79,7 +79,7 @@
         pub fn remaining_slice_mut(&mut self) -> &mut [u8] {
             let len = self.position.min(self.inner.as_mut().len());
 
-            &mut self.inner.as_mut()[len..]
+            &mut self.inner[len..]
         }
 
         pub fn write(&mut self, data: &[u8]) -> Result<usize> {
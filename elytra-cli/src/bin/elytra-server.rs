@@ -6,21 +6,33 @@ use color_eyre::eyre::OptionExt;
 use elytra_cli::ElytraDevice;
 use elytra_cli::wasm::WasmDevice;
 use elytra_cli::tcp::TcpServer;
+use elytra_conf::store::FileStore;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     eprintln!("elytra mock impl server");
 
-    let file_path = args().skip(1).next().map(|s|  PathBuf::from_str(&s).unwrap())
+    let file_path = args().nth(1).map(|s|  PathBuf::from_str(&s).unwrap())
         .ok_or_eyre("Missing argument FILE")?;
 
-    let mut device = WasmDevice::new(&file_path)?;
+    let mut device = match args().nth(2) {
+        Some(store_path) => {
+            eprintln!("Storing props in {}", store_path);
+            WasmDevice::with_store(&file_path, FileStore::new(store_path))?
+        },
+        None => WasmDevice::new(&file_path)?,
+    };
+
+    let device: &mut dyn ElytraDevice = &mut device;
+    // Only used to keep secrets out of the log below
+    let info = device.get_info()?;
+    let schema = device.get_schema(&info)?;
 
     let mut server = TcpServer::new()?;
     loop {
         eprint!("Waiting for connection... ");
-        let bytes = server.recieve()?;
-        eprintln!("Recieved: {:02x?}", bytes);
-        let bytes = device.send_command_raw(bytes.try_into().unwrap())?;
+        let bytes = server.recieve()?.try_into().unwrap();
+        eprintln!("Recieved: {:02x?}", schema.redact_command(bytes));
+        let bytes = device.send_command_raw(bytes)?;
         eprintln!("Response: {:02x?}", bytes);
         server.respond(&bytes)?;
         eprintln!();
@@ -3,13 +3,17 @@ use std::{error::Error, net::SocketAddr};
 use std::path::PathBuf;
 use elytra_conf::entry::ExtraFlags;
 use elytra_conf::config::QueryTargetKey;
+use elytra_conf::text::CharClass;
 use elytra_conf::values::ValueType;
 
 use owo_colors::{AnsiColors, OwoColorize};
 
 use clap::{Args, Parser, Subcommand};
 
-use elytra_cli::{ElytraDevice, tcp::TcpDevice, wasm::WasmDevice, Entry, LayoutEntry, Section, tui};
+use elytra_cli::{ElytraDevice, Entry, tcp::TcpDevice, wasm::WasmDevice, LayoutEntry, tui};
+use elytra_cli::value::{color_value, format_chars, format_item, format_number, format_step, format_value, list_items, parse_value, record_cells};
+use elytra_conf::command::ListEditKey;
+use elytra_conf::color::palette_entry;
 
 #[derive(Debug, Clone)]
 enum DeviceType {
@@ -51,7 +55,26 @@ enum Commands {
     Query(QueryArgs),
 
     /// View a section summary
-    Sections
+    Sections,
+
+    /// Read the value of a prop (c) or info field (i)
+    Get(GetArgs),
+
+    /// Write the value of a prop, list props take any number of items. Several props are
+    /// written all or nothing
+    Set(SetArgs),
+
+    /// Change a single item of a list prop
+    List(ListArgs),
+
+    /// Add, change or delete a row of a table prop
+    Row(RowArgs),
+
+    /// Clear a secret prop
+    Clear(ClearArgs),
+
+    /// Reset a prop to its default, or every prop with --all
+    Reset(ResetArgs)
 }
 
 /// Elytra command line tool
@@ -81,6 +104,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         Commands::Query(args) => run_query(device, args),
         Commands::Info => run_info(device),
         Commands::Sections => run_sections(device),
+        Commands::Get(args) => run_get(device, args),
+        Commands::Set(args) => run_set(device, args),
+        Commands::List(args) => run_list(device, args),
+        Commands::Row(args) => run_row(device, args),
+        Commands::Clear(args) => run_clear(device, args),
+        Commands::Reset(args) => run_reset(device, args),
     }
 
 }
@@ -93,73 +122,369 @@ struct QueryArgs {
     prop: QueryTargetKey
 }
 
-fn run_info(mut device: Box<dyn ElytraDevice + 'static>) -> Result<(), Box<dyn Error>> {
+#[derive(Debug, Args)]
+struct GetArgs {
+    entry: char,
+    index: u8,
+    /// Show the default value instead of the current one
+    #[arg(long)]
+    default: bool,
+}
+
+#[derive(Debug, Args)]
+struct SetArgs {
+    /// Have the device revert the change unless it can still be reached to confirm it
+    /// within SECONDS, for settings that could cut off the connection
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u16).range(1..))]
+    confirm_within: Option<u16>,
+    /// The index followed by the value, or the items of a list. A single `-` empties a
+    /// list. Several props are written as `INDEX=VALUE` pairs
+    #[arg(allow_hyphen_values = true, required = true, value_name = "INDEX VALUES|INDEX=VALUE")]
+    args: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+struct ClearArgs {
+    index: u8,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct ResetArgs {
+    index: Option<u8>,
+    /// Reset every prop of the device
+    #[arg(long)]
+    all: bool,
+}
+
+#[derive(Debug, Args)]
+struct ListArgs {
+    index: u8,
+    #[command(subcommand)]
+    edit: ListEdit,
+}
+
+#[derive(Debug, Subcommand)]
+enum ListEdit {
+    /// Add an item to the end of the list
+    Add {
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Replace the item at a position
+    Replace {
+        at: u8,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Remove the item at a position
+    Remove { at: u8 },
+    /// Remove all items
+    Clear,
+}
+
+#[derive(Debug, Args)]
+struct RowArgs {
+    index: u8,
+    #[command(subcommand)]
+    edit: RowEdit,
+}
+
+#[derive(Debug, Subcommand)]
+enum RowEdit {
+    /// Add a row with the given values of the first columns, the rest keep their defaults
+    Add {
+        #[arg(allow_hyphen_values = true, required = true)]
+        values: Vec<String>,
+    },
+    /// Change a cell of a row, the column is given by name or position
+    Set {
+        row: u8,
+        column: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Delete a row, the rows after it move up
+    Delete { row: u8 },
+}
+
+fn run_get(mut device: Box<dyn ElytraDevice + 'static>, args: GetArgs) -> Result<(), Box<dyn Error>> {
     let info = device.get_info()?;
-    println!("Version: {}", info.proto_version);
+    let schema = device.get_schema(&info)?;
+    let (entry, field) = match args.entry {
+        'c' => (schema.props.get(args.index as usize), LayoutEntry::Prop(args.index)),
+        'i' => (schema.infos.get(args.index as usize), LayoutEntry::Info(args.index)),
+        other => Err(format!("Not a field type: {}", other))?,
+    };
+    let entry = entry.ok_or("No such field")?;
+    if args.default {
+        let value = device.read_default(&field)?;
+        print_log(device.get_log());
+        if entry.is_table() {
+            return print_table(entry, &[]);
+        }
+        return print_value(entry, &value);
+    }
+    if entry.is_table() {
+        let rows = device.read_table(args.index)?;
+        print_log(device.get_log());
+        return print_table(entry, &rows);
+    }
+    let value = device.read_field(&field)?;
+    print_log(device.get_log());
 
-    println!("Sections: {}", info.section_count);
-    println!("Prop fields: {}", info.prop_count);
-    println!("Info fields: {}", info.info_count);
-    println!("Actions: {}", info.action_count);
+    print_value(entry, &value)
+}
+
+fn print_table(entry: &Entry, rows: &[Vec<u8>]) -> Result<(), Box<dyn Error>> {
+    println!("{} ({}/{})", entry.name.bright_yellow(), rows.len(), entry.format.max_items);
+    let names: Vec<_> = entry.columns.iter().map(|column| column.name.as_str()).collect();
+    println!("     {}", names.join(" | ").bright_black());
+    for (i, row) in rows.iter().enumerate() {
+        let cells = record_cells(row, entry.columns.len()).into_iter().zip(&entry.columns)
+            .map(|(cell, column)| format_item(column, cell))
+            .collect::<Result<Vec<_>, _>>()?;
+        println!("  {} {}", format!("{}.", i).bright_black(), cells.join(" | "));
+    }
     Ok(())
 }
 
-fn run_sections(mut device: Box<dyn ElytraDevice + 'static>) -> Result<(), Box<dyn Error>> {
+fn print_value(entry: &Entry, value: &[u8]) -> Result<(), Box<dyn Error>> {
+    if !entry.is_list() {
+        print!("{}: {}", entry.name.bright_yellow(), format_value(entry, value)?);
+        print_swatch(entry, value);
+        return Ok(());
+    }
+    let items = list_items(value);
+    println!("{} ({}/{})", entry.name.bright_yellow(), items.len(), entry.format.max_items);
+    for (i, item) in items.into_iter().enumerate() {
+        print!("  {} {}", format!("{}.", i).bright_black(), format_item(entry, item)?);
+        print_swatch(entry, item);
+    }
+    Ok(())
+}
+
+fn print_swatch(entry: &Entry, value: &[u8]) {
+    match color_value(entry, value) {
+        Some(color) => println!(" {}", "██".truecolor(color.r, color.g, color.b)),
+        None => println!(),
+    }
+}
+
+fn run_set(mut device: Box<dyn ElytraDevice + 'static>, args: SetArgs) -> Result<(), Box<dyn Error>> {
     let info = device.get_info()?;
-    print_log(device.get_log());
-    println!("Querying {} section(s)...", info.section_count.bright_blue());
+    let schema = device.get_schema(&info)?;
+    let assignments = parse_assignments(&args.args)?;
+    let props = assignments.iter()
+        .map(|(index, _)| schema.props.get(*index as usize).ok_or_else(|| format!("No such prop: {}", index)))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Values as stored by the device, for the writes it clamped
+    let mut clamped = Vec::new();
+    let mut write_all = |device: &mut (dyn ElytraDevice + 'static)| {
+        assignments.iter().zip(&props).try_for_each(|((index, values), entry)| {
+            clamped.push(write_values(device, entry, *index, values)?);
+            Ok(())
+        })
+    };
+    match (&assignments[..], args.confirm_within) {
+        ([_], None) => write_all(device.as_mut())?,
+        (_, None) => device.transaction(write_all)?,
+        (_, Some(seconds)) => device.pending_transaction(seconds, write_all)?,
+    }
 
-    // println!("")
-    let mut sections = Vec::with_capacity(info.section_count as usize);
+    let values = assignments.iter().map(|(index, _)| device.read_prop(*index)).collect::<Result<Vec<_>, _>>();
+    let values = match (values, args.confirm_within) {
+        (Err(e), Some(seconds)) => Err(format!("Lost the device, it reverts the change within {} seconds: {}", seconds, e))?,
+        (values, _) => values?,
+    };
+    if args.confirm_within.is_some() {
+        // Reading the values back showed the device is still reachable
+        device.confirm_transaction()?;
+    }
+    print_log(device.get_log());
 
-    for i in 0..info.section_count {
-        println!(" ~ Querying section #{} basic", i.bright_blue());
-        let mut section_entry = device.get_entry(b's', i)?;
-        print_log(device.get_log());
-        println!(" ~ Querying section #{} layout", i.bright_blue());
-        let layout_ids = device.get_layout(i)?;
-        print_log(device.get_log());
-        println!(" ~ Querying section extras...");
-        if section_entry.flags.contains(ExtraFlags::HasHelp) {
-            section_entry.help = Some(device.get_extra(b's', i, b'h')?);
-            print_log(device.get_log());
+    for (entry, stored) in props.iter().zip(clamped) {
+        if let Some(stored) = stored {
+            eprintln!("{} {}: value clamped to {}", "warning:".bright_yellow(), entry.name, format_value(entry, &stored)?);
         }
-        if section_entry.flags.contains(ExtraFlags::HasIcon) {
-            section_entry.icon = Some(device.get_extra(b's', i, b'i')?);
-            print_log(device.get_log());
+    }
+    for (entry, value) in props.into_iter().zip(values) {
+        print_value(entry, &value)?;
+    }
+    Ok(())
+}
+
+/// Index of a prop and the values to write to it.
+type Assignment = (u8, Vec<String>);
+
+/// Splits the arguments of `set` into the props to write and their values, either an
+/// index followed by values or `INDEX=VALUE` pairs.
+fn parse_assignments(args: &[String]) -> Result<Vec<Assignment>, Box<dyn Error>> {
+    let parse_index = |index: &str| index.parse::<u8>().map_err(|_| format!("Invalid prop index: {}", index));
+    if !args[0].contains('=') {
+        if args.len() < 2 {
+            Err("Missing value")?
         }
+        return Ok(vec![(parse_index(&args[0])?, args[1..].to_vec())]);
+    }
+    args.iter().map(|arg| {
+        let (index, value) = arg.split_once('=').ok_or_else(|| format!("Expected INDEX=VALUE, got {}", arg))?;
+        Ok((parse_index(index)?, vec![value.to_owned()]))
+    }).collect()
+}
 
-        println!(" ~ Querying {} fields(s)...", layout_ids.len().bright_blue());
-        let mut layout: Vec<(LayoutEntry, Entry)>  = layout_ids.into_iter().map(|layout| {
-            match layout {
-                LayoutEntry::Prop(li) => device.get_entry(b'c', li),
-                LayoutEntry::Info(li) => device.get_entry(b'i', li),
-            }.map(|entry| (layout, entry) ).unwrap()
-        }).collect();
-        print_log(device.get_log());
-        
-        println!(" ~ Querying field extras...");
-        for (le, entry) in layout.iter_mut() {
-            let (vt, index) = match le {
-                LayoutEntry::Prop(li) => (b'c', li),
-                LayoutEntry::Info(li) => (b'i', li),
-            };
-    
-            if entry.flags.contains(ExtraFlags::HasHelp) {
-                entry.help = Some(device.get_extra(vt, *index, b'h')?)
+fn write_values(device: &mut (dyn ElytraDevice + 'static), entry: &Entry, index: u8, values: &[String]) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    if entry.is_table() {
+        Err(format!("{} is a table, change its rows with `row`", entry.name))?
+    }
+    match values {
+        [value] if entry.is_secret() => device.write_secret(index, &parse_value(entry, value)?),
+        [value] if !entry.is_list() => device.write_prop(index, &parse_value(entry, value)?),
+        _ if !entry.is_list() => Err(format!("{} takes a single value", entry.name))?,
+        [empty] if empty == "-" => device.write_list(index, &[]),
+        items => {
+            let items = items.iter().map(|item| parse_value(entry, item)).collect::<Result<Vec<_>, _>>()?;
+            device.write_list(index, &items)
+        },
+    }
+}
+
+fn run_list(mut device: Box<dyn ElytraDevice + 'static>, args: ListArgs) -> Result<(), Box<dyn Error>> {
+    let info = device.get_info()?;
+    let schema = device.get_schema(&info)?;
+    let entry = schema.props.get(args.index as usize).ok_or("No such prop")?;
+    if !entry.is_list() {
+        Err(format!("{} is not a list", entry.name))?
+    }
+    match args.edit {
+        ListEdit::Add { value } => device.edit_list(args.index, ListEditKey::Append, 0, &parse_value(entry, &value)?)?,
+        ListEdit::Replace { at, value } => device.edit_list(args.index, ListEditKey::Replace, at, &parse_value(entry, &value)?)?,
+        ListEdit::Remove { at } => device.edit_list(args.index, ListEditKey::Remove, at, &[])?,
+        ListEdit::Clear => device.edit_list(args.index, ListEditKey::Clear, 0, &[])?,
+    }
+
+    let value = device.read_prop(args.index)?;
+    print_log(device.get_log());
+
+    print_value(entry, &value)
+}
+
+fn run_row(mut device: Box<dyn ElytraDevice + 'static>, args: RowArgs) -> Result<(), Box<dyn Error>> {
+    let info = device.get_info()?;
+    let schema = device.get_schema(&info)?;
+    let entry = schema.props.get(args.index as usize).ok_or("No such prop")?;
+    if !entry.is_table() {
+        Err(format!("{} is not a table", entry.name))?
+    }
+    match args.edit {
+        RowEdit::Add { values } => {
+            if values.len() > entry.columns.len() {
+                Err(format!("{} has only {} columns", entry.name, entry.columns.len()))?
             }
-            if entry.flags.contains(ExtraFlags::HasIcon) {
-                entry.icon = Some(device.get_extra(vt, *index, b'i')?)
+            let values = values.iter().zip(&entry.columns)
+                .map(|(value, column)| parse_value(column, value))
+                .collect::<Result<Vec<_>, _>>()?;
+            let row = device.read_prop(args.index)?[0];
+            for (column, value) in values.iter().enumerate() {
+                if entry.columns[column].is_secret() {
+                    device.write_secret_cell(args.index, row, column as u8, value)?;
+                } else {
+                    device.write_cell(args.index, row, column as u8, value)?;
+                }
             }
-        }
+        },
+        RowEdit::Set { row, column, value } => {
+            let column = entry.columns.iter().position(|c| c.name.eq_ignore_ascii_case(&column))
+                .or_else(|| column.parse().ok().filter(|column| *column < entry.columns.len()))
+                .ok_or_else(|| format!("No column {} in {}", column, entry.name))?;
+            let rows = device.read_prop(args.index)?[0];
+            if row >= rows {
+                Err(format!("{} has only {} rows, add rows with `row {} add`", entry.name, rows, args.index))?
+            }
+            let value = parse_value(&entry.columns[column], &value)?;
+            if entry.columns[column].is_secret() {
+                device.write_secret_cell(args.index, row, column as u8, &value)?;
+            } else {
+                device.write_cell(args.index, row, column as u8, &value)?;
+            }
+        },
+        RowEdit::Delete { row } => device.delete_row(args.index, row)?,
+    }
+
+    let rows = device.read_table(args.index)?;
+    print_log(device.get_log());
+
+    print_table(entry, &rows)
+}
+
+fn run_clear(mut device: Box<dyn ElytraDevice + 'static>, args: ClearArgs) -> Result<(), Box<dyn Error>> {
+    let info = device.get_info()?;
+    let schema = device.get_schema(&info)?;
+    let entry = schema.props.get(args.index as usize).ok_or("No such prop")?;
+    if !entry.is_secret() {
+        Err(format!("{} is not a secret", entry.name))?
+    }
+    device.clear_secret(args.index)?;
+
+    let value = device.read_prop(args.index)?;
+    print_log(device.get_log());
+
+    print_value(entry, &value)
+}
+
+fn run_reset(mut device: Box<dyn ElytraDevice + 'static>, args: ResetArgs) -> Result<(), Box<dyn Error>> {
+    let Some(index) = args.index else {
+        device.factory_reset()?;
         print_log(device.get_log());
+        println!("Reset every prop to its default");
+        return Ok(());
+    };
+    let info = device.get_info()?;
+    let schema = device.get_schema(&info)?;
+    let entry = schema.props.get(index as usize).ok_or("No such prop")?;
+    device.reset_prop(index)?;
 
-        let section = Section {
-            entry: section_entry,
-            layout,
-        };
-        sections.push(section);
+    if entry.is_table() {
+        let rows = device.read_table(index)?;
+        print_log(device.get_log());
+        return print_table(entry, &rows);
     }
+    let value = device.read_prop(index)?;
+    print_log(device.get_log());
+
+    print_value(entry, &value)
+}
+
+fn run_info(mut device: Box<dyn ElytraDevice + 'static>) -> Result<(), Box<dyn Error>> {
+    let info = device.get_info()?;
+    if let Some(identity) = device.get_identity()? {
+        println!("Vendor: {}", identity.vendor);
+        println!("Product: {}", identity.product);
+        println!("Firmware: {}", identity.firmware);
+        if let Some(hardware) = identity.hardware {
+            println!("Hardware: {}", hardware);
+        }
+        if let Some(serial) = identity.serial {
+            println!("Serial: {}", serial);
+        }
+    }
+    println!("Version: {}", info.proto_version);
+    println!("Schema version: {}", info.schema_version);
+    println!("Schema fingerprint: {:08x}", info.fingerprint);
+
+    println!("Sections: {}", info.section_count);
+    println!("Prop fields: {}", info.prop_count);
+    println!("Info fields: {}", info.info_count);
+    println!("Actions: {}", info.action_count);
+    Ok(())
+}
+
+fn run_sections(mut device: Box<dyn ElytraDevice + 'static>) -> Result<(), Box<dyn Error>> {
+    let info = device.get_info()?;
+    print_log(device.get_log());
+    println!("Loading schema of {} section(s)...", info.section_count.bright_blue());
+    let sections = device.get_schema(&info)?.sections();
+    print_log(device.get_log());
 
     println!();
     println!("{}", "Sections:".bright_white());
@@ -200,9 +525,62 @@ fn run_sections(mut device: Box<dyn ElytraDevice + 'static>) -> Result<(), Box<d
 
             // print!("      Flags: ");
     
+            if let Some(id) = entry.id {
+                println!("      Id: {}", format!("{:08x}", id).bright_white());
+            }
             if let Some(icon) = &entry.icon {
                 println!("      Icon: {}", icon.bright_white());
             }
+            if let Some(unit) = &entry.format.unit {
+                println!("      Unit: {}", unit.bright_white());
+            }
+            let bound = |value| format_number(entry, value);
+            let text = ValueType::try_from(entry.variant).is_ok_and(|vt| vt.is_text());
+            if text && entry.format.has_range() {
+                println!("      Length: {}", format!("{}..={} bytes", entry.format.min, entry.format.max).bright_white());
+            } else if entry.format.has_range() {
+                println!("      Range: {}", format!("{}..={}", bound(entry.format.min), bound(entry.format.max)).bright_white());
+            }
+            if entry.format.step > 1 {
+                println!("      Step: {}", format_step(entry, entry.format.step).bright_white());
+            }
+            if entry.format.chars != CharClass::all() {
+                println!("      Characters: {}", format_chars(entry.format.chars).bright_white());
+            }
+            if let Some(pattern) = &entry.pattern {
+                println!("      Pattern: {}", pattern.bright_white());
+            }
+            if entry.is_list() {
+                println!("      Items: {}", format!("up to {}", entry.format.max_items).bright_white());
+            }
+            if !entry.names.is_empty() {
+                println!("      Flags: {}", entry.names.join(", ").bright_white());
+            }
+            if entry.is_table() {
+                let columns: Vec<_> = entry.columns.iter()
+                    .map(|column| format!("{} ({})", column.name, ValueType::try_from(column.variant).map(|vt| vt.to_string()).unwrap_or_default()))
+                    .collect();
+                println!("      Columns: {}", columns.join(", ").bright_white());
+                println!("      Rows: {}", format!("up to {}", entry.format.max_items).bright_white());
+            }
+            let color = [ValueType::Color as u8, ValueType::ColorAlpha as u8].contains(&entry.variant);
+            if color && entry.flags.contains(ExtraFlags::HasOptions) {
+                let (entry_type, index) = match l {
+                    LayoutEntry::Prop(index) => (b'c', *index),
+                    LayoutEntry::Info(index) => (b'i', *index),
+                };
+                let count = u32::from_le_bytes(entry.constraints[..4].try_into()?);
+                print!("      Palette:");
+                for option in 0..count {
+                    let option = device.get_option(entry_type, index, option as u16)?;
+                    match palette_entry(&option) {
+                        Some((name, color)) => print!(" {} {}", "██".truecolor(color.r, color.g, color.b), name.bright_white()),
+                        None => print!(" {}", option.bright_red()),
+                    }
+                }
+                println!();
+                print_log(device.get_log());
+            }
 
             println!();
             
@@ -238,7 +616,7 @@ fn print_log(log: Vec<([u8; 64],[u8; 64])>) {
 fn print_bytes(bytes: &[u8]) {
     for chunk in bytes.chunks(32) {
         let colored_chunk: Vec<_> = chunk.iter().copied().map(|b| {
-            let c = char::try_from(b).unwrap_or('.');
+            let c = char::from(b);
             if c.is_control() {
                 if (1..=9).contains(&b) {
                     (b, char::from_digit(b as u32, 10).unwrap(), AnsiColors::BrightCyan)
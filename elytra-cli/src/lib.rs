@@ -1,11 +1,13 @@
-use std::{error::Error, io::Write};
+use std::{error::Error, fmt, io::Write};
 
 use color_eyre::eyre::{eyre};
-use elytra_conf::{command::CommandKey, config::QueryTargetKey, entry::ExtraFlags};
+use elytra_conf::{command::{CommandError, CommandKey, ListEditKey, TransactionKey, WriteFlags}, config::QueryTargetKey, entry::ExtraFlags, text::CharClass, values::ValueType};
 
 pub mod wasm;
 pub mod tcp;
 pub mod tui;
+pub mod schema;
+pub mod value;
 
 pub trait ElytraDevice: Send {
     fn send_command_raw(&mut self, bytes: [u8; 64]) -> Result<[u8; 64], Box<dyn Error>>;
@@ -18,7 +20,7 @@ pub struct Section {
     pub layout: Vec<(LayoutEntry, Entry)>
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum LayoutEntry {
     Info(u8),
     Prop(u8),
@@ -33,21 +35,240 @@ pub struct Entry {
     pub icon: Option<String>,
     pub help: Option<String>,
     pub entry_type: u8,
+    /// Stable id, only known when the entry was loaded from a schema dump.
+    pub id: Option<u32>,
+    pub format: NumberFormat,
+    /// Names of the bits of flags fields.
+    pub names: Vec<String>,
+    /// Columns of tables, indexed like the cells of a row.
+    pub columns: Vec<Entry>,
+    /// Pattern text values have to match, see [`elytra_conf::text`].
+    pub pattern: Option<String>,
     pub layout: Option<Vec<LayoutEntry>>
 }
 
+impl Entry {
+    /// Whether the field holds a list of values, see [`NumberFormat::max_items`].
+    pub fn is_list(&self) -> bool {
+        self.flags.contains(ExtraFlags::IsMulti)
+    }
+
+    /// Whether the field holds a write-only secret, which is read as whether it is set.
+    pub fn is_secret(&self) -> bool {
+        self.variant == ValueType::Secret as u8
+    }
+
+    /// Whether the field is a table, whose rows are read and written one by one.
+    pub fn is_table(&self) -> bool {
+        self.variant == ValueType::Table as u8
+    }
+}
+
+/// Constraints and display hints of numeric and text values.
+#[derive(Clone)]
+pub struct NumberFormat {
+    /// Decimal places of decimal values.
+    pub scale: u8,
+    pub step: u64,
+    pub radix: u8,
+    pub unsigned: bool,
+    /// Inclusive bounds, as `u64` bit patterns for unsigned values.
+    pub min: i64,
+    pub max: i64,
+    /// Largest number of items of list fields or rows of tables, `0` for single values.
+    pub max_items: u8,
+    /// Characters accepted in text values.
+    pub chars: CharClass,
+    pub unit: Option<String>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self { scale: 0, step: 0, radix: 10, unsigned: false, min: i64::MIN, max: i64::MAX, max_items: 0, chars: CharClass::all(), unit: None }
+    }
+}
+
+impl NumberFormat {
+    /// Length of the encoded format before the unit.
+    const LEN: usize = 29;
+
+    fn parse(bytes: &[u8], unit: String) -> Result<Self, Box<dyn Error>> {
+        let bytes: &[u8; Self::LEN] = bytes.get(..Self::LEN).ok_or_else(|| eyre!("Truncated number format"))?.try_into()?;
+        Ok(Self {
+            scale: bytes[0],
+            radix: bytes[1],
+            unsigned: bytes[2] != 0,
+            step: u64::from_le_bytes(bytes[3..11].try_into()?),
+            min: i64::from_le_bytes(bytes[11..19].try_into()?),
+            max: i64::from_le_bytes(bytes[19..27].try_into()?),
+            max_items: bytes[27],
+            chars: CharClass::from_bits_truncate(bytes[28]),
+            unit: Some(unit).filter(|unit| !unit.is_empty()),
+        })
+    }
+
+    /// Whether the bounds are narrower than the full range of the value.
+    pub fn has_range(&self) -> bool {
+        if self.unsigned {
+            self.min != 0 || self.max != -1
+        } else {
+            self.min != i64::MIN || self.max != i64::MAX
+        }
+    }
+}
+
 pub struct Info {
     pub proto_version: u8,
     pub prop_count: u8,
     pub info_count: u8,
     pub section_count: u8,
     pub action_count: u8,
+    pub schema_version: u16,
+    /// Hash of the schema, `0` for firmware that does not report one.
+    pub fingerprint: u32,
+}
+
+/// Product and firmware reported by the `Identity` command.
+pub struct Identity {
+    pub vendor: String,
+    pub product: String,
+    pub firmware: String,
+    pub hardware: Option<String>,
+    pub serial: Option<String>,
 }
 
+impl Identity {
+    fn parse(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut bytes = bytes.iter().copied();
+        let mut take = |len: u8| -> Result<Vec<u8>, Box<dyn Error>> {
+            let taken: Vec<u8> = bytes.by_ref().take(len as usize).collect();
+            if taken.len() < len as usize {
+                Err(eyre!("Truncated identity"))?
+            }
+            Ok(taken)
+        };
+        let mut text = || -> Result<String, Box<dyn Error>> {
+            let len = take(1)?[0];
+            Ok(String::from_utf8_lossy(&take(len)?).into_owned())
+        };
+        let vendor = text()?;
+        let product = text()?;
+        let firmware = text()?;
+        let hardware = Some(text()?).filter(|hw| !hw.is_empty());
+
+        let [kind, len] = take(2)?[..] else { unreachable!() };
+        let serial = take(len)?;
+        let serial = match kind {
+            0 => None,
+            1 => Some(String::from_utf8_lossy(&serial).into_owned()),
+            _ if serial.len() == 16 => Some(format_uuid(&serial)),
+            _ => Some(serial.iter().map(|b| format!("{:02x}", b)).collect()),
+        };
+        Ok(Identity { vendor, product, firmware, hardware, serial })
+    }
+}
+
+fn format_uuid(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Largest value chunk of a `WritePropAt` command.
+const WRITE_CHUNK_SIZE: usize = 64 - 7;
+/// Largest item of an `EditList` command.
+const LIST_ITEM_SIZE: usize = 64 - 5;
+/// Largest value chunk of a `WriteCell` command.
+const CELL_CHUNK_SIZE: usize = 64 - 7;
+
 fn err_msg(bytes: &[u8]) -> String {
     String::from_utf8_lossy(&bytes[2..]).trim_end_matches('\0').to_owned()
 }
 
+/// Error response sent by the device.
+#[derive(Debug)]
+pub struct DeviceError {
+    pub code: u8,
+    pub message: String,
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Got error response: {} ({:02x?}) ", self.message, self.code)
+    }
+}
+
+impl Error for DeviceError {}
+
+/// Whether `e` is the device rejecting a command it does not know, as older firmware does.
+fn is_invalid_command(e: &(dyn Error + 'static)) -> bool {
+    e.downcast_ref::<DeviceError>().is_some_and(|e| e.code == CommandError::InvalidCommand as u8)
+}
+
+fn check_status(res: &[u8; 64]) -> Result<(), Box<dyn Error>> {
+    match res[0] {
+        1 | 2 => Ok(()),
+        _ => Err(DeviceError { code: res[1], message: err_msg(res) })?
+    }
+}
+
+/// Splits `value` into chunks of at most `size` bytes, each with its offset. UTF-8 sequences
+/// are kept in one chunk so every chunk is valid text, an empty value is one empty chunk.
+fn utf8_chunks(value: &[u8], size: usize) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let mut end = value.len().min(offset + size);
+        while end < value.len() && end > offset + 1 && value[end] & 0xc0 == 0x80 {
+            end -= 1;
+        }
+        let chunk = (offset, &value[offset..end]);
+        offset = end;
+        done = offset >= value.len();
+        Some(chunk)
+    })
+}
+
+fn parse_layout(bytes: &[u8]) -> Result<Vec<LayoutEntry>, Box<dyn Error>> {
+    let mut entries = Vec::new();
+    for field in bytes.chunks_exact(2) {
+        entries.push(match field[0] {
+            0 => break,
+            b'c' => LayoutEntry::Prop(field[1]),
+            b'i' => LayoutEntry::Info(field[1]),
+            ft => Err(eyre!("Unknown field type: {:02x}", ft))?
+        });
+    }
+    Ok(entries)
+}
+
+/// Decodes the response to a `Field` query.
+fn parse_entry(entry_type: u8, res: &[u8; 64]) -> Result<Entry, Box<dyn Error>> {
+    let flags = ExtraFlags::from_bits_truncate(res[1]);
+    let variant = res[2];
+    let mut constraints = [0u8; 8];
+    constraints.copy_from_slice(&res[3..11]);
+    let name = str::from_utf8(&res[11..])?.trim_end_matches('\0').to_owned();
+
+    Ok(Entry {
+        name,
+        flags,
+        variant,
+        constraints,
+        entry_type,
+        id: None,
+        format: NumberFormat::default(),
+        help: None,
+        icon: None,
+        names: Vec::new(),
+        columns: Vec::new(),
+        pattern: None,
+        layout: None,
+    })
+}
+
 impl dyn ElytraDevice {
     pub fn get_entry(&mut self, entry_type: u8, index: u8) -> Result<Entry, Box<dyn Error>> {
         let res = self.send_command( &[
@@ -55,23 +276,15 @@ impl dyn ElytraDevice {
             entry_type, index, 
             QueryTargetKey::Field as u8
         ])?;
-        if res[0] != 1 { return Err(eyre!("Got error response: {} ({:02x?}) ", err_msg(&res), &res[1]))? }
-        let flags = ExtraFlags::from_bits_truncate(res[1]);
-        let variant = res[2];
-        let mut constraints = [0u8; 8];
-        constraints.copy_from_slice(&res[3..11]);
-        let name = str::from_utf8(&res[11..])?.trim_end_matches('\0').to_owned();
-
-        Ok(Entry {
-            name,
-            flags,
-            variant,
-            constraints,
-            entry_type,
-            help: None,
-            icon: None,
-            layout: None,
-        })
+        check_status(&res)?;
+        parse_entry(entry_type, &res)
+    }
+
+    /// Queries a column of a table prop.
+    pub fn get_column(&mut self, index: u8, column: u8) -> Result<Entry, Box<dyn Error>> {
+        let res = self.send_command(&[b'q', b'c', index, QueryTargetKey::Column as u8, column])?;
+        check_status(&res)?;
+        parse_entry(b'c', &res)
     }
 
     pub fn get_entries(&mut self, entry_type: u8, count: usize) -> Result<Vec<Entry>, Box<dyn Error>> {
@@ -90,48 +303,275 @@ impl dyn ElytraDevice {
         let prop_count = res.next().unwrap();
         let info_count = res.next().unwrap();
         let action_count = res.next().unwrap();
+        let schema_version = u16::from_le_bytes([res.next().unwrap(), res.next().unwrap()]);
+        let fingerprint = u32::from_le_bytes([res.next().unwrap(), res.next().unwrap(), res.next().unwrap(), res.next().unwrap()]);
         Ok(Info {
             proto_version,
             prop_count,
             info_count,
             section_count,
             action_count,
+            schema_version,
+            fingerprint,
         })
     }
 
+    /// Queries the identity of the device, `None` for firmware without the `Identity` command.
+    pub fn get_identity(&mut self) -> Result<Option<Identity>, Box<dyn Error>> {
+        match self.send_paged(&[CommandKey::Identity as u8]) {
+            Ok(bytes) => Identity::parse(&bytes).map(Some),
+            Err(e) if is_invalid_command(e.as_ref()) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     pub fn get_extra(&mut self, vt: u8, index: u8, q: u8) -> Result<String, Box<dyn Error>>  {
-        let res = self.send_command(&[b'q', vt, index, q])?;
-        Ok(String::from_utf8_lossy(&res[1..]).trim_end_matches('\0').to_string())
+        let res = self.send_paged(&[b'q', vt, index, q])?;
+        Ok(String::from_utf8_lossy(&res).trim_end_matches('\0').to_string())
+    }
+
+    /// Queries an option of an entry with options or suggestions.
+    pub fn get_option(&mut self, entry_type: u8, index: u8, option: u16) -> Result<String, Box<dyn Error>> {
+        let [lo, hi] = option.to_le_bytes();
+        let res = self.send_paged(&[b'q', entry_type, index, QueryTargetKey::Option as u8, lo, hi])?;
+        Ok(String::from_utf8_lossy(&res).trim_end_matches('\0').to_string())
+    }
+
+    /// Queries the number format, bounds and unit of a numeric entry, or the length and
+    /// accepted characters of a text entry.
+    pub fn get_format(&mut self, entry_type: u8, index: u8) -> Result<NumberFormat, Box<dyn Error>> {
+        let res = self.send_paged(&[b'q', entry_type, index, QueryTargetKey::Format as u8])?;
+        let unit = String::from_utf8_lossy(res.get(NumberFormat::LEN..).unwrap_or_default()).trim_end_matches('\0').to_owned();
+        NumberFormat::parse(&res, unit)
+    }
+
+    /// Queries the stable id of an entry, which unlike `index` survives firmware updates.
+    pub fn get_id(&mut self, entry_type: u8, index: u8) -> Result<u32, Box<dyn Error>> {
+        let res = self.send_command(&[b'q', entry_type, index, QueryTargetKey::Id as u8])?;
+        check_status(&res)?;
+        Ok(u32::from_le_bytes(res[1..5].try_into()?))
     }
 
     pub fn get_layout(&mut self, index: u8) -> Result<Vec<LayoutEntry>, Box<dyn Error>>  {
-        let mut res = self.send_command(&[b'q', b's', index, b'l'])?.into_iter();
-        assert_eq!(1, res.next().unwrap());
-        let mut entries = Vec::new();
+        let res = self.send_paged(&[b'q', b's', index, b'l'])?;
+        parse_layout(&res)
+    }
+
+    /// Reads the raw value of a prop, padded with zeroes.
+    pub fn read_prop(&mut self, index: u8) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.send_paged(&[CommandKey::ReadProp as u8, index])
+    }
+
+    /// Reads the raw value of an info field, padded with zeroes.
+    pub fn read_info(&mut self, index: u8) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.send_paged(&[CommandKey::ReadInfo as u8, index])
+    }
+
+    /// Reads the raw value of a field in a section layout.
+    pub fn read_field(&mut self, field: &LayoutEntry) -> Result<Vec<u8>, Box<dyn Error>> {
+        match field {
+            LayoutEntry::Prop(index) => self.read_prop(*index),
+            LayoutEntry::Info(index) => self.read_info(*index),
+        }
+    }
+
+    /// Writes the raw value of a prop, split over several `WritePropAt` commands if it
+    /// does not fit a single `WriteProp`. Returns the value as stored, padded with zeroes,
+    /// when the device clamped it to the prop.
+    pub fn write_prop(&mut self, index: u8, value: &[u8]) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        self.write_value(index, value, false)
+    }
+
+    /// Writes a secret prop like [`write_prop`](Self::write_prop), the value is left out
+    /// of the log.
+    pub fn write_secret(&mut self, index: u8, value: &[u8]) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        self.write_value(index, value, true)
+    }
+
+    /// Empties a secret prop, secrets with a minimum length can't be written empty.
+    pub fn clear_secret(&mut self, index: u8) -> Result<(), Box<dyn Error>> {
+        let res = self.send_command(&[CommandKey::ClearSecret as u8, index])?;
+        check_status(&res)
+    }
+
+    /// Reads the raw default value of a prop or info field, padded with zeroes.
+    pub fn read_default(&mut self, field: &LayoutEntry) -> Result<Vec<u8>, Box<dyn Error>> {
+        match field {
+            LayoutEntry::Prop(index) => self.send_paged(&[b'q', b'c', *index, QueryTargetKey::Default as u8]),
+            LayoutEntry::Info(index) => self.send_paged(&[b'q', b'i', *index, QueryTargetKey::Default as u8]),
+        }
+    }
+
+    /// Writes the default to a prop, a table loses all of its rows.
+    pub fn reset_prop(&mut self, index: u8) -> Result<(), Box<dyn Error>> {
+        let res = self.send_command(&[CommandKey::ResetProp as u8, index])?;
+        check_status(&res)
+    }
+
+    /// Writes the defaults to every prop of the device.
+    pub fn factory_reset(&mut self) -> Result<(), Box<dyn Error>> {
+        let res = self.send_command(&[CommandKey::FactoryReset as u8])?;
+        check_status(&res)
+    }
+
+    /// Runs `writes` in a transaction, so the device applies all of the prop writes or
+    /// none of them. The transaction is aborted if a write fails.
+    pub fn transaction(&mut self, writes: impl FnOnce(&mut Self) -> Result<(), Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
+        self.stage(writes)?;
+        self.send_transaction(&[TransactionKey::Commit as u8])
+    }
+
+    /// Runs `writes` in a transaction like [`transaction`](Self::transaction), the device
+    /// reverts them unless [`confirm_transaction`](Self::confirm_transaction) follows
+    /// within `seconds`.
+    pub fn pending_transaction(&mut self, seconds: u16, writes: impl FnOnce(&mut Self) -> Result<(), Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
+        self.stage(writes)?;
+        let [lo, hi] = seconds.to_le_bytes();
+        self.send_transaction(&[TransactionKey::CommitPending as u8, lo, hi])
+    }
+
+    /// Keeps the writes of a [`pending_transaction`](Self::pending_transaction).
+    pub fn confirm_transaction(&mut self) -> Result<(), Box<dyn Error>> {
+        self.send_transaction(&[TransactionKey::Confirm as u8])
+    }
+
+    fn stage(&mut self, writes: impl FnOnce(&mut Self) -> Result<(), Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
+        self.send_transaction(&[TransactionKey::Begin as u8])?;
+        writes(self).inspect_err(|_| {
+            let _ = self.send_transaction(&[TransactionKey::Abort as u8]);
+        })
+    }
+
+    fn send_transaction(&mut self, step: &[u8]) -> Result<(), Box<dyn Error>> {
+        let res = self.send_command(&[&[CommandKey::Transaction as u8], step].concat())?;
+        check_status(&res)
+    }
+
+    fn write_value(&mut self, index: u8, value: &[u8], secret: bool) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let redact = |header: usize| if secret { header } else { 64 };
+        if value.len() <= 62 {
+            let res = self.send_redacted(&[&[CommandKey::WriteProp as u8, index], value].concat(), redact(2))?;
+            check_status(&res)?;
+            return self.clamped_value(index, &res);
+        }
+
+        // The device answers the last chunk like a `WriteProp`
+        let total = u16::try_from(value.len())?.to_le_bytes();
+        let mut res = [0u8; 64];
+        for (offset, chunk) in utf8_chunks(value, WRITE_CHUNK_SIZE) {
+            let header = [CommandKey::WritePropAt as u8, index, offset as u8, (offset >> 8) as u8, total[0], total[1], chunk.len() as u8];
+            res = self.send_redacted(&[&header, chunk].concat(), redact(header.len()))?;
+            check_status(&res)?;
+        }
+        self.clamped_value(index, &res)
+    }
+
+    /// The value as stored from the response to a write if the device clamped it, read
+    /// again when it did not fit the response.
+    fn clamped_value(&mut self, index: u8, res: &[u8; 64]) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        // Older devices answer with a bare OK
+        let flags = WriteFlags::from_bits_truncate(res[1]);
+        if !flags.contains(WriteFlags::Clamped) {
+            return Ok(None);
+        }
+        match flags.contains(WriteFlags::Cut) {
+            true => self.read_prop(index).map(Some),
+            false => Ok(Some(res[2..].to_vec())),
+        }
+    }
+
+    /// Replaces the items of a list prop. The items that fit are sent in a single `WriteProp`
+    /// and the rest are appended one by one, clearing the list first would bring back
+    /// its default. Returns the items of the `WriteProp` as stored when they were clamped.
+    pub fn write_list(&mut self, index: u8, items: &[Vec<u8>]) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        let mut len = 0;
+        let fitting = items.iter().take_while(|item| {
+            len += 1 + item.len();
+            len <= 62
+        }).count();
+        let clamped = self.write_prop(index, &value::encode_list(&items[..fitting]))?;
+        for item in &items[fitting..] {
+            self.edit_list(index, ListEditKey::Append, 0, item)?;
+        }
+        Ok(clamped)
+    }
+
+    /// Appends, replaces or removes the item `at` of a list prop.
+    pub fn edit_list(&mut self, index: u8, edit: ListEditKey, at: u8, item: &[u8]) -> Result<(), Box<dyn Error>> {
+        if item.len() > LIST_ITEM_SIZE {
+            Err(eyre!("List items are limited to {} bytes", LIST_ITEM_SIZE))?
+        }
+        let header = [CommandKey::EditList as u8, index, edit as u8, at, item.len() as u8];
+        let res = self.send_command(&[&header, item].concat())?;
+        check_status(&res)
+    }
+
+    /// Reads the raw cells of every row of a table prop.
+    pub fn read_table(&mut self, index: u8) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        let rows = self.read_prop(index)?[0];
+        (0..rows).map(|row| self.read_row(index, row)).collect()
+    }
+
+    /// Reads the raw cells of a row of a table prop, each with a 1 byte length.
+    pub fn read_row(&mut self, index: u8, row: u8) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.send_paged(&[CommandKey::ReadRow as u8, index, row])
+    }
+
+    /// Writes a cell of a table row, split over several commands if it does not fit a
+    /// single one. Writing to the row after the last one appends a row.
+    pub fn write_cell(&mut self, index: u8, row: u8, column: u8, value: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.write_cell_value(index, row, column, value, false)
+    }
+
+    /// Writes a cell of a secret column like [`write_cell`](Self::write_cell), the value
+    /// is left out of the log.
+    pub fn write_secret_cell(&mut self, index: u8, row: u8, column: u8, value: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.write_cell_value(index, row, column, value, true)
+    }
+
+    fn write_cell_value(&mut self, index: u8, row: u8, column: u8, value: &[u8], secret: bool) -> Result<(), Box<dyn Error>> {
+        for (offset, chunk) in utf8_chunks(value, CELL_CHUNK_SIZE) {
+            let header = [CommandKey::WriteCell as u8, index, row, column, offset as u8, (offset >> 8) as u8, chunk.len() as u8];
+            let res = self.send_redacted(&[&header, chunk].concat(), if secret { header.len() } else { 64 })?;
+            check_status(&res)?;
+        }
+        Ok(())
+    }
+
+    /// Removes a row of a table prop, the rows after it move up.
+    pub fn delete_row(&mut self, index: u8, row: u8) -> Result<(), Box<dyn Error>> {
+        let res = self.send_command(&[CommandKey::DeleteRow as u8, index, row])?;
+        check_status(&res)
+    }
+
+    /// Sends a command that may have a response spanning several messages, requesting
+    /// pages until the device reports there is no more data. The page offset is appended
+    /// to `bytes`.
+    pub fn send_paged(&mut self, bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut payload = Vec::new();
         loop {
-            let Some(ft) = res.next() else {
-                break;
-            };
-            let Some(ix) = res.next() else {
-                break;
-            };
-            if ft == 0 {
-                break;
+            let offset = u16::try_from(payload.len())?.to_le_bytes();
+            let res = self.send_command(&[bytes, &offset].concat())?;
+            check_status(&res)?;
+            payload.extend_from_slice(&res[1..]);
+            if res[0] != 2 {
+                return Ok(payload);
             }
-            entries.push(match ft {
-                b'c' => LayoutEntry::Prop(ix),
-                b'i' => LayoutEntry::Info(ix),
-                ft => panic!("Unknown field type: {:02x}", ft)
-            });
         }
-        Ok(entries)
     }
 
     pub fn send_command(&mut self, bytes: &[u8]) -> Result<[u8; 64], Box<dyn Error>> {
+        self.send_redacted(bytes, 64)
+    }
+
+    /// Sends a command like [`send_command`](Self::send_command), the bytes from
+    /// `redact_from` on are logged as `*` so secrets don't end up in logs.
+    fn send_redacted(&mut self, bytes: &[u8], redact_from: usize) -> Result<[u8; 64], Box<dyn Error>> {
         let mut out_bytes= [0u8; 64];
         let _ = out_bytes.as_mut_slice().write(bytes)?;
 
         let in_bytes = self.send_command_raw(out_bytes)?;
+        out_bytes[redact_from..].fill(b'*');
         self.log_chat(out_bytes, in_bytes);
 
         Ok(in_bytes)
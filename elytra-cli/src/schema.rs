@@ -0,0 +1,250 @@
+use std::{env, error::Error, fs, path::PathBuf};
+
+use color_eyre::eyre::eyre;
+use elytra_conf::{command::CommandKey, config::{DUMP_COLUMN_TAG, DUMP_LAYOUT_TAG, DUMP_NAMES_TAG, EntryType, QueryTargetKey}, entry::ExtraFlags, values::ValueType};
+
+use crate::{ElytraDevice, Entry, Info, LayoutEntry, NumberFormat, Section, is_invalid_command, parse_layout};
+
+/// Complete description of a device, indexed like the entries on the device.
+pub struct Schema {
+    pub sections: Vec<Entry>,
+    pub props: Vec<Entry>,
+    pub infos: Vec<Entry>,
+    pub actions: Vec<Entry>,
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+    let (head, tail) = bytes.split_at_checked(len).ok_or_else(|| eyre!("Truncated schema entry"))?;
+    *bytes = tail;
+    Ok(head)
+}
+
+fn take_string(bytes: &mut &[u8], len: usize) -> Result<String, Box<dyn Error>> {
+    Ok(String::from_utf8_lossy(take(bytes, len)?).into_owned())
+}
+
+fn decode_entry(entry_type: u8, mut body: &[u8]) -> Result<(u8, Entry), Box<dyn Error>> {
+    let body = &mut body;
+    let index = take(body, 1)?[0];
+    let id = u32::from_le_bytes(take(body, 4)?.try_into()?);
+    let flags = ExtraFlags::from_bits_truncate(take(body, 1)?[0]);
+    let variant = take(body, 1)?[0];
+    let constraints = take(body, 8)?.try_into()?;
+    let name_len = take(body, 1)?[0] as usize;
+    let name = take_string(body, name_len)?;
+    let help_len = u16::from_le_bytes(take(body, 2)?.try_into()?) as usize;
+    let help = take_string(body, help_len)?;
+    let icon_len = take(body, 1)?[0] as usize;
+    let icon = take_string(body, icon_len)?;
+    let format = take(body, NumberFormat::LEN)?;
+    let unit_len = take(body, 1)?[0] as usize;
+    let unit = take_string(body, unit_len)?;
+    let format = NumberFormat::parse(format, unit)?;
+    let pattern_len = take(body, 1)?[0] as usize;
+    let pattern = take_string(body, pattern_len)?;
+
+    Ok((index, Entry {
+        name,
+        flags,
+        variant,
+        constraints,
+        help: flags.contains(ExtraFlags::HasHelp).then_some(help),
+        icon: flags.contains(ExtraFlags::HasIcon).then_some(icon),
+        entry_type,
+        id: Some(id),
+        format,
+        names: Vec::new(),
+        columns: Vec::new(),
+        pattern: flags.contains(ExtraFlags::HasPattern).then_some(pattern),
+        layout: None,
+    }))
+}
+
+impl Schema {
+    /// Decodes the frames produced by the `Dump` command.
+    pub fn decode(mut bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut schema = Schema {
+            sections: Vec::new(),
+            props: Vec::new(),
+            infos: Vec::new(),
+            actions: Vec::new(),
+        };
+
+        while let [tag, len_lo, len_hi, tail @ ..] = bytes {
+            if *tag == 0 {
+                break;
+            }
+            let len = u16::from_le_bytes([*len_lo, *len_hi]) as usize;
+            let body = tail.get(..len).ok_or_else(|| eyre!("Truncated schema frame"))?;
+            bytes = &tail[len..];
+
+            if *tag == DUMP_LAYOUT_TAG {
+                let (index, fields) = body.split_first().ok_or_else(|| eyre!("Empty layout frame"))?;
+                let section = schema.sections.get_mut(*index as usize)
+                    .ok_or_else(|| eyre!("Layout for unknown section {}", index))?;
+                section.layout = Some(parse_layout(fields)?);
+                continue;
+            }
+
+            if *tag == DUMP_NAMES_TAG {
+                let [entry_type, index, names @ ..] = body else {
+                    Err(eyre!("Truncated names frame"))?
+                };
+                let entry = match EntryType::try_from(*entry_type) {
+                    Ok(EntryType::Prop) => schema.props.get_mut(*index as usize),
+                    Ok(EntryType::Info) => schema.infos.get_mut(*index as usize),
+                    _ => None,
+                }.ok_or_else(|| eyre!("Names for unknown entry {}", index))?;
+                let mut names = names;
+                while let [len, ..] = names {
+                    names = &names[1..];
+                    entry.names.push(take_string(&mut names, *len as usize)?);
+                }
+                continue;
+            }
+
+            if *tag == DUMP_COLUMN_TAG {
+                let (index, column) = body.split_first().ok_or_else(|| eyre!("Empty column frame"))?;
+                let table = schema.props.get_mut(*index as usize)
+                    .ok_or_else(|| eyre!("Column for unknown table {}", index))?;
+                let (_, column) = decode_entry(EntryType::Prop as u8, column)?;
+                table.columns.push(column);
+                continue;
+            }
+
+            let entries = match EntryType::try_from(*tag) {
+                Ok(EntryType::Section) => &mut schema.sections,
+                Ok(EntryType::Prop) => &mut schema.props,
+                Ok(EntryType::Info) => &mut schema.infos,
+                Ok(EntryType::Action) => &mut schema.actions,
+                Err(_) => Err(eyre!("Unknown schema frame: {:02x}", tag))?,
+            };
+            let (index, entry) = decode_entry(*tag, body)?;
+            if index as usize != entries.len() {
+                Err(eyre!("Schema entry {} out of order", index))?
+            }
+            entries.push(entry);
+        }
+        Ok(schema)
+    }
+
+    /// Resolves the layout of every section to the entries it contains.
+    pub fn sections(&self) -> Vec<Section> {
+        self.sections.iter().map(|section_entry| {
+            let layout = section_entry.layout.iter().flatten().filter_map(|le| {
+                match le {
+                    LayoutEntry::Prop(ci) => self.props.get(*ci as usize),
+                    LayoutEntry::Info(ii) => self.infos.get(*ii as usize),
+                }.map(|entry| (le.clone(), entry.clone()))
+            }).collect();
+
+            Section { entry: section_entry.clone(), layout }
+        }).collect()
+    }
+
+    /// Copy of a command with the value of writes to secret props and columns replaced
+    /// by `*`, for logging commands sent by others.
+    pub fn redact_command(&self, mut bytes: [u8; 64]) -> [u8; 64] {
+        let entry = self.props.get(bytes[1] as usize);
+        let (header, secret) = match CommandKey::try_from(bytes[0]) {
+            Ok(CommandKey::WriteProp) => (2, entry.is_some_and(|e| e.is_secret())),
+            Ok(CommandKey::WritePropAt) => (7, entry.is_some_and(|e| e.is_secret())),
+            Ok(CommandKey::WriteCell) => (7, entry.and_then(|e| e.columns.get(bytes[3] as usize)).is_some_and(|c| c.is_secret())),
+            _ => (0, false),
+        };
+        if secret {
+            bytes[header..].fill(b'*');
+        }
+        bytes
+    }
+}
+
+/// Location of the cached schema dump of devices with the given fingerprint, in
+/// `$XDG_CACHE_HOME/elytra` or `~/.cache/elytra`.
+fn cache_path(fingerprint: u32) -> Option<PathBuf> {
+    let cache_dir = env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache_dir.join("elytra").join(format!("{:08x}.schema", fingerprint)))
+}
+
+impl dyn ElytraDevice {
+    /// Loads the complete schema, from the cache if a device with the same fingerprint was
+    /// seen before, otherwise with the `Dump` command. Falls back to querying every entry on
+    /// firmware that does not support it.
+    pub fn get_schema(&mut self, info: &Info) -> Result<Schema, Box<dyn Error>> {
+        let cache = (info.fingerprint != 0).then(|| cache_path(info.fingerprint)).flatten();
+        if let Some(schema) = cache.as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| Schema::decode(&bytes).ok()) {
+            return Ok(schema);
+        }
+
+        match self.send_paged(&[CommandKey::Dump as u8]) {
+            Ok(bytes) => {
+                let schema = Schema::decode(&bytes)?;
+                if let Some(path) = cache {
+                    // The cache only saves round trips, failing to write it is not an error
+                    let _ = path.parent().map_or(Ok(()), fs::create_dir_all)
+                        .and_then(|_| fs::write(&path, &bytes));
+                }
+                Ok(schema)
+            },
+            Err(e) if is_invalid_command(e.as_ref()) => {
+                self.query_schema(info)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn dump_schema(&mut self) -> Result<Schema, Box<dyn Error>> {
+        let bytes = self.send_paged(&[CommandKey::Dump as u8])?;
+        Schema::decode(&bytes)
+    }
+
+    /// Loads the schema with one query per entry, help text, icon and layout.
+    pub fn query_schema(&mut self, info: &Info) -> Result<Schema, Box<dyn Error>> {
+        let mut sections = self.query_entries(b's', info.section_count)?;
+        for (index, section) in sections.iter_mut().enumerate() {
+            section.layout = Some(self.get_layout(index as u8)?);
+        }
+        Ok(Schema {
+            sections,
+            props: self.query_entries(b'c', info.prop_count)?,
+            infos: self.query_entries(b'i', info.info_count)?,
+            actions: self.query_entries(b'a', info.action_count)?,
+        })
+    }
+
+    fn query_entries(&mut self, entry_type: u8, count: u8) -> Result<Vec<Entry>, Box<dyn Error>> {
+        let mut entries = self.get_entries(entry_type, count as usize)?;
+        for (index, entry) in entries.iter_mut().enumerate() {
+            if entry.flags.contains(ExtraFlags::HasHelp) {
+                entry.help = Some(self.get_extra(entry_type, index as u8, b'h')?);
+            }
+            if entry.flags.contains(ExtraFlags::HasIcon) {
+                entry.icon = Some(self.get_extra(entry_type, index as u8, b'i')?);
+            }
+            let (numeric, text) = ValueType::try_from(entry.variant).map_or((false, false), |vt| (vt.is_integer(), vt.is_text()));
+            if numeric || text || entry.flags.contains(ExtraFlags::HasUnit) {
+                entry.format = self.get_format(entry_type, index as u8)?;
+            }
+            if entry.flags.contains(ExtraFlags::HasPattern) {
+                entry.pattern = Some(self.get_extra(entry_type, index as u8, QueryTargetKey::Pattern as u8)?);
+            }
+            if entry.variant == ValueType::Flags as u8 {
+                let count = u32::from_le_bytes(entry.constraints[..4].try_into()?).min(64);
+                entry.names = (0..count)
+                    .map(|bit| self.get_option(entry_type, index as u8, bit as u16))
+                    .collect::<Result<_, _>>()?;
+            }
+            if entry.variant == ValueType::Table as u8 {
+                // Only the dump has the format of columns
+                let count = u32::from_le_bytes(entry.constraints[..4].try_into()?).min(255);
+                entry.columns = (0..count)
+                    .map(|column| self.get_column(index as u8, column as u8))
+                    .collect::<Result<_, _>>()?;
+            }
+        }
+        Ok(entries)
+    }
+}
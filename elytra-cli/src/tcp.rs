@@ -57,7 +57,7 @@ impl TcpServer {
     pub fn recieve(&mut self) -> std::io::Result<&[u8]> {
         let (mut stream, _) = self.listener.accept()?;
         eprintln!("Got connection from {:?}", stream.peer_addr()?);
-        stream.read(self.read_buf.as_mut_slice())?;
+        stream.read_exact(self.read_buf.as_mut_slice())?;
         self.stream = Some(stream);
         Ok(&self.read_buf)
     }
@@ -66,7 +66,7 @@ impl TcpServer {
         let Some(mut stream) = self.stream.as_ref() else {
             return Err(std::io::Error::other("no current stream"));
         };
-        stream.write(bytes)?;    
+        stream.write_all(bytes)?;
         stream.flush()?;
         self.stream = None;
         Ok(())
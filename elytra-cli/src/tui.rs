@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::thread;
 use std::{sync::mpsc::Receiver};
 
@@ -5,27 +6,30 @@ use std::sync::mpsc::{Sender, channel};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 
 use elytra_conf::entry::ExtraFlags;
+use elytra_conf::values::ValueType;
 use ratatui::text::Span;
 use ratatui::prelude::*;
-use ratatui::widgets::{Clear, List, ListDirection, ListItem, Padding, Row, Table};
+use ratatui::widgets::{Cell, Clear, List, ListDirection, ListItem, Padding, Row, Table};
 use ratatui::{
     DefaultTerminal, Frame, buffer::Buffer, layout::{Constraint, Layout, Rect}, style::Stylize, symbols::border, text::{Line, Text}, widgets::{Block, Paragraph, Widget}
 };
 
-use crate::{ElytraDevice, Entry, Info, LayoutEntry, Section};
+use crate::{ElytraDevice, Entry, Identity, Info, LayoutEntry, Section, value::{color_value, format_item, format_value, list_items, parse_value, record_cells}};
 
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+type ChatLog = Vec<([u8; 64], [u8; 64])>;
 
 enum Progress {
-    Working((String, Vec<([u8; 64], [u8; 64])>)),
-    Failed((String, Vec<([u8; 64], [u8; 64])>)),
-    Done(DeviceInfo)
+    Working((String, ChatLog)),
+    Failed((String, ChatLog)),
+    /// Loading finished, the device is handed back to send edits.
+    Done((Box<DeviceInfo>, Box<dyn ElytraDevice>))
 }
 
 enum AppState {
     Working(LoadingWidget),
-    Done(DeviceInfo)
+    Done(Box<DeviceInfo>)
 }
 
 pub fn run(mut device: Box<dyn ElytraDevice + 'static>) -> Result<()> {
@@ -37,117 +41,353 @@ pub fn run(mut device: Box<dyn ElytraDevice + 'static>) -> Result<()> {
 
     thread::spawn(move || {
         let final_progress = match run_worker(&mut device, tx.clone()) {
-            Ok(di) => Progress::Done(di),
+            Ok(di) => Progress::Done((Box::new(di), device)),
             Err(e) => Progress::Failed((format!("{:?}", e), device.get_log()))
         };
         tx.send(final_progress).unwrap();
     });
 
-    let result = App{ rx, state: AppState::Working(LoadingWidget::new()), exit: false }.run(&mut terminal);
+    let result = App{ rx, state: AppState::Working(LoadingWidget::new()), device: None, exit: false }.run(&mut terminal);
     ratatui::restore();
-    Ok(result?)
+    result
 }
 
-fn run_worker(mut device: &mut Box<dyn ElytraDevice + 'static>, tx: Sender<Progress>) -> Result<DeviceInfo> {
+fn run_worker(device: &mut Box<dyn ElytraDevice + 'static>, tx: Sender<Progress>) -> Result<DeviceInfo> {
     let _ = tx.send(Progress::Working(("Getting device info".to_owned(), vec![])));
     let info = device.get_info()?;
-    
-
-    let mut sections = get_entries(&mut device, &tx, b's', info.section_count as usize, "sections")?;
-    get_layout(&mut device, &tx, &mut sections)?;
-    let props = get_entries(&mut device, &tx, b'c', info.prop_count as usize, "prop fields")?;
-    let infos = get_entries(&mut device, &tx, b'i', info.info_count as usize, "info fields")?;
-    let actions = get_entries(&mut device, &tx, b'a', info.action_count as usize, "actions")?;
+    let identity = device.get_identity()?;
 
+    let _ = tx.send(Progress::Working(("Loading schema".to_owned(), device.get_log())));
+    let schema = device.get_schema(&info)?;
 
     tx.send(Progress::Working(("Assembling sections".to_owned(), device.get_log())))?;
+    let sections = schema.sections();
+
+    tx.send(Progress::Working(("Reading values".to_owned(), device.get_log())))?;
+    let mut values = HashMap::new();
+    let mut tables = HashMap::new();
+    for (field, entry) in sections.iter().flat_map(|s| &s.layout) {
+        if !values.contains_key(field) {
+            values.insert(field.clone(), device.read_field(field)?);
+        }
+        if let (LayoutEntry::Prop(index), true) = (field, entry.is_table()) {
+            tables.insert(*index, device.read_table(*index)?);
+        }
+    }
 
-    // Err(format!("Misc error: {:#?}", actions.len()))?;
-
-    let sections = sections.into_iter().map(|section_entry| {
-        let layout = section_entry.layout.clone().unwrap_or_default();
-        let layout = layout.into_iter().map(|le| {
-            match le {
-                LayoutEntry::Prop(ci) => (le, props[ci as usize].clone()),
-                LayoutEntry::Info(ii) => (le, infos[ii as usize].clone())
-            }
-        }).collect();
-
-        Section {entry: section_entry, layout}
-    }).collect();
-    
-    
     Ok(DeviceInfo{
         info,
+        identity,
         sections,
-        actions,
-        section_index: 0
+        values,
+        tables,
+        actions: schema.actions,
+        section_index: 0,
+        editor: None,
+        prop_editor: None,
+        confirm_reset: false,
+        notice: None,
     })
 }
 
-fn get_extras(
-        device: &mut Box<dyn ElytraDevice + 'static>, 
-        tx: &Sender<Progress>, 
-        entries: &mut Vec<Entry>, 
-        extra_type: u8, 
-        cond: impl Fn(&Entry) -> bool, 
-        apply: impl Fn(&mut Entry, String) -> (), 
-        name: &str) -> Result<()> {
-    let count = entries.iter().filter(|e| cond(e)).count();
-    let _ = tx.send(Progress::Working((format!("  Getting {} {}", count, name), device.get_log())));
-    for (index, entry) in entries.iter_mut().enumerate().filter(|(_, e)| cond(e)) {
-        apply(entry, device.get_extra(entry.entry_type, index as u8, extra_type)?);
+/// Position of a numeric value within its range, for fields that have one.
+fn slider(entry: &Entry, value: &[u8]) -> Option<String> {
+    const WIDTH: i128 = 20;
+
+    let numeric = [ValueType::Integer as u8, ValueType::Decimal as u8].contains(&entry.variant);
+    if !numeric || !entry.format.has_range() {
+        return None;
     }
-    // thread::sleep(std::time::Duration::from_secs(1));
-    Ok(())
+    let wide = |value: i64| if entry.format.unsigned { value as u64 as i128 } else { value as i128 };
+    let value = wide(i64::from_le_bytes(value.get(..8)?.try_into().ok()?));
+    let (min, max) = (wide(entry.format.min), wide(entry.format.max));
+    let filled = if max > min { ((value - min) * WIDTH / (max - min)).clamp(0, WIDTH) } else { WIDTH };
+    Some(format!("  [{}{}]", "=".repeat(filled as usize), "-".repeat((WIDTH - filled) as usize)))
 }
 
-fn get_layout(
-        device: &mut Box<dyn ElytraDevice + 'static>, 
-        tx: &Sender<Progress>,
-        sections: &mut Vec<Entry>) -> Result<()> {
-    let _ = tx.send(Progress::Working((format!("  Getting section layout"), device.get_log())));
-
-    
-    for (index, entry) in sections.iter_mut().enumerate() {
-        let layout = device.get_layout(index as u8)?;
-        entry.layout = Some(layout)
-    }
-    // thread::sleep(std::time::Duration::from_secs(1));
-    Ok(())
+/// Checkbox for every bit of a flags field.
+fn checkboxes(entry: &Entry, value: &[u8]) -> Vec<Span<'static>> {
+    let bits = u64::from_le_bytes(value.get(..8).and_then(|v| v.try_into().ok()).unwrap_or_default());
+    entry.names.iter().enumerate().flat_map(|(bit, name)| [
+        Span::from(if bits >> bit & 1 != 0 { "  [x] " } else { "  [ ] " }).bold(),
+        Span::from(name.clone()),
+    ]).collect()
 }
 
-fn get_entries(device: &mut Box<dyn ElytraDevice + 'static>, tx: &Sender<Progress>, entry_type: u8, count: usize, n: &str) -> Result<Vec<Entry>> {
-    let _ = tx.send(Progress::Working((format!("Getting {} {}", count, n), device.get_log())));
-    // thread::sleep(std::time::Duration::from_secs(2));
-    let mut entries = device.get_entries(entry_type, count as usize)?;
+/// Line for every item of a list field.
+fn list_lines(entry: &Entry, value: &[u8]) -> Vec<Line<'static>> {
+    list_items(value).into_iter().enumerate().map(|(i, item)| Line::from_iter([
+        Span::from(format!("  {}. ", i)).fg(Color::DarkGray),
+        Span::from(format_item(entry, item).unwrap_or_default()).bold(),
+    ].into_iter().chain(swatch(entry, item)))).collect()
+}
 
-    get_extras(device, tx, &mut entries, 
-        b'h',
-        |e| e.flags.contains(ExtraFlags::HasHelp), 
-        |e, extra| e.help = Some(extra),
-        "help texts")?;
+/// Formatted cells of a table row.
+fn row_cells(entry: &Entry, row: &[u8]) -> Vec<String> {
+    let cells = record_cells(row, entry.columns.len());
+    entry.columns.iter().enumerate()
+        .map(|(i, column)| cells.get(i).and_then(|cell| format_item(column, cell).ok()).unwrap_or_default())
+        .collect()
+}
 
-    get_extras(device, tx, &mut entries, 
-        b'i',
-        |e| e.flags.contains(ExtraFlags::HasIcon), 
-        |e, extra| e.icon = Some(extra),
-        "icons")?;
+/// Column header and a line for every row of a table field.
+fn table_lines(entry: &Entry, rows: &[Vec<u8>]) -> Vec<Line<'static>> {
+    let names: Vec<_> = entry.columns.iter().map(|column| column.name.as_str()).collect();
+    let header = Line::from(format!("     {}", names.join(" | "))).fg(Color::DarkGray);
+    [header].into_iter().chain(rows.iter().enumerate().map(|(i, row)| Line::from_iter([
+        Span::from(format!("  {}. ", i)).fg(Color::DarkGray),
+        Span::from(row_cells(entry, row).join(" | ")).bold(),
+    ]))).collect()
+}
 
-    Ok(entries)
+/// Block filled with the colour of colour fields.
+fn swatch(entry: &Entry, value: &[u8]) -> Vec<Span<'static>> {
+    match color_value(entry, value) {
+        Some(color) => vec![Span::from(" "), Span::from("    ").bg(Color::Rgb(color.r, color.g, color.b))],
+        None => vec![],
+    }
 }
 
 struct DeviceInfo {
     info: Info,
+    identity: Option<Identity>,
     sections: Vec<Section>,
+    /// Raw values of the fields in the layout.
+    values: HashMap<LayoutEntry, Vec<u8>>,
+    /// Raw rows of the table props in the layout.
+    tables: HashMap<u8, Vec<Vec<u8>>>,
     section_index: usize,
+    editor: Option<TableEditor>,
+    prop_editor: Option<PropEditor>,
+    /// Asking to reset the props of the current section, opened with r.
+    confirm_reset: bool,
+    /// Error of the last reset.
+    notice: Option<String>,
     #[allow(unused)]
     actions: Vec<Entry>
 }
 
+/// Editor for the rows of a table in the current section, opened with Enter.
+struct TableEditor {
+    /// Position of the table in the section layout.
+    field: usize,
+    row: usize,
+    column: usize,
+    /// Text of the cell being edited.
+    input: Option<String>,
+    /// Error of the last edit.
+    error: Option<String>,
+}
+
+/// Editor for the integer and decimal props of the current section, opened with e. The
+/// text is parsed like on the command line, so `0x` and `0b` prefixes work.
+struct PropEditor {
+    /// Position of the prop in the section layout.
+    field: usize,
+    input: String,
+    /// Error of the last edit.
+    error: Option<String>,
+}
+
+impl DeviceInfo {
+    /// Positions of the tables in the layout of the current section.
+    fn section_tables(&self) -> Vec<usize> {
+        self.sections.get(self.section_index).into_iter()
+            .flat_map(|section| section.layout.iter().enumerate())
+            .filter(|(_, (_, entry))| entry.is_table())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Prop index and entry of the table being edited.
+    fn editor_table(&self) -> Option<(u8, &Entry)> {
+        let editor = self.editor.as_ref()?;
+        match self.sections.get(self.section_index)?.layout.get(editor.field)? {
+            (LayoutEntry::Prop(index), entry) => Some((*index, entry)),
+            _ => None,
+        }
+    }
+
+    /// Positions of the writable integer and decimal props in the layout of the current section.
+    fn section_numbers(&self) -> Vec<usize> {
+        let numeric = [ValueType::Integer as u8, ValueType::Decimal as u8];
+        self.sections.get(self.section_index).into_iter()
+            .flat_map(|section| section.layout.iter().enumerate())
+            .filter(|(_, (field, entry))| matches!(field, LayoutEntry::Prop(_)) && numeric.contains(&entry.variant)
+                && !entry.is_list() && !entry.flags.contains(ExtraFlags::ReadOnly))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Editor for the prop at `field` in the section layout, starting with its current value.
+    fn prop_editor(&self, field: usize) -> Option<PropEditor> {
+        let (layout, entry) = self.sections.get(self.section_index)?.layout.get(field)?;
+        let input = self.values.get(layout).and_then(|value| format_value(entry, value).ok()).unwrap_or_default();
+        Some(PropEditor { field, input, error: None })
+    }
+
+    fn open_prop_editor(&mut self) {
+        self.prop_editor = self.section_numbers().first().and_then(|field| self.prop_editor(*field));
+    }
+
+    fn handle_prop_editor_key(&mut self, device: &mut Box<dyn ElytraDevice>, code: KeyCode) {
+        let numbers = self.section_numbers();
+        let Some(editor) = self.prop_editor.as_mut() else { return };
+        match code {
+            KeyCode::Char(c) => editor.input.push(c),
+            KeyCode::Backspace => { editor.input.pop(); },
+            KeyCode::Tab => {
+                let next = numbers.iter().position(|field| *field == editor.field).map_or(0, |i| (i + 1) % numbers.len());
+                self.prop_editor = numbers.get(next).and_then(|field| self.prop_editor(*field));
+            },
+            KeyCode::Enter => {
+                let field = editor.field;
+                let Some((LayoutEntry::Prop(index), entry)) = self.sections.get(self.section_index)
+                    .and_then(|section| section.layout.get(field)).cloned() else { return };
+                let result = parse_value(&entry, &editor.input)
+                    .and_then(|value| device.write_prop(index, &value))
+                    .and_then(|_| device.read_prop(index));
+                // Edits are not logged anywhere once loading is done
+                device.get_log();
+                match result {
+                    Ok(value) => {
+                        self.values.insert(LayoutEntry::Prop(index), value);
+                        self.prop_editor = None;
+                    },
+                    Err(e) => editor.error = Some(e.to_string()),
+                }
+            },
+            KeyCode::Esc => self.prop_editor = None,
+            _ => {}
+        }
+    }
+
+    fn open_editor(&mut self) {
+        self.editor = self.section_tables().first().map(|field| TableEditor {
+            field: *field, row: 0, column: 0, input: None, error: None
+        });
+    }
+
+    fn handle_editor_key(&mut self, device: &mut Box<dyn ElytraDevice>, code: KeyCode) {
+        let Some((index, entry)) = self.editor_table().map(|(index, entry)| (index, entry.clone())) else {
+            self.editor = None;
+            return;
+        };
+        let rows = self.tables.get(&index).map_or(0, Vec::len);
+        let tables = self.section_tables();
+        let Some(editor) = self.editor.as_mut() else { return };
+
+        if let Some(input) = editor.input.as_mut() {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => { input.pop(); },
+                KeyCode::Esc => editor.input = None,
+                KeyCode::Enter => {
+                    let column = &entry.columns[editor.column];
+                    let result = parse_value(column, input).and_then(|value| if column.is_secret() {
+                        device.write_secret_cell(index, editor.row as u8, editor.column as u8, &value)
+                    } else {
+                        device.write_cell(index, editor.row as u8, editor.column as u8, &value)
+                    });
+                    match result {
+                        Ok(()) => {
+                            editor.input = None;
+                            editor.error = None;
+                            self.reload_table(device, index);
+                        },
+                        Err(e) => editor.error = Some(e.to_string()),
+                    }
+                },
+                _ => {}
+            }
+            return;
+        }
+
+        // The row after the last one adds a row
+        let last_row = if rows < entry.format.max_items as usize { rows } else { rows.saturating_sub(1) };
+        match code {
+            KeyCode::Up => editor.row = editor.row.saturating_sub(1),
+            KeyCode::Down => editor.row = (editor.row + 1).min(last_row),
+            KeyCode::Left => editor.column = editor.column.saturating_sub(1),
+            KeyCode::Right => editor.column = (editor.column + 1).min(entry.columns.len().saturating_sub(1)),
+            KeyCode::Tab => {
+                let next = tables.iter().position(|field| *field == editor.field).map_or(0, |i| (i + 1) % tables.len());
+                *editor = TableEditor { field: tables[next], row: 0, column: 0, input: None, error: None };
+            },
+            KeyCode::Enter if !entry.columns.is_empty() => {
+                let column = &entry.columns[editor.column];
+                let cell = self.tables.get(&index).and_then(|rows| rows.get(editor.row))
+                    .and_then(|row| record_cells(row, entry.columns.len()).get(editor.column).map(|cell| format_item(column, cell)));
+                editor.input = Some(match cell {
+                    Some(Ok(text)) if !column.is_secret() => text,
+                    _ => String::new(),
+                });
+            },
+            KeyCode::Delete | KeyCode::Char('d') if editor.row < rows => {
+                match device.delete_row(index, editor.row as u8) {
+                    Ok(()) => {
+                        editor.error = None;
+                        editor.row = editor.row.min(rows.saturating_sub(2));
+                        self.reload_table(device, index);
+                    },
+                    Err(e) => editor.error = Some(e.to_string()),
+                }
+            },
+            KeyCode::Esc | KeyCode::Char('q') => self.editor = None,
+            _ => {}
+        }
+    }
+
+    fn handle_reset_key(&mut self, device: &mut Box<dyn ElytraDevice>, code: KeyCode) {
+        self.confirm_reset = false;
+        if code == KeyCode::Char('y') {
+            self.notice = self.reset_section(device).err().map(|e| e.to_string());
+        }
+    }
+
+    /// Writes the defaults to the props of the current section and reads them again.
+    fn reset_section(&mut self, device: &mut Box<dyn ElytraDevice>) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let props: Vec<_> = self.sections.get(self.section_index).into_iter()
+            .flat_map(|section| section.layout.iter())
+            .filter_map(|(field, entry)| match field {
+                LayoutEntry::Prop(index) => Some((*index, entry.is_table())),
+                LayoutEntry::Info(_) => None,
+            })
+            .collect();
+        let result = props.into_iter().try_for_each(|(index, table)| {
+            device.reset_prop(index)?;
+            if table {
+                self.tables.insert(index, device.read_table(index)?);
+            }
+            self.values.insert(LayoutEntry::Prop(index), device.read_prop(index)?);
+            Ok(())
+        });
+        device.get_log();
+        result
+    }
+
+    /// Reads the rows and row count of a table again after an edit.
+    fn reload_table(&mut self, device: &mut Box<dyn ElytraDevice>, index: u8) {
+        let result = device.read_table(index).and_then(|rows| Ok((rows, device.read_prop(index)?)));
+        // Edits are not logged anywhere once loading is done
+        device.get_log();
+        match result {
+            Ok((rows, value)) => {
+                self.tables.insert(index, rows);
+                self.values.insert(LayoutEntry::Prop(index), value);
+            },
+            Err(e) => if let Some(editor) = self.editor.as_mut() {
+                editor.error = Some(e.to_string());
+            },
+        }
+    }
+}
+
 pub struct App {
     exit: bool,
     state: AppState,
+    /// The device, once loading is done.
+    device: Option<Box<dyn ElytraDevice>>,
     rx: Receiver<Progress>,
 }
 
@@ -163,8 +403,9 @@ impl App {
             self.handle_events()?;
             if let Ok(progress) = self.rx.try_recv() {
                 match progress {
-                    Progress::Done(di) => {
-                        self.state = AppState::Done(di)
+                    Progress::Done((di, device)) => {
+                        self.state = AppState::Done(di);
+                        self.device = Some(device);
                     },
                     Progress::Working((status, mut items)) => {
                         match &mut self.state {
@@ -222,14 +463,55 @@ impl App {
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
+        if let (AppState::Done(dev_info), Some(device)) = (&mut self.state, &mut self.device)
+            && dev_info.editor.is_some() {
+            dev_info.handle_editor_key(device, key_event.code);
+            return Ok(());
+        }
+        if let (AppState::Done(dev_info), Some(device)) = (&mut self.state, &mut self.device)
+            && dev_info.prop_editor.is_some() {
+            dev_info.handle_prop_editor_key(device, key_event.code);
+            return Ok(());
+        }
+        if let (AppState::Done(dev_info), Some(device)) = (&mut self.state, &mut self.device)
+            && dev_info.confirm_reset {
+            dev_info.handle_reset_key(device, key_event.code);
+            return Ok(());
+        }
         match key_event.code {
             KeyCode::Char('q') => self.exit(),
             KeyCode::Up => self.update_selection(-1),
             KeyCode::Down => self.update_selection(1),
+            KeyCode::Enter => self.open_editor(),
+            KeyCode::Char('e') => self.open_prop_editor(),
+            KeyCode::Char('r') => self.confirm_reset(),
             _ => Ok(())
         }
     }
 
+    fn open_editor(&mut self) -> Result<()> {
+        if let (AppState::Done(dev_info), Some(_)) = (&mut self.state, &self.device) {
+            dev_info.open_editor();
+        }
+        Ok(())
+    }
+
+    fn open_prop_editor(&mut self) -> Result<()> {
+        if let (AppState::Done(dev_info), Some(_)) = (&mut self.state, &self.device) {
+            dev_info.open_prop_editor();
+            dev_info.notice = None;
+        }
+        Ok(())
+    }
+
+    fn confirm_reset(&mut self) -> Result<()> {
+        if let (AppState::Done(dev_info), Some(_)) = (&mut self.state, &self.device) {
+            dev_info.confirm_reset = true;
+            dev_info.notice = None;
+        }
+        Ok(())
+    }
+
     fn exit(&mut self) -> Result<()> {
         self.exit = true;
         Ok(())
@@ -242,6 +524,7 @@ impl App {
             } else {
                 dev_info.section_index = dev_info.section_index.saturating_sub(1);
             }
+            dev_info.notice = None;
         }
         Ok(())
     }
@@ -272,7 +555,7 @@ impl Widget for &DeviceInfo {
     fn render(self, area: Rect, buf: &mut Buffer) {
         
         let vertical = Layout::vertical([
-            Constraint::Length(6), 
+            Constraint::Length(7), 
             Constraint::Fill(1)
             // Constraint::Percentage(50), 
         ]).spacing(0)
@@ -281,9 +564,27 @@ impl Widget for &DeviceInfo {
         
         let rows = vertical.split(area);
 
+        let (device, firmware) = match &self.identity {
+            Some(identity) => (
+                format!("{} {}{}", identity.vendor, identity.product,
+                    identity.hardware.as_ref().map(|hw| format!(" (rev. {})", hw)).unwrap_or_default()),
+                format!("{}{}", identity.firmware,
+                    identity.serial.as_ref().map(|serial| format!("   Serial: {}", serial)).unwrap_or_default()),
+            ),
+            None => ("-".to_owned(), "-".to_owned()),
+        };
+
         Paragraph::new(Text::from_iter([
             Line::from_iter([ 
-                Span::from("Version:"), 
+                Span::from("Device: "), 
+                Span::from(device)
+            ]),
+            Line::from_iter([ 
+                Span::from("Firmware: "), 
+                Span::from(firmware)
+            ]),
+            Line::from_iter([ 
+                Span::from("Version: "), 
                 Span::from(format!("{}", self.info.proto_version))
             ])
         ]))
@@ -314,25 +615,79 @@ impl Widget for &DeviceInfo {
         tabs.render(horz[0], buf);
 
         if let Some(section) = self.sections.get(self.section_index) {
-            let section_text = Text::from_iter(section.layout.iter().flat_map(|(_, e)|
-                [
-                    Line::from_iter([ 
-                        Span::from(format!("{}", e.name)), 
-                    ]),
+            let editing = self.prop_editor.as_ref().map(|editor| editor.field);
+            let section_text = Text::from_iter(section.layout.iter().enumerate().flat_map(|(i, (l, e))| {
+                let mut lines = vec![
+                    match self.values.get(l) {
+                        _ if editing == Some(i) => Line::from_iter([
+                            Span::from(e.name.clone()),
+                            Span::from(": "),
+                            Span::from(format!("{}_", self.prop_editor.as_ref().map_or("", |editor| editor.input.as_str()))).fg(Color::Yellow),
+                        ]),
+                        Some(value) if e.variant == ValueType::Bool as u8 => Line::from_iter([
+                            Span::from(if value[0] != 0 { "[x] " } else { "[ ] " }).bold(),
+                            Span::from(e.name.clone()),
+                        ]),
+                        Some(value) if e.is_list() => Line::from_iter([
+                            Span::from(e.name.clone()),
+                            Span::from(format!(" ({}/{})", list_items(value).len(), e.format.max_items)).fg(Color::DarkGray),
+                        ]),
+                        Some(value) if e.is_table() => Line::from_iter([
+                            Span::from(e.name.clone()),
+                            Span::from(format!(" ({}/{})", value[0], e.format.max_items)).fg(Color::DarkGray),
+                            Span::from("  Enter to edit").fg(Color::DarkGray).italic(),
+                        ]),
+                        Some(value) if e.variant == ValueType::Flags as u8 => Line::from_iter(
+                            [Span::from(e.name.clone()), Span::from(":")].into_iter().chain(checkboxes(e, value))
+                        ),
+                        Some(value) => Line::from_iter([ 
+                            Span::from(e.name.clone()), 
+                            Span::from(": "),
+                            Span::from(format_value(e, value).unwrap_or_default()).bold(),
+                            Span::from(slider(e, value).unwrap_or_default()).fg(Color::Cyan),
+                        ].into_iter().chain(swatch(e, value))),
+                        None => Line::from_iter([ 
+                            Span::from(e.name.clone()), 
+                        ]),
+                    },
+                ];
+                if let Some(value) = self.values.get(l).filter(|_| e.is_list()) {
+                    lines.extend(list_lines(e, value));
+                }
+                if let (LayoutEntry::Prop(index), true) = (l, e.is_table()) {
+                    lines.extend(table_lines(e, self.tables.get(index).map_or(&[], Vec::as_slice)));
+                }
+                lines.extend([
                     // Line::from("                 ").underlined(),
                     Line::from_iter([ 
-                        Span::from(format!("{}", e.help.clone().unwrap_or_default())).fg(Color::DarkGray)
+                        Span::from(e.help.clone().unwrap_or_default()).fg(Color::DarkGray)
                     ]),
                     Line::from(""),
-                ]
-            ));
+                ]);
+                lines
+            }));
+            let help = match &self.notice {
+                _ if self.prop_editor.is_some() => match self.prop_editor.as_ref().and_then(|editor| editor.error.as_ref()) {
+                    Some(error) => Line::from(format!(" {} ", error)).fg(Color::Red),
+                    None => Line::from(" Enter: save  Tab: next number  Esc: cancel ").fg(Color::DarkGray),
+                },
+                _ if self.confirm_reset => Line::from(format!(" Reset the props of {} to their defaults? y/n ", section.entry.name)).fg(Color::Yellow),
+                Some(notice) => Line::from(format!(" {} ", notice)).fg(Color::Red),
+                None => Line::from(" e: edit numbers  r: reset to defaults ").fg(Color::DarkGray),
+            };
             let para = Paragraph::new(section_text)
                 .left_aligned()
                 .block(Block::bordered().padding(Padding::symmetric(2, 1))
-                    .title(Line::from(format!(" {} ", section.entry.name))))
+                    .title(Line::from(format!(" {} ", section.entry.name)))
+                    .title_bottom(help))
                 ;
             Widget::render(Clear, horz[1], buf);
             para.render(horz[1], buf);
+
+            if let (Some(editor), Some((index, entry))) = (&self.editor, self.editor_table()) {
+                let rows = self.tables.get(&index).map_or(&[][..], Vec::as_slice);
+                render_editor(editor, entry, rows, horz[1].inner(Margin::new(2, 1)), buf);
+            }
         }
         
 
@@ -383,8 +738,49 @@ impl Widget for &DeviceInfo {
     }
 }
 
+/// Popup with the rows of the table being edited, the selected cell is highlighted.
+fn render_editor(editor: &TableEditor, entry: &Entry, rows: &[Vec<u8>], area: Rect, buf: &mut Buffer) {
+    let selected = |row: usize, column: usize| match (row == editor.row, column == editor.column) {
+        (true, true) => Style::new().bg(Color::White).fg(Color::Black),
+        (true, false) => Style::new().bold(),
+        _ => Style::new(),
+    };
+    let mut table_rows: Vec<Row> = rows.iter().enumerate().map(|(r, row)| {
+        Row::new(row_cells(entry, row).into_iter().enumerate().map(|(c, cell)| {
+            match (&editor.input, r == editor.row && c == editor.column) {
+                (Some(input), true) => Cell::from(format!("{}_", input)).style(Style::new().fg(Color::Yellow)),
+                _ => Cell::from(cell).style(selected(r, c)),
+            }
+        }))
+    }).collect();
+    if rows.len() < entry.format.max_items as usize {
+        let new_row = rows.len();
+        table_rows.push(Row::new((0..entry.columns.len()).map(|c| {
+            match (&editor.input, new_row == editor.row && c == editor.column) {
+                (Some(input), true) => Cell::from(format!("{}_", input)).style(Style::new().fg(Color::Yellow)),
+                _ => Cell::from(if c == 0 { "+ new row" } else { "" }).style(selected(new_row, c).fg(Color::DarkGray)),
+            }
+        })));
+    }
+
+    let help = match (&editor.error, &editor.input) {
+        (Some(error), _) => Line::from(error.clone()).fg(Color::Red),
+        (None, Some(_)) => Line::from(" Enter: save  Esc: cancel ").fg(Color::DarkGray),
+        (None, None) => Line::from(" Arrows: move  Enter: edit  d: delete row  Tab: next table  Esc: close ").fg(Color::DarkGray),
+    };
+    let widths = entry.columns.iter().map(|_| Constraint::Fill(1));
+    let table = Table::new(table_rows, widths)
+        .header(Row::new(entry.columns.iter().map(|column| column.name.clone())).style(Style::new().bold()).bottom_margin(1))
+        .block(Block::bordered()
+            .title(format!(" {} ({}/{}) ", entry.name, rows.len(), entry.format.max_items))
+            .title_bottom(help)
+            .padding(Padding::symmetric(1, 1)));
+    Widget::render(Clear, area, buf);
+    Widget::render(table, area, buf);
+}
+
 struct LoadingWidget {
-    log: Vec<([u8; 64], [u8; 64])>,
+    log: ChatLog,
     statuses: Vec<String>,
     failure: Option<String>
 }
@@ -412,7 +808,7 @@ impl Widget for &LoadingWidget {
                         Span::from(format!("{:width$}🅧", "", width = status.chars().take_while(|c| c.is_whitespace()).count())).fg(Color::LightRed),
                         Span::from(format!(" {}", status.trim_start())).fg(Color::Red),
                         Span::from(" Failed: "),
-                        Span::from(format!("{}", failure)).fg(Color::White),
+                        Span::from(failure.clone()).fg(Color::White),
                         ])
                     } else {Line::from_iter([
                         Span::from(format!("  {}", status)).style(Style::new().white()),
@@ -490,7 +886,7 @@ fn fmt_hex_bytes(bytes: &[u8; 64]) -> (Text<'_>, Text<'_>) {
 
 fn fmt_chunk(chunk: &[u8]) -> Vec<(u8, char, Style)> {
     chunk.iter().copied().map(|b| {
-        let c = char::try_from(b).unwrap_or('.');
+        let c = char::from(b);
         if c.is_control() {
             if (1..=9).contains(&b) {
                 (b, char::from_digit(b as u32, 10).unwrap(), Style::new().light_cyan())
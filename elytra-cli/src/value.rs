@@ -0,0 +1,659 @@
+use std::error::Error;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use color_eyre::eyre::eyre;
+use elytra_conf::color::Color;
+use elytra_conf::net::{cidr_bytes, decode_cidr, is_hostname, max_prefix};
+use elytra_conf::time::{Date, DateTime, SECONDS_PER_DAY, TimeOfDay};
+use elytra_conf::text::{CharClass, matches_pattern};
+use elytra_conf::values::{ValueType, scale_factor};
+
+use crate::{Entry, NumberFormat};
+
+fn value_type(entry: &Entry) -> Result<ValueType, Box<dyn Error>> {
+    Ok(ValueType::try_from(entry.variant).map_err(|_| eyre!("Unknown value type: {:02x}", entry.variant))?)
+}
+
+fn with_unit(value: String, entry: &Entry) -> String {
+    match &entry.format.unit {
+        Some(unit) => format!("{} {}", value, unit),
+        None => value,
+    }
+}
+
+fn text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn integer(bytes: &[u8]) -> i64 {
+    let mut value = [0u8; 8];
+    let len = bytes.len().min(8);
+    value[..len].copy_from_slice(&bytes[..len]);
+    i64::from_le_bytes(value)
+}
+
+/// Formats an integer in the radix and sign of `format`, with a `0x`, `0o` or `0b` prefix
+/// for radixes other than 10.
+pub fn format_integer(value: i64, format: &NumberFormat) -> String {
+    let (sign, abs) = match format.unsigned {
+        true => ("", value as u64),
+        false if value < 0 => ("-", value.unsigned_abs()),
+        false => ("", value as u64),
+    };
+    match format.radix {
+        16 => format!("{}0x{:x}", sign, abs),
+        8 => format!("{}0o{:o}", sign, abs),
+        2 => format!("{}0b{:b}", sign, abs),
+        _ => format!("{}{}", sign, abs),
+    }
+}
+
+/// Parses an integer, in the radix of `format` unless it has a radix prefix.
+pub fn parse_integer(text: &str, format: &NumberFormat) -> Result<i64, Box<dyn Error>> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let (radix, digits) = [("0x", 16), ("0o", 8), ("0b", 2)].into_iter()
+        .find_map(|(prefix, radix)| text.strip_prefix(prefix).map(|digits| (radix, digits)))
+        .unwrap_or((format.radix as u32, text));
+    // from_str_radix would take another sign, as in `-+5`
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        Err(eyre!("Invalid digits: {}", digits))?
+    }
+    let abs = u64::from_str_radix(digits, radix)?;
+    Ok(match (format.unsigned, negative) {
+        (true, true) => Err(eyre!("Value can't be negative"))?,
+        (true, false) => abs as i64,
+        (false, true) => 0i64.checked_sub_unsigned(abs).ok_or_else(|| eyre!("Value is too small"))?,
+        (false, false) => i64::try_from(abs)?,
+    })
+}
+
+/// Formats stored units of a decimal with `scale` places, `-215` is `-2.15` for a scale of 2.
+pub fn format_decimal(units: i64, scale: u8) -> String {
+    if scale == 0 {
+        return units.to_string();
+    }
+    let factor = scale_factor(scale).unsigned_abs();
+    let sign = if units < 0 { "-" } else { "" };
+    let abs = units.unsigned_abs();
+    format!("{}{}.{:0width$}", sign, abs / factor, abs % factor, width = scale as usize)
+}
+
+/// Parses a decimal into stored units of a field with `scale` places.
+pub fn parse_decimal(text: &str, scale: u8) -> Result<i64, Box<dyn Error>> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        Err(eyre!("Invalid decimal: {}", text))?
+    }
+    if fraction.len() > scale as usize {
+        Err(eyre!("At most {} decimal places are supported", scale))?
+    }
+    let digits = format!("{}{:0<width$}", whole, fraction, width = scale as usize);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        Err(eyre!("Invalid decimal: {}", text))?
+    }
+    let units: i64 = digits.parse()?;
+    Ok(if negative { -units } else { units })
+}
+
+/// Parses a network prefix such as `192.168.1.10/24`, a plain address is a prefix of its
+/// full length.
+pub fn parse_cidr(text: &str) -> Result<(IpAddr, u8), Box<dyn Error>> {
+    let (addr, prefix) = text.split_once('/').unwrap_or((text, ""));
+    let addr: IpAddr = addr.parse()?;
+    let prefix = match prefix {
+        "" => max_prefix(addr),
+        prefix => prefix.parse()?,
+    };
+    if prefix > max_prefix(addr) {
+        Err(eyre!("Prefix length can be at most {}", max_prefix(addr)))?
+    }
+    Ok((addr, prefix))
+}
+
+/// Parses a hardware address of 6 hex bytes separated by `:` or `-`.
+pub fn parse_mac(text: &str) -> Result<[u8; 6], Box<dyn Error>> {
+    let bytes = text.split([':', '-'])
+        .map(|byte| match byte.len() {
+            1 | 2 if byte.bytes().all(|b| b.is_ascii_hexdigit()) => u8::from_str_radix(byte, 16).ok(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    Ok(bytes.and_then(|bytes| bytes.try_into().ok()).ok_or_else(|| eyre!("Invalid MAC address: {}", text))?)
+}
+
+/// Formats seconds since midnight as `07:30`, or `07:30:15` if there are seconds.
+pub fn format_time(seconds: i64) -> String {
+    let time = TimeOfDay::from_seconds(seconds);
+    match time.second {
+        0 => format!("{:02}:{:02}", time.hour, time.minute),
+        second => format!("{:02}:{:02}:{:02}", time.hour, time.minute, second),
+    }
+}
+
+/// Parses a time of day as `HH:MM` or `HH:MM:SS` into seconds since midnight.
+pub fn parse_time(text: &str) -> Result<i64, Box<dyn Error>> {
+    let parts = text.split(':').map(str::parse::<u8>).collect::<Result<Vec<_>, _>>()
+        .map_err(|_| eyre!("Invalid time: {}", text))?;
+    let (hour, minute, second) = match parts[..] {
+        [hour, minute] => (hour, minute, 0),
+        [hour, minute, second] => (hour, minute, second),
+        _ => Err(eyre!("Invalid time: {}", text))?,
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        Err(eyre!("Time out of range: {}", text))?
+    }
+    Ok(TimeOfDay::new(hour, minute, second).seconds())
+}
+
+/// Formats days since the epoch as an ISO 8601 date, `2024-05-01`.
+pub fn format_date(days: i64) -> String {
+    let date = Date::from_days(days);
+    format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)
+}
+
+/// Parses an ISO 8601 date into days since the epoch.
+pub fn parse_date(text: &str) -> Result<i64, Box<dyn Error>> {
+    let invalid = || eyre!("Invalid date, expected YYYY-MM-DD: {}", text);
+    // Split from the end so negative years keep their sign
+    let mut parts = text.rsplitn(3, '-');
+    let day = parts.next().and_then(|day| day.parse().ok()).ok_or_else(invalid)?;
+    let month = parts.next().and_then(|month| month.parse().ok()).ok_or_else(invalid)?;
+    let year = parts.next().and_then(|year| year.parse().ok()).ok_or_else(invalid)?;
+    Ok(Date::try_new(year, month, day).ok_or_else(invalid)?.days())
+}
+
+/// Formats seconds since the epoch as an ISO 8601 timestamp in UTC.
+pub fn format_timestamp(seconds: i64) -> String {
+    let time = DateTime::from_timestamp(seconds).time;
+    format!("{}T{:02}:{:02}:{:02}Z", format_date(seconds.div_euclid(SECONDS_PER_DAY)), time.hour, time.minute, time.second)
+}
+
+/// Parses an ISO 8601 timestamp such as `2024-05-01T07:30:00+02:00` into seconds since the
+/// epoch. Timestamps without an offset are in UTC and a date alone is midnight.
+pub fn parse_timestamp(text: &str) -> Result<i64, Box<dyn Error>> {
+    let (date, time) = text.split_once(['T', ' ']).unwrap_or((text, "00:00"));
+    let (time, offset) = match time.find(['Z', '+', '-']) {
+        Some(at) => time.split_at(at),
+        None => (time, ""),
+    };
+    let offset = match offset {
+        "" | "Z" => 0,
+        offset => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let offset = &offset[1..];
+            let offset = match offset.split_once(':') {
+                Some((hours, minutes)) => format!("{}:{}", hours, minutes),
+                None if offset.len() == 4 => format!("{}:{}", &offset[..2], &offset[2..]),
+                None => format!("{}:00", offset),
+            };
+            sign * parse_time(&offset)?
+        },
+    };
+    Ok(parse_date(date)? * SECONDS_PER_DAY + parse_time(time)? - offset)
+}
+
+const DURATION_UNITS: [(char, i64); 5] = [('w', 7 * SECONDS_PER_DAY), ('d', SECONDS_PER_DAY), ('h', 3600), ('m', 60), ('s', 1)];
+
+/// Formats a duration in seconds as `1h30m`, leaving out units that are zero.
+pub fn format_duration(seconds: i64) -> String {
+    if seconds == 0 {
+        return "0s".to_owned();
+    }
+    let mut rest = seconds.unsigned_abs();
+    let mut text = if seconds < 0 { "-".to_owned() } else { String::new() };
+    // Weeks are left out, `14d` reads better than `2w`
+    for (unit, size) in &DURATION_UNITS[1..] {
+        let count = rest / *size as u64;
+        if count > 0 {
+            text += &format!("{}{}", count, unit);
+            rest %= *size as u64;
+        }
+    }
+    text
+}
+
+/// Parses a duration such as `5m`, `1h30m`, `2d 12h` or `-5m` into seconds, a plain number
+/// is in seconds.
+pub fn parse_duration(text: &str) -> Result<i64, Box<dyn Error>> {
+    let invalid = || eyre!("Invalid duration, use a number followed by w, d, h, m or s: {}", text);
+    let text = text.trim();
+    if let Ok(seconds) = text.parse() {
+        return Ok(seconds);
+    }
+    let (sign, mut rest) = match text.strip_prefix('-') {
+        Some(text) => (-1, text.trim_start()),
+        None => (1, text),
+    };
+    if rest.is_empty() {
+        Err(invalid())?
+    }
+    let mut seconds: i64 = 0;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let count: i64 = rest[..digits].parse().map_err(|_| invalid())?;
+        let unit = rest[digits..].chars().next().ok_or_else(invalid)?;
+        let size = DURATION_UNITS.iter().find(|(u, _)| *u == unit).ok_or_else(invalid)?.1;
+        seconds = count.checked_mul(size * sign).and_then(|s| s.checked_add(seconds)).ok_or_else(|| eyre!("Duration is too long"))?;
+        rest = rest[digits + unit.len_utf8()..].trim_start();
+    }
+    Ok(seconds)
+}
+
+/// Formats the stored `i64` of an integer typed value, without the unit.
+pub fn format_number(entry: &Entry, value: i64) -> String {
+    match value_type(entry) {
+        Ok(ValueType::Decimal) => format_decimal(value, entry.format.scale),
+        Ok(ValueType::Time) => format_time(value),
+        Ok(ValueType::Date) => format_date(value),
+        Ok(ValueType::Timestamp) => format_timestamp(value),
+        Ok(ValueType::Duration) => format_duration(value),
+        _ => format_integer(value, &entry.format),
+    }
+}
+
+/// Formats the step of an integer typed value, which is a duration for times and dates.
+pub fn format_step(entry: &Entry, step: u64) -> String {
+    let step = step.min(i64::MAX as u64) as i64;
+    match value_type(entry) {
+        Ok(ValueType::Time | ValueType::Timestamp) => format_duration(step),
+        Ok(ValueType::Date) => format_duration(step.saturating_mul(SECONDS_PER_DAY)),
+        _ => format_number(entry, step),
+    }
+}
+
+/// Parses text typed by the user into the stored `i64` of an integer typed value.
+pub fn parse_number(entry: &Entry, text: &str) -> Result<i64, Box<dyn Error>> {
+    match value_type(entry)? {
+        ValueType::Decimal => parse_decimal(text, entry.format.scale),
+        ValueType::Time => parse_time(text),
+        ValueType::Date => parse_date(text),
+        ValueType::Timestamp => parse_timestamp(text),
+        ValueType::Duration => parse_duration(text),
+        _ => parse_integer(text, &entry.format),
+    }
+}
+
+/// Formats a colour as `#rrggbb`, or `#rrggbbaa` with `alpha`.
+pub fn format_color(color: Color, alpha: bool) -> String {
+    match alpha {
+        true => format!("#{:02x}{:02x}{:02x}{:02x}", color.r, color.g, color.b, color.a),
+        false => format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b),
+    }
+}
+
+/// Colour of a raw colour value, `None` for other types.
+pub fn color_value(entry: &Entry, bytes: &[u8]) -> Option<Color> {
+    let [r, g, b, a] = array::<4>(bytes);
+    match value_type(entry).ok()? {
+        ValueType::Color => Some(Color::rgb(r, g, b)),
+        ValueType::ColorAlpha => Some(Color::rgba(r, g, b, a)),
+        _ => None,
+    }
+}
+
+/// Formats the set bits of a flags value as `Mon|Wed`, `-` if none are set.
+pub fn format_flags(bits: u64, names: &[String]) -> String {
+    let set: Vec<_> = (0..64).filter(|bit| bits >> bit & 1 != 0)
+        .map(|bit| names.get(bit).cloned().unwrap_or_else(|| format!("bit{}", bit)))
+        .collect();
+    if set.is_empty() { "-".to_owned() } else { set.join("|") }
+}
+
+/// Parses bit names separated by `|` or `,` into flags, `-` or nothing clears all bits.
+pub fn parse_flags(text: &str, names: &[String]) -> Result<u64, Box<dyn Error>> {
+    let text = text.trim();
+    if text.is_empty() || text == "-" {
+        return Ok(0);
+    }
+    text.split(['|', ',']).map(str::trim).try_fold(0u64, |bits, name| {
+        let bit = names.iter().position(|n| n.eq_ignore_ascii_case(name))
+            .ok_or_else(|| eyre!("Unknown flag {}, expected one of: {}", name, names.join(", ")))?;
+        Ok(bits | 1 << bit)
+    })
+}
+
+fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0u8; N];
+    let len = bytes.len().min(N);
+    array[..len].copy_from_slice(&bytes[..len]);
+    array
+}
+
+/// Splits the raw value of a list field into its items, each with a 1 byte length.
+pub fn list_items(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut items = Vec::new();
+    let mut bytes = bytes;
+    while let Some((&len, rest)) = bytes.split_first().filter(|(len, _)| **len != 0) {
+        let Some(item) = rest.get(..len as usize) else { break };
+        items.push(item);
+        bytes = &rest[item.len()..];
+    }
+    items
+}
+
+/// Encodes `items` as the raw value of a list field.
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    items.iter().flat_map(|item| [item.len() as u8].into_iter().chain(item.iter().copied())).collect()
+}
+
+/// Splits a row read from a table into the raw value of every column, each has a 1 byte
+/// length.
+pub fn record_cells(bytes: &[u8], columns: usize) -> Vec<&[u8]> {
+    let mut cells = Vec::new();
+    let mut bytes = bytes;
+    while let Some((&len, rest)) = bytes.split_first().filter(|_| cells.len() < columns) {
+        let Some(cell) = rest.get(..len as usize) else { break };
+        cells.push(cell);
+        bytes = &rest[cell.len()..];
+    }
+    cells
+}
+
+/// Formats a value for display, the items of lists are separated by commas.
+pub fn format_value(entry: &Entry, bytes: &[u8]) -> Result<String, Box<dyn Error>> {
+    if !entry.is_list() {
+        return format_item(entry, bytes);
+    }
+    let items = list_items(bytes).into_iter()
+        .map(|item| format_item(entry, item))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(if items.is_empty() { "-".to_owned() } else { items.join(", ") })
+}
+
+/// Formats a single value, or a single item of a list.
+pub fn format_item(entry: &Entry, bytes: &[u8]) -> Result<String, Box<dyn Error>> {
+    Ok(match value_type(entry)? {
+        ValueType::Text | ValueType::Hostname => text(bytes),
+        ValueType::Secret => match bytes.first() {
+            Some(1) => "(set)".to_owned(),
+            _ => "(not set)".to_owned(),
+        },
+        ValueType::Ipv4 => Ipv4Addr::from(array::<4>(bytes)).to_string(),
+        ValueType::Ipv6 => Ipv6Addr::from(array::<16>(bytes)).to_string(),
+        ValueType::Cidr => decode_cidr(bytes).map(|(addr, prefix)| format!("{}/{}", addr, prefix)).unwrap_or_default(),
+        ValueType::Mac => array::<6>(bytes).map(|b| format!("{:02x}", b)).join(":"),
+        ValueType::Integer | ValueType::Decimal | ValueType::Time | ValueType::Date
+            | ValueType::Timestamp | ValueType::Duration => with_unit(format_number(entry, integer(bytes)), entry),
+        ValueType::Bool => (bytes.first() != Some(&0)).to_string(),
+        ValueType::Flags => format_flags(integer(bytes) as u64, &entry.names),
+        ValueType::Color | ValueType::ColorAlpha => {
+            let alpha = entry.variant == ValueType::ColorAlpha as u8;
+            color_value(entry, bytes).map(|color| format_color(color, alpha)).unwrap_or_default()
+        },
+        ValueType::Status => format!("[{}] {}", bytes.first().copied().unwrap_or_default(), text(bytes.get(1..).unwrap_or_default())),
+        ValueType::Bytes => {
+            let len = u64::from_le_bytes(entry.constraints) as usize;
+            bytes.iter().take(len).map(|b| format!("{:02x}", b)).collect()
+        },
+        ValueType::Table => format!("{} of {} rows", bytes.first().copied().unwrap_or_default(), entry.format.max_items),
+        ValueType::Options => {
+            // Options are not length prefixed, show as many as the field allows
+            let max = u16::from_le_bytes([entry.constraints[6], entry.constraints[7]]) as usize;
+            bytes.chunks_exact(2).take(max)
+                .map(|option| u16::from_le_bytes([option[0], option[1]]).to_string())
+                .collect::<Vec<_>>().join(",")
+        },
+    })
+}
+
+/// Checks text against the length, accepted characters and pattern of `entry`, which the
+/// device would reject. Option lists are left to the device.
+fn check_text(entry: &Entry, text: &str) -> Result<(), Box<dyn Error>> {
+    let format = &entry.format;
+    if format.has_range() && !(format.min..=format.max).contains(&(text.len() as i64)) {
+        Err(eyre!("Text has to be {} to {} bytes long", format.min, format.max))?
+    }
+    if let Some(c) = text.chars().find(|c| !format.chars.accepts(*c)) {
+        Err(eyre!("Invalid character {:?}, expected {}", c, format_chars(format.chars)))?
+    }
+    if let Some(pattern) = &entry.pattern && !matches_pattern(pattern, text, false) {
+        Err(eyre!("Text does not match the pattern {}", pattern))?
+    }
+    Ok(())
+}
+
+/// Names of the classes of accepted characters, such as `digits, lower`.
+pub fn format_chars(chars: CharClass) -> String {
+    let names: Vec<_> = chars.iter_names().map(|(name, _)| name.to_lowercase()).collect();
+    names.join(", ")
+}
+
+/// Parses a value typed by the user into the raw bytes to write, the unit is optional. For
+/// list fields this is a single item.
+pub fn parse_value(entry: &Entry, text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let number = || {
+        let text = text.trim();
+        entry.format.unit.as_ref().and_then(|unit| text.strip_suffix(unit.as_str())).unwrap_or(text).trim_end()
+    };
+    Ok(match value_type(entry)? {
+        ValueType::Text | ValueType::Secret => {
+            check_text(entry, text)?;
+            text.as_bytes().to_vec()
+        },
+        ValueType::Hostname => match text.trim() {
+            text if text.is_empty() || is_hostname(text) => {
+                check_text(entry, text)?;
+                text.as_bytes().to_vec()
+            },
+            _ => Err(eyre!("Invalid hostname: {}", text))?,
+        },
+        ValueType::Ipv4 => text.trim().parse::<Ipv4Addr>()?.octets().to_vec(),
+        ValueType::Ipv6 => text.trim().parse::<Ipv6Addr>()?.octets().to_vec(),
+        ValueType::Cidr => {
+            let (addr, prefix) = parse_cidr(text.trim())?;
+            cidr_bytes(addr, prefix).collect()
+        },
+        ValueType::Mac => parse_mac(text.trim())?.to_vec(),
+        ValueType::Integer | ValueType::Decimal | ValueType::Time | ValueType::Date
+            | ValueType::Timestamp | ValueType::Duration => parse_number(entry, number())?.to_le_bytes().to_vec(),
+        ValueType::Flags => parse_flags(text, &entry.names)?.to_le_bytes().to_vec(),
+        ValueType::Color | ValueType::ColorAlpha => {
+            let color = Color::parse(text.trim()).ok_or_else(|| eyre!("Invalid colour, expected #rrggbb: {}", text))?;
+            match value_type(entry)? {
+                ValueType::ColorAlpha => color.bytes().to_vec(),
+                _ if color.a != 255 => Err(eyre!("Colour has no alpha channel"))?,
+                _ => color.bytes()[..3].to_vec(),
+            }
+        },
+        ValueType::Bool => match text.trim().to_ascii_lowercase().as_str() {
+            "true" | "on" | "yes" | "1" => vec![1],
+            "false" | "off" | "no" | "0" => vec![0],
+            _ => Err(eyre!("Invalid toggle value: {}", text))?,
+        },
+        ValueType::Bytes => {
+            if !text.bytes().all(|b| b.is_ascii_hexdigit()) || !text.len().is_multiple_of(2) {
+                Err(eyre!("Invalid hex bytes: {}", text))?
+            }
+            (0..text.len()).step_by(2)
+                .map(|i| u8::from_str_radix(&text[i..i + 2], 16))
+                .collect::<Result<_, _>>()?
+        },
+        ValueType::Options => text.split(',')
+            .map(|option| option.trim().parse::<u16>().map(u16::to_le_bytes))
+            .collect::<Result<Vec<_>, _>>()?.concat(),
+        ValueType::Status => Err(eyre!("Status values can't be written"))?,
+        ValueType::Table => Err(eyre!("Tables are written row by row"))?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use elytra_conf::color::Color;
+    use elytra_conf::entry::ExtraFlags;
+    use elytra_conf::values::ValueType;
+
+    use super::*;
+
+    fn entry(variant: ValueType) -> Entry {
+        Entry {
+            name: "test".to_owned(),
+            flags: ExtraFlags::empty(),
+            variant: variant as u8,
+            constraints: [0; 8],
+            icon: None,
+            help: None,
+            entry_type: b'c',
+            id: None,
+            format: NumberFormat::default(),
+            names: Vec::new(),
+            columns: Vec::new(),
+            pattern: None,
+            layout: None,
+        }
+    }
+
+    #[test]
+    fn decimal_roundtrip() {
+        for (units, scale) in [(-215, 2), (5, 1), (-5, 1), (0, 0), (1234, 3), (i64::MAX, 4)] {
+            assert_eq!(units, parse_decimal(&format_decimal(units, scale), scale).unwrap());
+        }
+        assert_eq!(50, parse_decimal("5", 1).unwrap());
+        assert_eq!(5, parse_decimal(".5", 1).unwrap());
+        assert_eq!(-10, parse_decimal("-1.", 1).unwrap());
+        for text in [".", "-", "", "-.", "1.234", "1a", "--1", "1.-5", "1.2.3"] {
+            assert!(parse_decimal(text, 2).is_err(), "{:?} parsed", text);
+        }
+    }
+
+    #[test]
+    fn integer_radix_roundtrip() {
+        let hex = NumberFormat { radix: 16, ..NumberFormat::default() };
+        let unsigned = NumberFormat { radix: 16, unsigned: true, ..NumberFormat::default() };
+        assert_eq!("0xff", format_integer(255, &hex));
+        assert_eq!("-0x10", format_integer(-16, &hex));
+        assert_eq!("0xffffffffffffffff", format_integer(-1, &unsigned));
+        for (value, format) in [(255, &hex), (-16, &hex), (i64::MIN, &hex), (-1, &unsigned), (42, &NumberFormat::default())] {
+            assert_eq!(value, parse_integer(&format_integer(value, format), format).unwrap());
+        }
+        assert_eq!(255, parse_integer("ff", &hex).unwrap());
+        assert_eq!(5, parse_integer("0b101", &NumberFormat::default()).unwrap());
+        assert_eq!(8, parse_integer("0o10", &NumberFormat::default()).unwrap());
+
+        assert!(parse_integer("-1", &unsigned).is_err());
+        assert!(parse_integer("0xg", &hex).is_err());
+        for text in ["+5", "-+5", "0x+f", "-0x-f"] {
+            assert!(parse_integer(text, &hex).is_err(), "{:?} parsed", text);
+        }
+        assert!(parse_integer("9223372036854775808", &NumberFormat::default()).is_err());
+        assert!(parse_integer("", &NumberFormat::default()).is_err());
+    }
+
+    #[test]
+    fn time_roundtrip() {
+        assert_eq!("07:30", format_time(27000));
+        assert_eq!("23:59:59", format_time(86399));
+        for seconds in [0, 27000, 27015, 86399] {
+            assert_eq!(seconds, parse_time(&format_time(seconds)).unwrap());
+        }
+        for text in ["24:00", "07:60", "07:30:60", "7", "a:b", "07:30:00:00", ""] {
+            assert!(parse_time(text).is_err(), "{:?} parsed", text);
+        }
+    }
+
+    #[test]
+    fn date_roundtrip() {
+        assert_eq!("1970-01-01", format_date(0));
+        assert_eq!("1969-12-31", format_date(-1));
+        assert_eq!("2024-02-29", format_date(parse_date("2024-02-29").unwrap()));
+        for days in [-1, 0, 19844, 2932896] {
+            assert_eq!(days, parse_date(&format_date(days)).unwrap());
+        }
+        assert_eq!("2147483647-12-31", format_date(i64::MAX));
+        assert_eq!("-2147483648-01-01", format_date(i64::MIN));
+        for text in ["2023-02-29", "2024-13-01", "2024-00-10", "2024/05/01", "2024-05", ""] {
+            assert!(parse_date(text).is_err(), "{:?} parsed", text);
+        }
+    }
+
+    #[test]
+    fn timestamp_roundtrip() {
+        let utc = parse_timestamp("2024-05-01T05:30:00Z").unwrap();
+        assert_eq!("2024-05-01T05:30:00Z", format_timestamp(utc));
+        assert_eq!(utc, parse_timestamp("2024-05-01T07:30:00+02:00").unwrap());
+        assert_eq!(utc, parse_timestamp("2024-05-01 07:30+0200").unwrap());
+        assert_eq!(utc, parse_timestamp("2024-05-01T02:30:00-03").unwrap());
+        assert_eq!(utc - 5 * 3600 - 30 * 60, parse_timestamp("2024-05-01").unwrap());
+        for seconds in [-1, 0, utc] {
+            assert_eq!(seconds, parse_timestamp(&format_timestamp(seconds)).unwrap());
+        }
+        assert!(parse_timestamp("2024-05-01T25:00").is_err());
+        assert!(parse_timestamp("2024-05-01T05:30+2:99").is_err());
+    }
+
+    #[test]
+    fn cidr_and_mac() {
+        assert_eq!(("192.168.1.10".parse().unwrap(), 24), parse_cidr("192.168.1.10/24").unwrap());
+        assert_eq!(("10.0.0.1".parse().unwrap(), 32), parse_cidr("10.0.0.1").unwrap());
+        assert_eq!(("fe80::1".parse().unwrap(), 64), parse_cidr("fe80::1/64").unwrap());
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+        assert!(parse_cidr("10.0.0/8").is_err());
+        assert!(parse_cidr("10.0.0.0/").is_ok());
+
+        let mac = [0x28, 0xcd, 0xc1, 0x0e, 0x4a, 0x17];
+        assert_eq!(mac, parse_mac("28:cd:c1:0e:4a:17").unwrap());
+        assert_eq!(mac, parse_mac("28-CD-C1-E-4A-17").unwrap());
+        let text = format_item(&entry(ValueType::Mac), &mac).unwrap();
+        assert_eq!(mac, parse_mac(&text).unwrap());
+        for text in ["28:cd:c1:0e:4a", "28:cd:c1:0e:4a:17:00", "128:cd:c1:0e:4a:17", "28:cd:c1:0e:4a:zz", "+f:cd:c1:0e:4a:17"] {
+            assert!(parse_mac(text).is_err(), "{:?} parsed", text);
+        }
+    }
+
+    #[test]
+    fn color_roundtrip() {
+        let color = entry(ValueType::Color);
+        let alpha = entry(ValueType::ColorAlpha);
+        assert_eq!(vec![0xff, 0x30, 0x00], parse_value(&color, "#ff3000").unwrap());
+        assert_eq!(vec![0xff, 0x30, 0x00, 0x80], parse_value(&alpha, "#ff300080").unwrap());
+        assert_eq!("#ff3000", format_item(&color, &[0xff, 0x30, 0x00]).unwrap());
+        assert_eq!("#ff300080", format_item(&alpha, &[0xff, 0x30, 0x00, 0x80]).unwrap());
+        assert_eq!("#a0e0ff", format_color(Color::rgb(0xa0, 0xe0, 0xff), false));
+        assert!(parse_value(&color, "#ff300080").is_err());
+        assert!(parse_value(&color, "red").is_err());
+    }
+
+    #[test]
+    fn duration_roundtrip() {
+        assert_eq!("1h30m", format_duration(5400));
+        assert_eq!("-5m", format_duration(-300));
+        assert_eq!("14d", format_duration(14 * 86400));
+        for seconds in [0, 1, -1, 59, 5400, -300, -93784, 14 * 86400, i64::MAX, i64::MIN] {
+            assert_eq!(seconds, parse_duration(&format_duration(seconds)).unwrap());
+        }
+        assert_eq!(-300, parse_duration("-300").unwrap());
+        assert_eq!(-9000, parse_duration("- 2h 30m").unwrap());
+        assert_eq!(2 * 604800, parse_duration("2w").unwrap());
+        for text in ["", "-", "5x", "m", "-5m-3s", "--5m", "1h 5"] {
+            assert!(parse_duration(text).is_err(), "{:?} parsed", text);
+        }
+        assert!(parse_duration("9999999999999999w").is_err());
+    }
+
+    #[test]
+    fn flags_roundtrip() {
+        let names: Vec<_> = ["Mon", "Tue", "Wed"].map(str::to_owned).into();
+        assert_eq!("Mon|Wed", format_flags(0b101, &names));
+        assert_eq!("-", format_flags(0, &names));
+        assert_eq!("Tue|bit5", format_flags(0b100010, &names));
+        assert_eq!(0b101, parse_flags(&format_flags(0b101, &names), &names).unwrap());
+        assert_eq!(0b011, parse_flags("mon, TUE", &names).unwrap());
+        assert_eq!(0, parse_flags("-", &names).unwrap());
+        assert!(parse_flags("Mon|Sun", &names).is_err());
+    }
+
+    #[test]
+    fn hex_bytes() {
+        assert_eq!(vec![0x00, 0xff, 0x1a], parse_value(&entry(ValueType::Bytes), "00fF1a").unwrap());
+        for text in ["0", "+f", "0g", "ö0"] {
+            assert!(parse_value(&entry(ValueType::Bytes), text).is_err(), "{:?} parsed", text);
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use std::{cell::Cell, error::Error, fs::File, io::{Read, Write}, path::Path};
+use std::{cell::Cell, error::Error, fs::File, io::{Read, Write}, path::Path, time::{Duration, Instant}};
+use elytra_conf::{config::VALUE_LENGTH, store::{PropStore, RamStore}};
 use log::debug;
 
 use color_eyre::eyre::eyre;
@@ -14,10 +15,15 @@ use crate::ElytraDevice;
 pub struct WasmDevice {
     instance: Instance,
     store: Store<HostState>,
-    log: Cell<Vec<([u8; 64], [u8; 64])>>
+    log: Cell<Vec<([u8; 64], [u8; 64])>>,
+    /// Host time the module's `tick` export has been called up to.
+    ticked: Instant,
 }
 
-type HostState = u32;
+/// Host side of the `elytra.store_*` imports used by `elytra_wasm::HostStore`.
+struct HostState {
+    store: Box<dyn PropStore + Send>,
+}
 
 type Message = (u64, u64, u64, u64, u64, u64, u64, u64);
 
@@ -30,7 +36,13 @@ fn pack64(value: [u8; 64]) -> Message {
 }
 
 impl WasmDevice {
+    /// Loads the module at `file_path`, keeping stored props in memory for the
+    /// lifetime of the device.
     pub fn new(file_path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::with_store(file_path, RamStore::<256>::new())
+    }
+
+    pub fn with_store(file_path: &Path, store: impl PropStore + Send + 'static) -> Result<Self, Box<dyn Error>> {
 
         let engine = Engine::default();
         // Now we can compile the above Wasm module with the given Wasm source.
@@ -44,8 +56,9 @@ impl WasmDevice {
             debug!("WASM Export {}: {:#?}", e.name(), e.ty());
         }
 
-        let mut store = Store::new(&engine, 0);
-        let linker = <Linker<HostState>>::new(&engine);
+        let mut store = Store::new(&engine, HostState { store: Box::new(store) });
+        let mut linker = <Linker<HostState>>::new(&engine);
+        define_store_imports(&mut linker)?;
 
 
         #[cfg(feature = "wasmtime")]
@@ -55,13 +68,68 @@ impl WasmDevice {
         let instance = linker.instantiate_and_start(&mut store, &module)?;
 
         Ok(Self {
-            instance, store, log: Cell::new(vec![])
+            instance, store, log: Cell::new(vec![]), ticked: Instant::now()
         })
     }
+
+    /// Passes the time since the last call on to the module's `tick` export, if it has
+    /// one, so timeouts run out between commands.
+    fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let Ok(tick_fn) = self.instance.get_typed_func::<u64, ()>(&mut self.store, "tick") else {
+            return Ok(());
+        };
+        let millis = self.ticked.elapsed().as_millis() as u64;
+        tick_fn.call(&mut self.store, millis)?;
+        self.ticked += Duration::from_millis(millis);
+        Ok(())
+    }
+}
+
+fn memory(caller: &mut Caller<'_, HostState>) -> Option<Memory> {
+    caller.get_export("memory").and_then(Extern::into_memory)
+}
+
+fn define_store_imports(linker: &mut Linker<HostState>) -> Result<(), Box<dyn Error>> {
+    linker.func_wrap("elytra", "store_load", |mut caller: Caller<'_, HostState>, key: u32, ptr: u32| -> i32 {
+        let Some(memory) = memory(&mut caller) else { return -1 };
+        match caller.data_mut().store.load_key(key) {
+            Ok(Some(bytes)) => match memory.write(&mut caller, ptr as usize, &bytes) {
+                Ok(_) => 1,
+                Err(_) => -1,
+            },
+            Ok(None) => 0,
+            Err(_) => -1,
+        }
+    })?;
+    linker.func_wrap("elytra", "store_save", |mut caller: Caller<'_, HostState>, key: u32, ptr: u32| -> i32 {
+        let Some(memory) = memory(&mut caller) else { return -1 };
+        let mut bytes = [0u8; VALUE_LENGTH];
+        if memory.read(&caller, ptr as usize, &mut bytes).is_err() {
+            return -1;
+        }
+        match caller.data_mut().store.save_key(key, &bytes) {
+            Ok(_) => 1,
+            Err(_) => -1,
+        }
+    })?;
+    linker.func_wrap("elytra", "store_erase", |mut caller: Caller<'_, HostState>, key: u32| -> i32 {
+        match caller.data_mut().store.erase_key(key) {
+            Ok(_) => 1,
+            Err(_) => -1,
+        }
+    })?;
+    linker.func_wrap("elytra", "store_erase_all", |mut caller: Caller<'_, HostState>| -> i32 {
+        match caller.data_mut().store.erase_all() {
+            Ok(_) => 1,
+            Err(_) => -1,
+        }
+    })?;
+    Ok(())
 }
 
 impl ElytraDevice for WasmDevice {
     fn send_command_raw(&mut self, bytes: [u8; 64]) -> Result<[u8; 64], Box<dyn std::error::Error>> {
+        self.tick()?;
         let msg_in = pack64(bytes);
         let send_fn = self.instance.get_typed_func::<Message, u32>(&mut self.store, "send")
             .unwrap();
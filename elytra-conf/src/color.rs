@@ -0,0 +1,83 @@
+use core::prelude::rust_2024::{*};
+
+/// Colour of a [`ValueType::Color`](crate::values::ValueType::Color) or
+/// [`ValueType::ColorAlpha`](crate::values::ValueType::ColorAlpha) field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// Opacity, `255` is opaque and colours without alpha are always opaque.
+    pub a: u8,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Opaque colour from `0xrrggbb`.
+    pub const fn hex(rgb: u32) -> Self {
+        Self::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    pub const fn bytes(&self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    /// Parses `#rgb`, `#rrggbb` or `#rrggbbaa`, the `#` is optional.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.strip_prefix('#').unwrap_or(text);
+        // from_str_radix would take a sign, as in `+f`
+        if !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |i: usize| u8::from_str_radix(&text[i..=i], 16).ok();
+        let byte = |i: usize| u8::from_str_radix(&text[i..i + 2], 16).ok();
+        match text.len() {
+            3 => Some(Self::rgb(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+            6 => Some(Self::rgb(byte(0)?, byte(2)?, byte(4)?)),
+            8 => Some(Self::rgba(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a palette entry such as `Warm white #ffd8a8` into its name and colour, entries
+/// without a name are just the colour.
+pub fn palette_entry(entry: &str) -> Option<(&str, Color)> {
+    let (name, color) = entry.trim().rsplit_once(' ').unwrap_or(("", entry.trim()));
+    Some((name.trim_end(), Color::parse(color)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Color, palette_entry};
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(Some(Color::hex(0xff8800)), Color::parse("#ff8800"));
+        assert_eq!(Some(Color::hex(0xff8800)), Color::parse("F80"));
+        assert_eq!(Some(Color::rgba(1, 2, 3, 4)), Color::parse("#01020304"));
+        assert_eq!(None, Color::parse("#ff88"));
+        assert_eq!(None, Color::parse("#gg8800"));
+        assert_eq!(None, Color::parse("#ff88ö"));
+        assert_eq!(None, Color::parse("#+f+f+f"));
+        assert_eq!(None, Color::parse("+f+f+f"));
+    }
+
+    #[test]
+    fn palette_entries() {
+        assert_eq!(Some(("Warm white", Color::hex(0xffd8a8))), palette_entry("Warm white #ffd8a8"));
+        assert_eq!(Some(("", Color::hex(0x00ff00))), palette_entry("#00ff00"));
+        assert_eq!(None, palette_entry("Red"));
+    }
+}
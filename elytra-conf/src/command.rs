@@ -1,20 +1,41 @@
+use bitflags::bitflags;
 use log::{debug, info};
 use num_enum::TryFromPrimitive;
 use core::{panic, slice};
+use core::time::Duration;
 use core::prelude::rust_2024::{*};
 
 use crate::config::{EntryIndex, EntryType, QueryTarget, QueryTargetKey};
 use crate::{ActionIndex, InfoIndex, PropIndex, SectionIndex};
 use crate::{
     config::{MESSAGE_LENGTH, PAYLOAD_SIZE},
-    field::FieldValue
+    entry::EntryVariant,
+    field::FieldValue,
+    values::ValueType,
 };
 
+bitflags! {
+    /// First byte of the response to `WriteProp` and the last `WritePropAt` chunk.
+    #[derive(Debug, Eq, PartialEq, Clone, Copy)]
+    pub struct WriteFlags: u8 {
+        /// The value was clamped to the prop, the response holds it as stored.
+        const Clamped = 1 << 0;
+        /// The stored value did not fit the response and was cut.
+        const Cut = 1 << 1;
+    }
+}
+
 pub struct CommandResponse {
     bytes: [u8; MESSAGE_LENGTH],
     len: usize,
 }
 
+impl Default for CommandResponse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CommandResponse {
     pub fn ok() -> Self { Self::new() }
 
@@ -45,11 +66,34 @@ impl CommandResponse {
         cr
     }
 
-    pub fn from_field_value(field_value: FieldValue) -> Self {
-        Self {
-            bytes: field_value.into_message_bytes(),
-            len: MESSAGE_LENGTH,
+    /// Builds the page of `payload` starting at `offset`. If the payload does not fit
+    /// the status byte is set to `2`, telling the host to ask for the next page.
+    pub fn paged<T: IntoIterator<Item = u8>>(payload: T, offset: u16) -> Self {
+        let mut payload = payload.into_iter().skip(offset as usize).peekable();
+        let mut cr = CommandResponse::new();
+        cr.extend(payload.by_ref().take(PAYLOAD_SIZE));
+        if payload.peek().is_some() {
+            cr.bytes[0] = 2;
         }
+        cr
+    }
+
+    pub fn from_field_value(field_value: FieldValue, offset: u16) -> Self {
+        Self::paged(field_value.message_payload(), offset)
+    }
+
+    /// Answers a write with [`WriteFlags`] followed by the value as stored. Values that
+    /// don't fit are cut and flagged, the host reads them in full.
+    pub fn write_echo(field_value: &FieldValue, clamped: bool) -> Self {
+        let mut payload = field_value.message_payload().peekable();
+        let mut cr = CommandResponse::new();
+        cr.push(0);
+        cr.extend(payload.by_ref().take(PAYLOAD_SIZE - 1));
+        let mut flags = WriteFlags::empty();
+        flags.set(WriteFlags::Clamped, clamped);
+        flags.set(WriteFlags::Cut, payload.peek().is_some());
+        cr.bytes[1] = flags.bits();
+        cr
     }
 
     pub fn push(&mut self, value: u8) {
@@ -82,8 +126,7 @@ impl From<Option<&'static str>> for CommandResponse {
 
 impl From<&'static str> for CommandResponse {
     fn from(value: &'static str) -> Self {
-        let str_len: usize = value.floor_char_boundary(value.len().min(PAYLOAD_SIZE));
-        CommandResponse::from_payload(value.bytes().take(str_len))
+        CommandResponse::paged(value.bytes(), 0)
     }
 }
 
@@ -96,34 +139,111 @@ impl From<Result<CommandResponse, CommandError>> for CommandResponse {
 #[repr(u8)]
 #[derive(TryFromPrimitive)]
 pub enum CommandKey {
-    ReadProp = 'r' as u8,
-    WriteProp = 'w' as u8,
-    ReadInfo = 'R' as u8,
-    WriteInfo = 'W' as u8,
-    Query = 'q' as u8,
-    Action = 'a' as u8,
-    Meta = 'm' as u8,
+    ReadProp = b'r',
+    WriteProp = b'w',
+    WritePropAt = b'p',
+    EditList = b'l',
+    ReadRow = b'g',
+    WriteCell = b'y',
+    DeleteRow = b'x',
+    ClearSecret = b'c',
+    Transaction = b't',
+    ResetProp = b'z',
+    FactoryReset = b'Z',
+    ReadInfo = b'R',
+    WriteInfo = b'W',
+    Query = b'q',
+    Action = b'a',
+    Meta = b'm',
+    Dump = b'd',
+    Identity = b'I',
     Noop = 0,
 }
 
+#[repr(u8)]
+#[derive(TryFromPrimitive)]
+pub enum ListEditKey {
+    Append = b'a',
+    Replace = b'r',
+    Remove = b'x',
+    Clear = b'c',
+}
+
+#[repr(u8)]
+#[derive(TryFromPrimitive, Clone, Copy, Debug)]
+pub enum TransactionKey {
+    Begin = b'b',
+    Commit = b'c',
+    CommitPending = b'p',
+    Confirm = b'k',
+    Abort = b'x',
+}
+
+/// Step of a transaction, see [`crate::transaction`].
+#[derive(Clone, Copy, Debug)]
+pub enum TransactionStep {
+    Begin,
+    Commit,
+    /// Commits the staged writes and reverts them unless they are confirmed within the
+    /// given time, sent as seconds with 2 bytes. The time can't be zero.
+    CommitPending(Duration),
+    Confirm,
+    Abort,
+}
+
+/// Change to the items of a list prop, see [`FieldValue::edit_items`].
+pub enum ListEdit {
+    Append(FieldValue),
+    Replace(u8, FieldValue),
+    Remove(u8),
+    Clear,
+}
+
 // pub enum QueryArgs {
 //     entry_type: EntryType
 // }
 
 pub enum Command<A: ActionIndex, P: PropIndex, I: InfoIndex, S: SectionIndex> {
-    ReadProp(P),
-    WriteProp((P, FieldValue)),
-    ReadInfo(I),
+    ReadProp((P, u16)),
+    /// Writes the value of a prop, the flag tells whether it was clamped to the field.
+    WriteProp((P, FieldValue, bool)),
+    /// Chunk of a value that does not fit a single message, written from `offset` on. The
+    /// command holds the offset, the total length of the value and the chunk with a 1 byte
+    /// length. Text has to be split at char boundaries.
+    WritePropAt((P, u16, u16, FieldValue)),
+    /// Changes a single item of a list prop. The command holds the edit, the item index
+    /// and the item with a 1 byte length.
+    EditList((P, ListEdit)),
+    /// Page of a row of a table prop starting at the given offset, see [`crate::table`].
+    ReadRow((P, u8, u16)),
+    /// Replaces a cell of a table row from `offset` on like `WritePropAt`. The command
+    /// holds the row, the column, the offset and the bytes with a 1 byte length.
+    WriteCell((P, u8, u8, u16, FieldValue)),
+    DeleteRow((P, u8)),
+    /// Empties a secret prop, which can't be done with `WriteProp` when the secret has a
+    /// minimum length. Secrets are write-only, reads only tell whether they are set.
+    ClearSecret(P),
+    /// Begins, commits or aborts a batch of prop writes that is applied as a whole.
+    Transaction(TransactionStep),
+    /// Writes the default to a prop, tables lose all rows.
+    ResetProp(P),
+    /// Erases every stored value at once so all props fall back to their defaults.
+    FactoryReset,
+    ReadInfo((I, u16)),
     WriteInfo((I, FieldValue)),
-    Query((EntryIndex<A, P, I, S>, QueryTarget)),
+    Query((EntryIndex<A, P, I, S>, QueryTarget, u16)),
     Action(A),
     Meta,
+    /// Page of the schema dump starting at the given offset, see [`Config::dump`](crate::config::Config::dump).
+    Dump(u16),
+    /// Page of the device identity starting at the given offset.
+    Identity(u16),
     Noop,
 }
 
 impl <A: ActionIndex, P: PropIndex, I: InfoIndex, S: SectionIndex> Command<A, P, I, S> {
-    pub fn from_bytes<'a>(bytes: &[u8]) -> Result<Command<A, P, I, S>, CommandError> {
-        let mut bytes = bytes.into_iter();
+    pub fn from_bytes(bytes: &[u8]) -> Result<Command<A, P, I, S>, CommandError> {
+        let mut bytes = bytes.iter();
         let key = bytes.next()
             .and_then(|b| CommandKey::try_from(*b).ok())
             .ok_or(CommandError::InvalidCommand)?;
@@ -133,23 +253,103 @@ impl <A: ActionIndex, P: PropIndex, I: InfoIndex, S: SectionIndex> Command<A, P,
                 Ok(Command::Action(Self::get_action_index(&mut bytes)?))
             },
             CommandKey::ReadProp => {
-                Ok(Command::ReadProp(Self::get_prop_index(&mut bytes)?))
+                let prop_field = Self::get_prop_index(&mut bytes)?;
+                Ok(Command::ReadProp((prop_field, Self::get_offset(&mut bytes))))
             },
             CommandKey::WriteProp => { 
                 let prop_field = Self::get_prop_index(&mut bytes)?;
                 let payload = Self::get_payload(&mut bytes)?;
                 let desc = P::get_entry(prop_field);
-                let field_value = FieldValue::from_message(desc, payload);
-                Ok(Command::WriteProp((prop_field, field_value)))
+                let (field_value, clamped) = FieldValue::from_message_clamped(desc, payload)?;
+                Ok(Command::WriteProp((prop_field, field_value, clamped)))
+            },
+            CommandKey::WritePropAt => {
+                let prop_field = Self::get_prop_index(&mut bytes)?;
+                let offset = Self::get_offset(&mut bytes);
+                let total = Self::get_offset(&mut bytes);
+                let len = *bytes.next().ok_or(CommandError::MissingArgument)? as usize;
+                let payload = bytes.as_slice().get(..len).ok_or(CommandError::InvalidData)?;
+                let mut chunk = FieldValue::new(P::get_entry(prop_field));
+                chunk.set_bytes(payload);
+                Ok(Command::WritePropAt((prop_field, offset, total, chunk)))
             },
+            CommandKey::EditList => {
+                let prop_field = Self::get_prop_index(&mut bytes)?;
+                let key = bytes.next().and_then(|b| ListEditKey::try_from(*b).ok())
+                    .ok_or(CommandError::InvalidData)?;
+                let index = bytes.next().copied().ok_or(CommandError::MissingArgument);
+                let len = bytes.next().copied().ok_or(CommandError::MissingArgument);
+                let item = || {
+                    let payload = bytes.as_slice().get(..len? as usize).ok_or(CommandError::InvalidData)?;
+                    FieldValue::item_from_message(P::get_entry(prop_field), payload)
+                };
+                let edit = match key {
+                    ListEditKey::Append => ListEdit::Append(item()?),
+                    ListEditKey::Replace => ListEdit::Replace(index?, item()?),
+                    ListEditKey::Remove => ListEdit::Remove(index?),
+                    ListEditKey::Clear => ListEdit::Clear,
+                };
+                Ok(Command::EditList((prop_field, edit)))
+            },
+            CommandKey::ReadRow => {
+                let prop_field = Self::get_table_index(&mut bytes)?;
+                let row = *bytes.next().ok_or(CommandError::MissingArgument)?;
+                Ok(Command::ReadRow((prop_field, row, Self::get_offset(&mut bytes))))
+            },
+            CommandKey::WriteCell => {
+                let prop_field = Self::get_table_index(&mut bytes)?;
+                let row = *bytes.next().ok_or(CommandError::MissingArgument)?;
+                let column = *bytes.next().ok_or(CommandError::MissingArgument)?;
+                let desc = P::get_entry(prop_field).columns().get(column as usize)
+                    .ok_or(CommandError::InvalidData)?;
+                let offset = Self::get_offset(&mut bytes);
+                let len = *bytes.next().ok_or(CommandError::MissingArgument)? as usize;
+                let payload = bytes.as_slice().get(..len).ok_or(CommandError::InvalidData)?;
+                let mut chunk = FieldValue::new(desc);
+                chunk.set_bytes(payload);
+                Ok(Command::WriteCell((prop_field, row, column, offset, chunk)))
+            },
+            CommandKey::DeleteRow => {
+                let prop_field = Self::get_table_index(&mut bytes)?;
+                let row = *bytes.next().ok_or(CommandError::MissingArgument)?;
+                Ok(Command::DeleteRow((prop_field, row)))
+            },
+            CommandKey::ClearSecret => {
+                let prop_field = Self::get_prop_index(&mut bytes)?;
+                match P::get_entry(prop_field).variant {
+                    EntryVariant::Field(ValueType::Secret) => Ok(Command::ClearSecret(prop_field)),
+                    _ => Err(CommandError::NotSupported),
+                }
+            },
+            CommandKey::Transaction => {
+                let key = bytes.next().and_then(|b| TransactionKey::try_from(*b).ok())
+                    .ok_or(CommandError::InvalidData)?;
+                let step = match key {
+                    TransactionKey::Begin => TransactionStep::Begin,
+                    TransactionKey::Commit => TransactionStep::Commit,
+                    TransactionKey::CommitPending => {
+                        let seconds = Self::get_offset(&mut bytes);
+                        if seconds == 0 {
+                            return Err(CommandError::InvalidData);
+                        }
+                        TransactionStep::CommitPending(Duration::from_secs(seconds as u64))
+                    },
+                    TransactionKey::Confirm => TransactionStep::Confirm,
+                    TransactionKey::Abort => TransactionStep::Abort,
+                };
+                Ok(Command::Transaction(step))
+            },
+            CommandKey::ResetProp => Ok(Command::ResetProp(Self::get_prop_index(&mut bytes)?)),
+            CommandKey::FactoryReset => Ok(Command::FactoryReset),
             CommandKey::ReadInfo => {
-                Ok(Command::ReadInfo(Self::get_info_index(&mut bytes)?))
+                let info_field = Self::get_info_index(&mut bytes)?;
+                Ok(Command::ReadInfo((info_field, Self::get_offset(&mut bytes))))
             },
             CommandKey::WriteInfo => {
                 let info_field = Self::get_info_index(&mut bytes)?;
                 let payload = Self::get_payload(&mut bytes)?;
                 let desc = I::get_entry(info_field);
-                let field_value = FieldValue::from_message(desc, payload);
+                let field_value = FieldValue::from_message(desc, payload)?;
                 Ok(Command::WriteInfo((info_field, field_value)))
             },
             CommandKey::Query => {
@@ -165,27 +365,39 @@ impl <A: ActionIndex, P: PropIndex, I: InfoIndex, S: SectionIndex> Command<A, P,
                     QueryTargetKey::Field => Ok(QueryTarget::Field),
                     QueryTargetKey::Help => Ok(QueryTarget::Help),
                     QueryTargetKey::Icon => Ok(QueryTarget::Icon),
+                    QueryTargetKey::Id => Ok(QueryTarget::Id),
+                    QueryTargetKey::Format => Ok(QueryTarget::Format),
+                    QueryTargetKey::Pattern => Ok(QueryTarget::Pattern),
+                    QueryTargetKey::Column => bytes.next()
+                        .map(|column| QueryTarget::Column(*column))
+                        .ok_or(CommandError::MissingArgument),
                     QueryTargetKey::Option => {
                         // let entry = entry_index.get_entry();
                         // let Constraints::Values(ValueConstraints{value_provider, ..}) = &entry.constraints else {
                         //     return Err(CommandError::NotSupported)
                         // };
                         // TODO: Replace with .next_chunk when stable
-                        let Some(index_bytes) = bytes.next().map(|b| bytes.next().map(|b2| [*b, *b2])).flatten() else {
+                        let Some(index_bytes) = bytes.next().and_then(|b| bytes.next().map(|b2| [*b, *b2])) else {
                             return Err(CommandError::MissingArgument)
                         };
                         let option_index: u16 = u16::from_le_bytes(index_bytes);
                         Ok(QueryTarget::Option(option_index))
                     },
+                    QueryTargetKey::Default => match entry_index {
+                        EntryIndex::Prop(_) | EntryIndex::Info(_) => Ok(QueryTarget::Default),
+                        _ => Err(CommandError::InvalidQuery)
+                    },
                     QueryTargetKey::Layout =>  match entry_index {
                         EntryIndex::Section(_si) => Ok(QueryTarget::Layout),
                         _ => Err(CommandError::InvalidQuery)
                     }
                 }?;
-                Ok(Command::Query((entry_index, target)))
+                Ok(Command::Query((entry_index, target, Self::get_offset(&mut bytes))))
             },
             CommandKey::Noop => Ok(Command::Noop),
             CommandKey::Meta => Ok(Command::Meta),
+            CommandKey::Dump => Ok(Command::Dump(Self::get_offset(&mut bytes))),
+            CommandKey::Identity => Ok(Command::Identity(Self::get_offset(&mut bytes))),
         }
     }
 
@@ -194,6 +406,15 @@ impl <A: ActionIndex, P: PropIndex, I: InfoIndex, S: SectionIndex> Command<A, P,
         P::from_byte(index).ok_or(CommandError::InvalidField)
     }
 
+    /// Reads the index of a prop that has to be a table.
+    fn get_table_index(bytes: &mut slice::Iter<'_, u8>) -> Result<P, CommandError> {
+        let prop_field = Self::get_prop_index(bytes)?;
+        match P::get_entry(prop_field).variant {
+            EntryVariant::Field(ValueType::Table) => Ok(prop_field),
+            _ => Err(CommandError::NotSupported),
+        }
+    }
+
     fn get_info_index(bytes: &mut slice::Iter<'_, u8>) -> Result<I, CommandError> {
         let index = *bytes.next().ok_or(CommandError::MissingArgument)?;
         I::from_byte(index).ok_or(CommandError::InvalidField)
@@ -209,9 +430,16 @@ impl <A: ActionIndex, P: PropIndex, I: InfoIndex, S: SectionIndex> Command<A, P,
         A::from_byte(index).ok_or(CommandError::InvalidAction)
     }
 
+    /// Reads the page offset of a command, which may be left out for the first page.
+    fn get_offset(bytes: &mut slice::Iter<'_, u8>) -> u16 {
+        let lo = bytes.next().copied().unwrap_or_default();
+        let hi = bytes.next().copied().unwrap_or_default();
+        u16::from_le_bytes([lo, hi])
+    }
+
     fn get_payload<'a>(bytes: &mut slice::Iter<'a, u8>) -> Result<&'a [u8], CommandError> {
         let trail = bytes.as_slice();
-        if trail.len() < 1 {
+        if trail.is_empty() {
             Err(CommandError::InvalidData)
         } else {
             Ok(trail)
@@ -258,4 +486,9 @@ pub enum CommandError {
     NotSupported = 10,
     Failed = 11,
     NoContent = 12,
+    ListFull = 13,
+    /// The value is well formed but breaks the constraints of the field.
+    InvalidValue = 14,
+    /// No transaction is open, it was never begun, already ended or timed out.
+    NoTransaction = 15,
 }
\ No newline at end of file
@@ -2,14 +2,33 @@ use core::{prelude::rust_2024::*};
 use num_enum::TryFromPrimitive;
 
 use crate::{
-   command::{CommandError, CommandResponse}, 
-   entry::{Constraints, EntryDesc, Field}, 
-   traits::{ActionIndex, PropIndex, InfoIndex, SectionIndex}
+   command::{Command, CommandError, CommandResponse, TransactionStep}, 
+   entry::{Constraints, EntryDesc, EntryVariant, Field}, 
+   field::FieldValue,
+   table::Record,
+   handler::{AsyncCommandHandler, CommandHandler},
+   identity::{Identity, SerialNumber},
+   store::{self, Migration, PropStore},
+   traits::{ActionIndex, PropIndex, InfoIndex, SectionIndex, fnv1a_hash},
+   values::ValueType,
 };
 use core::marker::PhantomData;
 
 pub const MESSAGE_LENGTH: usize = 64;
 pub const PAYLOAD_SIZE: usize = MESSAGE_LENGTH - 1;
+/// Size of the buffer backing a [`FieldValue`](crate::field::FieldValue), values that do
+/// not fit a single message are sent in pages.
+///
+/// It fits a full hostname or URL. Every `FieldValue`, store slot and transaction slot
+/// takes this much RAM, and it is part of the [`FileStore`](crate::store::FileStore) format.
+pub const VALUE_LENGTH: usize = 256;
+
+/// Frame tag of a section layout in the schema dump, entries are tagged with their [`EntryType`].
+pub const DUMP_LAYOUT_TAG: u8 = b'l';
+/// Frame tag of the bit names of a flags field in the schema dump.
+pub const DUMP_NAMES_TAG: u8 = b'n';
+/// Frame tag of a column of a table in the schema dump.
+pub const DUMP_COLUMN_TAG: u8 = b'r';
 
 #[repr(u8)]
 #[derive(Clone, Copy, PartialEq, Eq, Debug, TryFromPrimitive, strum::EnumString)]
@@ -29,7 +48,12 @@ pub enum QueryTargetKey {
     Icon = b'i',
     Help = b'h',
     Layout = b'l',
-    Option = b'o'
+    Option = b'o',
+    Id = b'k',
+    Format = b'n',
+    Column = b'c',
+    Pattern = b'x',
+    Default = b'd',
 }
 
 #[derive(Debug)]
@@ -39,6 +63,17 @@ pub enum QueryTarget {
     Help,
     Layout,
     Option(u16),
+    Id,
+    /// Number format (11 bytes), the inclusive bounds (8 bytes each), the maximum number
+    /// of items of lists (1 byte), the accepted characters of text (1 byte) and the unit
+    /// of numeric values.
+    Format,
+    /// Column of a table, answered like a `Field` query of the column entry.
+    Column(u8),
+    /// Pattern of text values, see [`crate::text`].
+    Pattern,
+    /// Default value of a prop or info field, sent like the value of a read.
+    Default,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -59,6 +94,15 @@ impl <AI: ActionIndex, PI: PropIndex, II: InfoIndex, SI: SectionIndex> EntryInde
             EntryIndex::Section(si) => si.get_entry(),
         }
     }
+
+    pub fn id(self) -> u32 {
+        match self {
+            EntryIndex::Action(ai) => ai.id(),
+            EntryIndex::Prop(pi) => pi.id(),
+            EntryIndex::Info(ii) => ii.id(),
+            EntryIndex::Section(si) => si.id(),
+        }
+    }
 }
 
 pub struct Config<
@@ -69,6 +113,10 @@ pub struct Config<
     AI: ActionIndex
 > {
     pub layout: [(SI, Field<PI, II>); L],
+    /// Schema version of the stored prop values, see [`Config::migrate`].
+    pub version: u16,
+    /// Product and firmware the config belongs to, see [`Config::with_identity`].
+    pub identity: Option<Identity>,
     _field_index: PhantomData<PI>,
     _status_index: PhantomData<II>,
     _action_index: PhantomData<AI>
@@ -78,104 +126,192 @@ pub struct Config<
 
 impl <'s: 'static, const L: usize, S: SectionIndex, P: PropIndex, I: InfoIndex, A: ActionIndex>  Config<L, S, P, I, A> {
 
-    const PROTO_VERSION: u8 = 1;
+    const PROTO_VERSION: u8 = 2;
 
     pub const fn new(
             layout: [(S, Field<P, I>); L]) -> Self {
         Self {
             layout,
+            version: 1,
+            identity: None,
             _field_index: PhantomData,
             _status_index: PhantomData,
             _action_index: PhantomData
         }
     }
 
-    // async fn _parse_command<'a, CH: CommandHandler<PI, II, AI>>(&'s self, mut bytes: slice::Iter<'a, u8>, handler: &mut CH) -> Result<CommandResponse, CommandError> {
-        
-    //     let command = bytes.next()
-    //         .and_then(|b| CommandKey::try_from(*b).ok())
-    //         .ok_or(CommandError::InvalidCommand)?;
-
-    //     match command {
-    //         CommandKey::Action =>{
-    //             let ai: AI = get_action(&mut bytes)?;
-    //             handler.do_action(ai).await?;
-    //             Ok(CommandResponse::OK)
-    //         },
-    //         CommandKey::ReadProp => {
-    //             let prop_field = get_prop_field(&mut bytes)?;
-    //             handler.read_prop(prop_field).await.map(CommandResponse::from_field_value)
-    //         },
-    //         CommandKey::WriteProp => { 
-    //             let prop_field = get_prop_field(&mut bytes)?;
-    //             let payload = get_payload(&mut bytes)?;
-    //             let desc = self.prop_field(prop_field);
-    //             let field_value = FieldValue::from_message(desc, payload);
-    //             handler.write_prop(prop_field, field_value).await.and(Ok(CommandResponse::OK))
-    //         },
-    //         CommandKey::ReadInfo => {
-    //             let info_field = get_info_field(&mut bytes)?;
-    //             handler.read_info(info_field).await.map(CommandResponse::from_field_value)
-    //         },
-    //         CommandKey::WriteInfo => {
-    //             let info_field = get_info_field(&mut bytes)?;
-    //             let payload = get_payload(&mut bytes)?;
-    //             let desc = self.info_field(info_field);
-    //             let field_value = FieldValue::from_message(desc, payload);
-    //             handler.write_info(info_field, field_value).await.and(Ok(CommandResponse::OK))
-    //         },
-    //         CommandKey::Query => {
-    //             let entry_type = Self::get_entry_type(&mut bytes)?;
-    //             debug!("Entry type: {:?}", entry_type);
-    //             let entry_index = Self::get_entry_index(&mut bytes, entry_type)?;
-    //             debug!("Entry index: {:?}", entry_index);
-    //             let query_prop = Self::get_query_prop(&mut bytes)?;
-    //             debug!("Query prop: {:?}", query_prop);
-
-    //             info!("Entry type: {:?}, index: {:?}, prop: {:?}", entry_type, entry_index, query_prop);
-
-    //             let entry = match entry_index {
-    //                 EntryIndex::Action(ai) => self.action_name(ai),
-    //                 EntryIndex::Prop(ci) => self.prop_field(ci),
-    //                 EntryIndex::Info(ii) => self.info_field(ii),
-    //                 EntryIndex::Section(si) => self.section_desc(si)
-    //             };
-                
-    //             match query_prop {
-    //                 QueryTargetKey::Field => Ok(entry.into()),
-    //                 QueryTargetKey::Help => entry.help.ok_or(CommandError::NoContent).map(Into::into),
-    //                 QueryTargetKey::Icon => entry.icon.ok_or(CommandError::NoContent).map(Into::into),
-    //                 QueryTargetKey::Option => {
-    //                     let Constraints::Values(ValueConstraints{value_provider, ..}) = &entry.constraints else {
-    //                         return Err(CommandError::NotSupported)
-    //                     };
-    //                     // TODO: Replace with .next_chunk when stable
-    //                     let Some(index_bytes) = bytes.next().map(|b| bytes.next().map(|b2| [*b, *b2])).flatten() else {
-    //                         return Err(CommandError::MissingArgument)
-    //                     };
-    //                     let option_index: u16 = u16::from_le_bytes(index_bytes);
-    //                     value_provider.get(option_index as usize).ok_or(CommandError::InvalidOption).map(|s| (*s).into())
-    //                 },
-    //                 QueryTargetKey::Layout => match entry_index {
-    //                     EntryIndex::Section(si) => Ok(self.section_layout(si)),
-    //                     _ => Err(CommandError::InvalidQuery)
-    //                 }
-    //             }
-    //         },
-    //         CommandKey::Noop => {
-    //             handler.noop().await;
-    //             Ok(CommandResponse::OK)
-    //         },
-    //         CommandKey::Meta => {
-    //             Ok(self.info_response())
-    //         },
-    //     }
-    // }
-
-    // pub async fn parse_command<'a, CH: CommandHandler<PI, II, AI>>(&'s self, bytes: &'a [u8], handler: &mut CH) -> CommandResponse {
-    //     let bytes = bytes.into_iter();
-    //     self._parse_command(bytes, handler).await.unwrap_or_else(CommandResponse::error)
-    // }
+    pub const fn with_version(self, version: u16) -> Self {
+        Self { version, ..self }
+    }
+
+    pub const fn with_identity(self, identity: Identity) -> Self {
+        Self { identity: Some(identity), ..self }
+    }
+
+    /// Identity of the device, with the serial number provided by the handler at runtime.
+    pub fn identity(&'s self, serial: Option<SerialNumber>) -> impl Iterator<Item = u8> {
+        self.identity.unwrap_or(Identity::UNKNOWN).bytes(serial)
+    }
+
+    /// Brings the values in `store` up to the schema version of this config, running
+    /// the `migrations` registered for each version in between.
+    ///
+    /// Must be called on boot before any value is read from `store`.
+    pub fn migrate<PS: PropStore>(&'s self, store: &mut PS, migrations: &[Migration]) -> Result<(), CommandError> {
+        store::migrate(store, self.version, migrations)
+    }
+
+    /// Parses `bytes` as a command and produces the response to send back.
+    ///
+    /// `Meta`, `Query`, `Dump` and `Identity` commands are answered from the config itself,
+    /// everything else is forwarded to `handler`.
+    pub fn dispatch<H: CommandHandler<P, I, A>>(&'s self, bytes: &[u8], handler: &mut H) -> CommandResponse {
+        self.dispatch_command(bytes, handler).into()
+    }
+
+    fn dispatch_command<H: CommandHandler<P, I, A>>(&'s self, bytes: &[u8], handler: &mut H) -> Result<CommandResponse, CommandError> {
+        match Command::<A, P, I, S>::from_bytes(bytes)? {
+            Command::ReadProp((prop, offset)) => handler.read_prop(prop)
+                .map(|value| CommandResponse::from_field_value(value, offset)),
+            Command::WriteProp((prop, value, clamped)) => {
+                let echo = CommandResponse::write_echo(&value, clamped);
+                handler.write_prop(prop, value).map(|_| echo)
+            },
+            Command::WritePropAt((prop, offset, total, chunk)) => {
+                let buffer = handler.chunk_buffer().ok_or(CommandError::NotSupported)?;
+                match buffer.push(prop, offset, total, chunk.get_bytes())? {
+                    Some(value) => {
+                        let echo = CommandResponse::write_echo(&value, false);
+                        handler.write_prop(prop, value).map(|_| echo)
+                    },
+                    None => Ok(CommandResponse::ok()),
+                }
+            },
+            Command::EditList((prop, edit)) => {
+                let mut value = handler.read_prop(prop)?;
+                value.edit_items(edit)?;
+                handler.write_prop(prop, value).map(|_| CommandResponse::ok())
+            },
+            Command::ReadRow((prop, row, offset)) => handler.read_row(prop, row)
+                .map(|record| CommandResponse::paged(record.message_payload(), offset)),
+            Command::WriteCell((prop, row, column, offset, chunk)) => {
+                let mut record = match handler.read_row(prop, row) {
+                    Err(CommandError::NoContent) => Record::new(prop.get_entry()),
+                    record => record?,
+                };
+                record.write_cell(column as usize, offset as usize, chunk.get_bytes())?;
+                handler.write_row(prop, row, record).map(|_| CommandResponse::ok())
+            },
+            Command::DeleteRow((prop, row)) => handler.delete_row(prop, row).map(|_| CommandResponse::ok()),
+            Command::ClearSecret(prop) => handler.write_prop(prop, FieldValue::new(prop.get_entry())).map(|_| CommandResponse::ok()),
+            Command::Transaction(step) => match step {
+                TransactionStep::Begin => handler.begin_transaction(),
+                TransactionStep::Commit => handler.commit_transaction(),
+                TransactionStep::CommitPending(within) => handler.commit_transaction_pending(within),
+                TransactionStep::Confirm => handler.confirm_transaction(),
+                TransactionStep::Abort => handler.abort_transaction(),
+            }.map(|_| CommandResponse::ok()),
+            Command::ResetProp(prop) => self.reset_prop(prop, handler).map(|_| CommandResponse::ok()),
+            Command::FactoryReset => handler.factory_reset().map(|_| CommandResponse::ok()),
+            Command::ReadInfo((info, offset)) => handler.read_info(info)
+                .map(|value| CommandResponse::from_field_value(value, offset)),
+            Command::WriteInfo((info, value)) => handler.write_info(info, value).map(|_| CommandResponse::ok()),
+            Command::Query((entry_index, target, offset)) => self.handle_query(entry_index, target, offset),
+            Command::Action(action) => handler.do_action(action).map(|_| CommandResponse::ok()),
+            Command::Meta => Ok(self.handle_meta()),
+            Command::Dump(offset) => Ok(CommandResponse::paged(self.dump(), offset)),
+            Command::Identity(offset) => Ok(CommandResponse::paged(self.identity(handler.serial_number()), offset)),
+            Command::Noop => {
+                handler.noop();
+                Ok(CommandResponse::ok())
+            },
+        }
+    }
+
+    /// Async variant of [`Config::dispatch`] for handlers implementing [`AsyncCommandHandler`].
+    pub async fn dispatch_async<H: AsyncCommandHandler<P, I, A>>(&'s self, bytes: &[u8], handler: &mut H) -> CommandResponse {
+        self.dispatch_command_async(bytes, handler).await.into()
+    }
+
+    async fn dispatch_command_async<H: AsyncCommandHandler<P, I, A>>(&'s self, bytes: &[u8], handler: &mut H) -> Result<CommandResponse, CommandError> {
+        match Command::<A, P, I, S>::from_bytes(bytes)? {
+            Command::ReadProp((prop, offset)) => handler.read_prop(prop).await
+                .map(|value| CommandResponse::from_field_value(value, offset)),
+            Command::WriteProp((prop, value, clamped)) => {
+                let echo = CommandResponse::write_echo(&value, clamped);
+                handler.write_prop(prop, value).await.map(|_| echo)
+            },
+            Command::WritePropAt((prop, offset, total, chunk)) => {
+                let buffer = handler.chunk_buffer().ok_or(CommandError::NotSupported)?;
+                match buffer.push(prop, offset, total, chunk.get_bytes())? {
+                    Some(value) => {
+                        let echo = CommandResponse::write_echo(&value, false);
+                        handler.write_prop(prop, value).await.map(|_| echo)
+                    },
+                    None => Ok(CommandResponse::ok()),
+                }
+            },
+            Command::EditList((prop, edit)) => {
+                let mut value = handler.read_prop(prop).await?;
+                value.edit_items(edit)?;
+                handler.write_prop(prop, value).await.map(|_| CommandResponse::ok())
+            },
+            Command::ReadRow((prop, row, offset)) => handler.read_row(prop, row).await
+                .map(|record| CommandResponse::paged(record.message_payload(), offset)),
+            Command::WriteCell((prop, row, column, offset, chunk)) => {
+                let mut record = match handler.read_row(prop, row).await {
+                    Err(CommandError::NoContent) => Record::new(prop.get_entry()),
+                    record => record?,
+                };
+                record.write_cell(column as usize, offset as usize, chunk.get_bytes())?;
+                handler.write_row(prop, row, record).await.map(|_| CommandResponse::ok())
+            },
+            Command::DeleteRow((prop, row)) => handler.delete_row(prop, row).await.map(|_| CommandResponse::ok()),
+            Command::ClearSecret(prop) => handler.write_prop(prop, FieldValue::new(prop.get_entry())).await.map(|_| CommandResponse::ok()),
+            Command::Transaction(step) => match step {
+                TransactionStep::Begin => handler.begin_transaction().await,
+                TransactionStep::Commit => handler.commit_transaction().await,
+                TransactionStep::CommitPending(within) => handler.commit_transaction_pending(within).await,
+                TransactionStep::Confirm => handler.confirm_transaction().await,
+                TransactionStep::Abort => handler.abort_transaction().await,
+            }.map(|_| CommandResponse::ok()),
+            Command::ResetProp(prop) => self.reset_prop_async(prop, handler).await.map(|_| CommandResponse::ok()),
+            Command::FactoryReset => handler.factory_reset().await.map(|_| CommandResponse::ok()),
+            Command::ReadInfo((info, offset)) => handler.read_info(info).await
+                .map(|value| CommandResponse::from_field_value(value, offset)),
+            Command::WriteInfo((info, value)) => handler.write_info(info, value).await.map(|_| CommandResponse::ok()),
+            Command::Query((entry_index, target, offset)) => self.handle_query(entry_index, target, offset),
+            Command::Action(action) => handler.do_action(action).await.map(|_| CommandResponse::ok()),
+            Command::Meta => Ok(self.handle_meta()),
+            Command::Dump(offset) => Ok(CommandResponse::paged(self.dump(), offset)),
+            Command::Identity(offset) => Ok(CommandResponse::paged(self.identity(handler.serial_number().await), offset)),
+            Command::Noop => {
+                handler.noop().await;
+                Ok(CommandResponse::ok())
+            },
+        }
+    }
+
+    /// Writes the default of `prop`, or deletes every row of a table.
+    fn reset_prop<H: CommandHandler<P, I, A>>(&'s self, prop: P, handler: &mut H) -> Result<(), CommandError> {
+        if prop.get_entry().variant != EntryVariant::Field(ValueType::Table) {
+            return handler.write_prop(prop, FieldValue::from_default(prop.get_entry()));
+        }
+        for row in (0..handler.read_prop(prop)?.row_count()).rev() {
+            handler.delete_row(prop, row)?;
+        }
+        Ok(())
+    }
+
+    async fn reset_prop_async<H: AsyncCommandHandler<P, I, A>>(&'s self, prop: P, handler: &mut H) -> Result<(), CommandError> {
+        if prop.get_entry().variant != EntryVariant::Field(ValueType::Table) {
+            return handler.write_prop(prop, FieldValue::from_default(prop.get_entry())).await;
+        }
+        for row in (0..handler.read_prop(prop).await?.row_count()).rev() {
+            handler.delete_row(prop, row).await?;
+        }
+        Ok(())
+    }
 
     pub fn handle_meta(&'s self) -> CommandResponse {
         let mut res = CommandResponse::new();
@@ -194,30 +330,102 @@ impl <'s: 'static, const L: usize, S: SectionIndex, P: PropIndex, I: InfoIndex,
         // Action count (1 byte)
         res.push(A::count() as u8);
 
+        // Schema version (2 bytes)
+        res.extend(self.version.to_le_bytes());
+
+        // Schema fingerprint (4 bytes)
+        res.extend(self.fingerprint().to_le_bytes());
+
         res
     }
 
-    pub fn handle_query(&'s self, entry_index: EntryIndex<A, P, I, S>, target: QueryTarget) -> Result<CommandResponse, CommandError> {
+    /// Hash over everything a host learns about the schema: the [`dump`](Self::dump) and
+    /// the option values. It only changes with the schema definition, so hosts can use it
+    /// to cache the device description across sessions. It is never `0`, which hosts
+    /// read as no fingerprint.
+    ///
+    /// Option values come from [`OptionValueProvider`](crate::entry::options::OptionValueProvider)s,
+    /// so the hash is computed when the host asks for it rather than at compile time.
+    pub fn fingerprint(&'s self) -> u32 {
+        let options = (0..P::count()).filter_map(|i| P::from_byte(i as u8)).map(|pi| pi.get_entry())
+            .chain((0..I::count()).filter_map(|i| I::from_byte(i as u8)).map(|ii| ii.get_entry()))
+            .filter_map(|entry| match &entry.constraints {
+                Constraints::Values(constr) => Some(constr.value_provider),
+                _ => None,
+            })
+            .flat_map(|provider| (0..provider.len()).filter_map(|i| provider.get(i)))
+            .flat_map(|value| value.bytes().chain([0]));
+        fnv1a_hash([Self::PROTO_VERSION].into_iter().chain(self.dump()).chain(options)).max(1)
+    }
+
+    pub fn handle_query(&'s self, entry_index: EntryIndex<A, P, I, S>, target: QueryTarget, offset: u16) -> Result<CommandResponse, CommandError> {
         let entry = entry_index.get_entry();
+        let text = |text: &'static str| CommandResponse::paged(text.bytes(), offset);
         use QueryTarget::{*};
         match target {
             Field => Ok(entry.into()),
-            Help => entry.help.ok_or(CommandError::NoContent).map(Into::into),
-            Icon => entry.icon.ok_or(CommandError::NoContent).map(Into::into),
+            Help => entry.help.ok_or(CommandError::NoContent).map(text),
+            Icon => entry.icon.ok_or(CommandError::NoContent).map(text),
             Option(option_index) => {
                 let Constraints::Values(constr) = &entry.constraints else {
                     return Err(CommandError::NotSupported)
                 };
                 constr.value_provider.get(option_index as usize)
-                    .ok_or(CommandError::InvalidOption).map(|s| (*s).into())
+                    .ok_or(CommandError::InvalidOption).map(text)
             },
             Layout => match entry_index {
-                        EntryIndex::Section(si) => Ok( self.section_layout(si)),
+                        EntryIndex::Section(si) => Ok( self.section_layout(si, offset)),
                         _ => Err(CommandError::InvalidQuery)
-            }
+            },
+            Id => Ok(CommandResponse::from_payload(entry_index.id().to_le_bytes())),
+            Format => Ok(CommandResponse::paged(number_format(entry).chain(entry.format.unit.unwrap_or_default().bytes()), offset)),
+            Column(column) => entry.columns().get(column as usize).ok_or(CommandError::InvalidQuery).map(|column| column.into()),
+            Pattern => entry.text.pattern.ok_or(CommandError::NoContent).map(text),
+            Default => Ok(CommandResponse::from_field_value(FieldValue::from_default(entry), offset)),
         }
     }
 
+    /// Serializes the whole schema as a sequence of frames, sent in pages by the `Dump`
+    /// command so a host can load a device in a few round trips. Every frame is a tag,
+    /// the body length (2 bytes) and the body:
+    ///
+    /// - entries are tagged with their [`EntryType`], the body holds the index, id (4 bytes),
+    ///   flags, variant, constraints (8 bytes), name (1 byte length), help (2 byte length),
+    ///   icon (1 byte length), the number format, bounds, item limit and accepted characters
+    ///   as returned by a `Format` query, the unit (1 byte length) and the pattern (1 byte length)
+    /// - layouts are tagged with [`DUMP_LAYOUT_TAG`], the body holds the section index
+    ///   followed by the fields as returned by a `Layout` query
+    /// - flags fields are followed by a frame tagged with [`DUMP_NAMES_TAG`], the body holds
+    ///   the entry type, index and the name of every bit (1 byte length)
+    /// - tables are followed by a frame tagged with [`DUMP_COLUMN_TAG`] for every column, the
+    ///   body holds the table index followed by the body of an entry frame of the column,
+    ///   with the column index and an id of `0`
+    ///
+    /// Sections come first, each followed by its layout, then props, info fields and actions.
+    pub fn dump(&'s self) -> impl Iterator<Item = u8> + 's {
+        let sections = (0..S::count()).filter_map(|i| S::from_byte(i as u8)).flat_map(move |si| {
+            dump_entry(EntryType::Section, si.as_index(), si.id(), si.get_entry())
+                .chain(self.dump_layout(si))
+        });
+        let props = (0..P::count()).filter_map(|i| P::from_byte(i as u8))
+            .flat_map(|pi| dump_entry(EntryType::Prop, pi.as_index(), pi.id(), pi.get_entry())
+                .chain(dump_names(EntryType::Prop, pi.as_index(), pi.get_entry()))
+                .chain(dump_columns(pi.as_index(), pi.get_entry())));
+        let infos = (0..I::count()).filter_map(|i| I::from_byte(i as u8))
+            .flat_map(|ii| dump_entry(EntryType::Info, ii.as_index(), ii.id(), ii.get_entry())
+                .chain(dump_names(EntryType::Info, ii.as_index(), ii.get_entry())));
+        let actions = (0..A::count()).filter_map(|i| A::from_byte(i as u8))
+            .flat_map(|ai| dump_entry(EntryType::Action, ai.as_index(), ai.id(), ai.get_entry()));
+        sections.chain(props).chain(infos).chain(actions)
+    }
+
+    fn dump_layout(&'s self, section: S) -> impl Iterator<Item = u8> + 's {
+        let fields = self.layout.iter().filter(move |(si, _)| *si == section);
+        let len = 1 + 2 * fields.clone().count() as u16;
+        let [len_lo, len_hi] = len.to_le_bytes();
+        [DUMP_LAYOUT_TAG, len_lo, len_hi, section.as_index() as u8].into_iter().chain(fields.flat_map(|(_, field)| field.bits()))
+    }
+
     pub fn prop_field(&'s self, index: P) -> &'s EntryDesc {
         index.get_entry()
     }
@@ -234,12 +442,578 @@ impl <'s: 'static, const L: usize, S: SectionIndex, P: PropIndex, I: InfoIndex,
         index.get_entry()
     }
 
-    pub fn section_layout(&'s self, section: S) -> CommandResponse {
-        let mut res = CommandResponse::new();
-        self.layout.iter()
+    pub fn section_layout(&'s self, section: S, offset: u16) -> CommandResponse {
+        let fields = self.layout.iter()
             .filter(|(si, _)| *si == section)
-            .take(31)
-            .for_each(|(_, field)| res.extend(field.bits()));
-        res
+            .flat_map(|(_, field)| field.bits());
+        CommandResponse::paged(fields, offset)
+    }
+}
+
+fn dump_entry(entry_type: EntryType, index: usize, id: u32, entry: &'static EntryDesc) -> impl Iterator<Item = u8> {
+    let help = entry.help.unwrap_or_default();
+    let icon = entry.icon.unwrap_or_default();
+    let unit = entry.format.unit.unwrap_or_default();
+    let pattern = entry.text.pattern.unwrap_or_default();
+    let len = entry_len(entry);
+    let mut head = [0u8; 19];
+    head[0] = entry_type as u8;
+    head[1..3].copy_from_slice(&(len as u16).to_le_bytes());
+    head[3] = index as u8;
+    head[4..8].copy_from_slice(&id.to_le_bytes());
+    head[8] = entry.flags().bits();
+    head[9] = entry.variant.bits();
+    head[10..18].copy_from_slice(&entry.constraints.bits());
+    head[18] = entry.name.len() as u8;
+    head.into_iter()
+        .chain(entry.name.bytes())
+        .chain((help.len() as u16).to_le_bytes())
+        .chain(help.bytes())
+        .chain([icon.len() as u8])
+        .chain(icon.bytes())
+        .chain(number_format(entry))
+        .chain([unit.len() as u8])
+        .chain(unit.bytes())
+        .chain([pattern.len() as u8])
+        .chain(pattern.bytes())
+}
+
+/// Body length of the dump frame of `entry`.
+fn entry_len(entry: &EntryDesc) -> usize {
+    let help = entry.help.unwrap_or_default();
+    let icon = entry.icon.unwrap_or_default();
+    let unit = entry.format.unit.unwrap_or_default();
+    let pattern = entry.text.pattern.unwrap_or_default();
+    16 + entry.name.len() + 2 + help.len() + 1 + icon.len() + 29 + 1 + unit.len() + 1 + pattern.len()
+}
+
+fn dump_columns(index: usize, entry: &'static EntryDesc) -> impl Iterator<Item = u8> {
+    entry.columns().iter().enumerate().flat_map(move |(column, desc)| {
+        let [len_lo, len_hi] = (1 + entry_len(desc) as u16).to_le_bytes();
+        [DUMP_COLUMN_TAG, len_lo, len_hi, index as u8].into_iter()
+            .chain(dump_entry(EntryType::Prop, column, 0, desc).skip(3))
+    })
+}
+
+fn dump_names(entry_type: EntryType, index: usize, entry: &'static EntryDesc) -> impl Iterator<Item = u8> {
+    let names = match (&entry.variant, &entry.constraints) {
+        (EntryVariant::Field(ValueType::Flags), Constraints::Values(constr)) => Some(constr.value_provider),
+        _ => None,
+    };
+    names.into_iter().flat_map(move |names| {
+        let names = (0..names.len().min(64)).filter_map(|i| names.get(i));
+        let len = 2 + names.clone().map(|name| 1 + name.len()).sum::<usize>();
+        let [len_lo, len_hi] = (len as u16).to_le_bytes();
+        [DUMP_NAMES_TAG, len_lo, len_hi, entry_type as u8, index as u8].into_iter()
+            .chain(names.flat_map(|name| [name.len() as u8].into_iter().chain(name.bytes())))
+    })
+}
+
+/// Number format of `entry` followed by its inclusive bounds, the item limit of lists and
+/// the accepted characters of text.
+fn number_format(entry: &EntryDesc) -> impl Iterator<Item = u8> {
+    let (min, max) = entry.bounds();
+    entry.format.bits().into_iter()
+        .chain(min.to_le_bytes())
+        .chain(max.to_le_bytes())
+        .chain([entry.max_items, entry.text.chars.bits()])
+}
+
+#[cfg(all(test, feature = "macros"))]
+pub(crate) mod test {
+    extern crate std;
+    use std::{thread, time::Duration, vec::Vec};
+    use futures::{channel::oneshot, executor::block_on};
+
+    use crate::command::CommandError;
+    use crate::handler::AsyncCommandHandler;
+    use crate::prelude::*;
+
+    crate::elytra!(CONF: TestConf {
+        identity: Identity::new("Elytra", "Test", "1.2.3").with_hardware("B"),
+        info: InfoField {
+            Uptime: integer("Uptime").readonly()
+        },
+        props: PropField {
+            Level: integer("Level").writable().with_range(0..100),
+            Energy: decimal("Energy", 2).readonly().with_unit("kWh")
+        },
+        sections: Section {
+            Main: section("Main")
+        },
+        actions: Action { },
+        layout: {
+            Section::Main: [
+                Field::Prop(PropField::Level),
+                Field::Info(InfoField::Uptime)
+            ]
+        }
+    });
+
+    #[derive(Default)]
+    struct TestHandler {
+        level: i64,
+        noops: usize,
+    }
+
+    impl CommandHandler<PropField, InfoField, Action> for TestHandler {
+        fn read_prop(&mut self, prop: PropField) -> Result<FieldValue, CommandError> {
+            Ok(FieldValue::new(prop.get_entry()).with_integer(self.level))
+        }
+
+        fn write_prop(&mut self, _prop: PropField, value: FieldValue) -> Result<(), CommandError> {
+            self.level = value.get_integer();
+            Ok(())
+        }
+
+        fn serial_number(&mut self) -> Option<SerialNumber> {
+            Some(SerialNumber::text("SN-0042"))
+        }
+
+        fn noop(&mut self) {
+            self.noops += 1;
+        }
+    }
+
+    #[test]
+    fn dispatch_meta_and_query() {
+        let mut handler = TestHandler::default();
+        let res = CONF.dispatch(b"m", &mut handler);
+        assert_eq!(&[1, TestConf::PROTO_VERSION, 1, 2, 1, 0, 1, 0], &res.as_bytes()[0..8]);
+        assert_eq!(CONF.fingerprint().to_le_bytes(), res.as_bytes()[8..12]);
+
+        let res = CONF.dispatch(&[b'q', b's', 0, b'l'], &mut handler);
+        assert_eq!(&[1, b'c', 0, b'i', 0], &res.as_bytes()[0..5]);
+
+        let res = CONF.dispatch(&[b'q', b'c', 0, b'k'], &mut handler);
+        assert_eq!(PropField::Level.id().to_le_bytes(), res.as_bytes()[1..5]);
+
+        let res = CONF.dispatch(&[b'q', b'c', 1, b'n'], &mut handler);
+        assert_eq!(&[1, 2, 10, 0], &res.as_bytes()[0..4]);
+        assert_eq!(i64::MIN.to_le_bytes(), res.as_bytes()[12..20]);
+        assert_eq!(b"\x3fkWh\0", &res.as_bytes()[29..34]);
+    }
+
+    #[test]
+    fn dispatch_identity() {
+        let mut handler = TestHandler::default();
+        let res = CONF.dispatch(b"I", &mut handler);
+        let expected = b"\x01\x06Elytra\x04Test\x051.2.3\x01B\x01\x07SN-0042\x00";
+        assert_eq!(expected, &res.as_bytes()[..expected.len()]);
+    }
+
+    #[test]
+    fn dispatch_props_to_handler() {
+        let mut handler = TestHandler::default();
+        // Writes echo the stored value after a flag telling it was clamped
+        let res = CONF.dispatch(&[b'w', 0, 250, 0, 0, 0, 0, 0, 0, 0], &mut handler);
+        assert_eq!(&[1, 1, 100, 0], &res.as_bytes()[0..4]);
+        assert_eq!(100, handler.level);
+        let res = CONF.dispatch(&[b'w', 0, 42, 0, 0, 0, 0, 0, 0, 0], &mut handler);
+        assert_eq!(&[1, 0, 42, 0], &res.as_bytes()[0..4]);
+        CONF.dispatch(&[b'w', 0, 100, 0, 0, 0, 0, 0, 0, 0], &mut handler);
+
+        let res = CONF.dispatch(&[b'r', 0], &mut handler);
+        assert_eq!(&[1, 100, 0], &res.as_bytes()[0..3]);
+
+        CONF.dispatch(&[0], &mut handler);
+        assert_eq!(1, handler.noops);
+    }
+
+    /// Answers reads from a "flash" that completes on another thread, so the
+    /// dispatch future has to actually suspend and be woken up again.
+    #[derive(Default)]
+    struct AsyncTestHandler {
+        level: i64,
+    }
+
+    impl AsyncCommandHandler<PropField, InfoField, Action> for AsyncTestHandler {
+        async fn read_prop(&mut self, prop: PropField) -> Result<FieldValue, CommandError> {
+            let (tx, rx) = oneshot::channel();
+            let level = self.level;
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                tx.send(level)
+            });
+            let level = rx.await.map_err(|_| CommandError::Failed)?;
+            Ok(FieldValue::new(prop.get_entry()).with_integer(level))
+        }
+
+        async fn write_prop(&mut self, _prop: PropField, value: FieldValue) -> Result<(), CommandError> {
+            self.level = value.get_integer();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn dispatch_async_on_executor() {
+        let mut handler = AsyncTestHandler::default();
+        block_on(async {
+            let res = CONF.dispatch_async(&[b'w', 0, 42, 0, 0, 0, 0, 0, 0, 0], &mut handler).await;
+            assert_eq!(&[1, 0, 42], &res.as_bytes()[0..3]);
+
+            let res = CONF.dispatch_async(&[b'r', 0], &mut handler).await;
+            assert_eq!(&[1, 42, 0], &res.as_bytes()[0..3]);
+
+            let res = CONF.dispatch_async(b"m", &mut handler).await;
+            assert_eq!(&[1, TestConf::PROTO_VERSION], &res.as_bytes()[0..2]);
+
+            let res = CONF.dispatch_async(&[b'a', 0], &mut handler).await;
+            assert_eq!(&[0, CommandError::InvalidAction as u8], &res.as_bytes()[0..2]);
+
+            let res = CONF.dispatch_async(&[b'R', 0], &mut handler).await;
+            assert_eq!(&[0, CommandError::NotSupported as u8], &res.as_bytes()[0..2]);
+        });
+    }
+
+    #[test]
+    fn dispatch_errors() {
+        let mut handler = TestHandler::default();
+        let res = CONF.dispatch(&[b'R', 0], &mut handler);
+        assert_eq!(&[0, CommandError::NotSupported as u8], &res.as_bytes()[0..2]);
+
+        let res = CONF.dispatch(&[b'r', 7], &mut handler);
+        assert_eq!(&[0, CommandError::InvalidField as u8], &res.as_bytes()[0..2]);
+
+        let res = CONF.dispatch(b"?", &mut handler);
+        assert_eq!(&[0, CommandError::InvalidCommand as u8], &res.as_bytes()[0..2]);
+    }
+
+    /// Collects all pages of the response to `command` like the host does.
+    fn read_paged<const L: usize, S: SectionIndex, P: PropIndex, I: InfoIndex, A: ActionIndex>(
+        conf: &'static Config<L, S, P, I, A>,
+        command: &[u8],
+        handler: &mut impl CommandHandler<P, I, A>,
+    ) -> Vec<u8> {
+        let mut payload = Vec::new();
+        loop {
+            let offset = (payload.len() as u16).to_le_bytes();
+            let res = conf.dispatch(&[command, &offset].concat(), handler);
+            let bytes = res.as_bytes();
+            assert!(bytes[0] == 1 || bytes[0] == 2, "error response {:?}", bytes);
+            payload.extend_from_slice(&bytes[1..]);
+            if bytes[0] == 1 {
+                return payload;
+            }
+        }
+    }
+
+    /// Config and handler shared by the tests of stored props.
+    pub(crate) mod fixture {
+        use crate::entry::EntryDesc;
+        use crate::prelude::*;
+
+        const NETWORK: [EntryDesc; 2] = [
+            prop("SSID").as_entry(),
+            secret("Password").as_entry(),
+        ];
+
+        pub(crate) const HELP: &str = "A help text that is a lot longer than what fits into a single \
+            message, so the host has to ask for it in several pages.";
+
+        pub(crate) struct NoInfo;
+        impl CommandHandler<PropField, InfoField, Action> for NoInfo {}
+
+        crate::elytra!(pub(crate) CONF: TestConf {
+            info: InfoField { },
+            props: PropField {
+                Name: prop("Name"),
+                Url: prop("Url").with_pattern("http*://*"),
+                Servers: hostname("Servers").list(8).with_default_text("ntp.se"),
+                Networks: table("Networks", &NETWORK, 2),
+                Pin: secret("Pin").with_length(4, 8),
+                Level: integer("Level").writable().with_default_integer(7)
+            },
+            sections: Section {
+                Main: section("Main").with_help(HELP)
+            },
+            actions: Action { },
+            layout: {
+                Section::Main: [
+                    Field::Prop(PropField::Name), Field::Prop(PropField::Url), Field::Prop(PropField::Servers), Field::Prop(PropField::Networks),
+                    Field::Prop(PropField::Pin), Field::Prop(PropField::Level), Field::Prop(PropField::Url), Field::Prop(PropField::Url),
+                    Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url),
+                    Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url),
+                    Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url),
+                    Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url),
+                    Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url),
+                    Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url),
+                    Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url),
+                    Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url), Field::Prop(PropField::Url)
+                ]
+            }
+        });
+    }
+
+    mod paging {
+        extern crate std;
+        use std::{string::String, vec::Vec};
+
+        use crate::command::{CommandError, CommandResponse, WriteFlags};
+        use crate::config::{DUMP_COLUMN_TAG, DUMP_LAYOUT_TAG};
+        use crate::prelude::*;
+        use crate::store::{RamStore, StoreHandler};
+
+        use super::fixture::*;
+        use super::read_paged;
+
+        #[test]
+        fn paged_help_and_layout() {
+            let mut handler = StoreHandler::new(RamStore::<4>::new(), NoInfo);
+            let res = CONF.dispatch(&[b'q', b's', 0, b'h'], &mut handler);
+            assert_eq!(2, res.as_bytes()[0]);
+
+            let help = read_paged(&CONF, &[b'q', b's', 0, b'h'], &mut handler);
+            assert_eq!(HELP.as_bytes(), &help[..HELP.len()]);
+            assert!(help[HELP.len()..].iter().all(|b| *b == 0));
+
+            let layout = read_paged(&CONF, &[b'q', b's', 0, b'l'], &mut handler);
+            assert_eq!(40, layout.chunks(2).take_while(|c| c[0] != 0).count());
+        }
+
+        #[test]
+        fn long_prop_values() {
+            let mut handler = StoreHandler::new(RamStore::<4>::new(), NoInfo);
+            let url: String = "https://example.com/".chars().chain("päth/".chars().cycle().take(180)).collect();
+
+            // Chunks have to be split at char boundaries
+            let chars: Vec<char> = url.chars().collect();
+            let total = (url.len() as u16).to_le_bytes();
+            let mut offset = 0;
+            let mut res = CommandResponse::ok();
+            for chunk in chars.chunks(20).map(String::from_iter) {
+                // The value is only written with the last chunk
+                assert_eq!(&[1, 0], &CONF.dispatch(&[b'r', 1], &mut handler).as_bytes()[0..2]);
+                let chunk = chunk.as_bytes();
+                let header = [b'p', 1, offset as u8, 0, total[0], total[1], chunk.len() as u8];
+                res = CONF.dispatch(&[&header, chunk].concat(), &mut handler);
+                assert_eq!(1, res.as_bytes()[0]);
+                offset += chunk.len();
+            }
+            // Which is echoed like a write, cut to fit the response
+            assert_eq!(WriteFlags::Cut.bits(), res.as_bytes()[1]);
+            assert_eq!(&url.as_bytes()[..62], &res.as_bytes()[2..]);
+
+            let value = read_paged(&CONF, &[b'r', 1], &mut handler);
+            assert_eq!(url.as_bytes(), &value[..url.len()]);
+
+            // Chunks can't leave gaps or continue another value
+            let res = CONF.dispatch(&[b'p', 1, 250, 0, 251, 0, 1, b'x'], &mut handler);
+            assert_eq!(&[0, CommandError::InvalidData as u8], &res.as_bytes()[0..2]);
+            CONF.dispatch(b"p\x01\0\0\x0c\0\x04http", &mut handler);
+            let res = CONF.dispatch(b"p\x01\x04\0\x0d\0\x04s://", &mut handler);
+            assert_eq!(&[0, CommandError::InvalidData as u8], &res.as_bytes()[0..2]);
+            let res = CONF.dispatch(b"p\x01\x04\0\x0c\0\x04s://", &mut handler);
+            assert_eq!(&[0, CommandError::InvalidData as u8], &res.as_bytes()[0..2]);
+
+            // The whole value has to match the pattern, not just the chunks
+            CONF.dispatch(b"p\x01\0\0\x05\0\x04http", &mut handler);
+            let res = CONF.dispatch(b"p\x01\x04\0\x05\0\x01s", &mut handler);
+            assert_eq!(&[0, CommandError::InvalidValue as u8], &res.as_bytes()[0..2]);
+            assert_eq!(url.as_bytes(), &read_paged(&CONF, &[b'r', 1], &mut handler)[..url.len()]);
+        }
+
+        #[test]
+        fn schema_dump() {
+            let mut handler = StoreHandler::new(RamStore::<4>::new(), NoInfo);
+            let dump = read_paged(&CONF, b"d", &mut handler);
+
+            // Section frame with its help text
+            assert_eq!(b's', dump[0]);
+            let len = u16::from_le_bytes([dump[1], dump[2]]) as usize;
+            let section = &dump[3..3 + len];
+            assert_eq!(0, section[0]);
+            assert_eq!(Section::Main.id().to_le_bytes(), section[1..5]);
+            assert_eq!(&[4, b'M', b'a', b'i', b'n'], &section[15..20]);
+            assert_eq!((HELP.len() as u16).to_le_bytes(), section[20..22]);
+
+            // Followed by the layout of the section and the prop
+            let layout = &dump[3 + len..];
+            assert_eq!(&[DUMP_LAYOUT_TAG, 81, 0, 0, b'c', 0], &layout[0..6]);
+            let prop = &layout[3 + 81..];
+            assert_eq!(b'c', prop[0]);
+            assert_eq!(PropField::Name.id().to_le_bytes(), prop[4..8]);
+
+            // The table is followed by its columns
+            let column = dump.windows(4).position(|w| w == [DUMP_COLUMN_TAG, 55, 0, 3]).unwrap();
+            assert_eq!(&[0, 0, 0, 0, 0], &dump[column + 4..column + 9]);
+            assert_eq!(&[4, b'S', b'S', b'I', b'D'], &dump[column + 19..column + 24]);
+            let res = CONF.dispatch(&[b'q', b'c', 3, b'c', 1], &mut handler);
+            assert_eq!(&[1, 0, b's'], &res.as_bytes()[0..3]);
+
+            // Patterns are sent after the unit and can be queried
+            let pattern = dump.windows(10).position(|w| w == b"\x09http*://*").unwrap();
+            assert_eq!(0, dump[pattern - 1]);
+            let res = CONF.dispatch(&[b'q', b'c', 1, b'x'], &mut handler);
+            assert_eq!(b"\x01http*://*\0", &res.as_bytes()[0..11]);
+            let res = CONF.dispatch(b"w\x01ftp://host", &mut handler);
+            assert_eq!(&[0, CommandError::InvalidValue as u8], &res.as_bytes()[0..2]);
+            assert_eq!(1, CONF.dispatch(b"w\x01https://host", &mut handler).as_bytes()[0]);
+
+            // Any change to the schema changes the fingerprint
+            assert_ne!(CONF.fingerprint(), super::CONF.fingerprint());
+        }
+    }
+
+    mod lists {
+        extern crate std;
+        use std::vec::Vec;
+
+        use crate::command::CommandError;
+        use crate::store::{RamStore, StoreHandler};
+
+        use super::fixture::*;
+        use super::read_paged;
+
+        #[test]
+        fn list_edits() {
+            let mut handler = StoreHandler::new(RamStore::<4>::new(), NoInfo);
+            assert_eq!(b"\x06ntp.se\0", &read_paged(&CONF, &[b'r', 2], &mut handler)[..8]);
+
+            let server = |n: usize| std::format!("time-{}.pool.example.com", n);
+            for n in 1..8 {
+                let server = server(n);
+                let header = [b'l', 2, b'a', 0, server.len() as u8];
+                let res = CONF.dispatch(&[&header, server.as_bytes()].concat(), &mut handler);
+                assert_eq!(1, res.as_bytes()[0]);
+            }
+            let res = CONF.dispatch(&[&[b'l', 2, b'a', 0, 3][..], b"one"].concat(), &mut handler);
+            assert_eq!(&[0, CommandError::ListFull as u8], &res.as_bytes()[0..2]);
+
+            // Items are validated like single values
+            let res = CONF.dispatch(&[&[b'l', 2, b'r', 2, 4][..], b"b_ad"].concat(), &mut handler);
+            assert_eq!(&[0, CommandError::InvalidData as u8], &res.as_bytes()[0..2]);
+
+            CONF.dispatch(&[&[b'l', 2, b'r', 2, 3][..], b"two"].concat(), &mut handler);
+            CONF.dispatch(&[b'l', 2, b'x', 0], &mut handler);
+            let res = CONF.dispatch(&[b'l', 2, b'x', 7], &mut handler);
+            assert_eq!(&[0, CommandError::InvalidData as u8], &res.as_bytes()[0..2]);
+
+            // The list is longer than a single message
+            let list = read_paged(&CONF, &[b'r', 2], &mut handler);
+            let mut expected = Vec::new();
+            for item in [server(1), "two".into(), server(3), server(4), server(5), server(6), server(7)] {
+                expected.push(item.len() as u8);
+                expected.extend_from_slice(item.as_bytes());
+            }
+            assert_eq!(&expected, &list[..expected.len()]);
+            assert_eq!(0, list[expected.len()]);
+
+            let res = CONF.dispatch(&[b'l', 0, b'c', 0], &mut handler);
+            assert_eq!(&[0, CommandError::NotSupported as u8], &res.as_bytes()[0..2]);
+            for truncated in [&[b'l', 2, b'r'][..], &[b'l', 2, b'r', 0], &[b'l', 2, b'x'], &[b'l', 2, b'a']] {
+                let res = CONF.dispatch(truncated, &mut handler);
+                assert_eq!(&[0, CommandError::MissingArgument as u8], &res.as_bytes()[0..2]);
+            }
+
+            CONF.dispatch(b"w\x02\x03one\x03two", &mut handler);
+            assert_eq!(b"\x03one\x03two\0", &read_paged(&CONF, &[b'r', 2], &mut handler)[..9]);
+        }
+    }
+
+    mod tables {
+        extern crate std;
+        use std::string::String;
+
+        use crate::command::CommandError;
+        use crate::store::{RamStore, StoreHandler};
+
+        use super::fixture::*;
+        use super::read_paged;
+
+        #[test]
+        fn table_rows() {
+            let mut handler = StoreHandler::new(RamStore::<4>::new(), NoInfo);
+            let res = CONF.dispatch(&[b'g', 3, 0], &mut handler);
+            assert_eq!(&[0, CommandError::NoContent as u8], &res.as_bytes()[0..2]);
+
+            // Writing the row after the last one appends a row
+            let ssid: String = "office-".repeat(10);
+            let (head, tail) = ssid.as_bytes().split_at(40);
+            CONF.dispatch(&[&[b'y', 3, 0, 0, 0, 0, 40][..], head].concat(), &mut handler);
+            CONF.dispatch(&[&[b'y', 3, 0, 0, 40, 0, tail.len() as u8][..], tail].concat(), &mut handler);
+            CONF.dispatch(&[&[b'y', 3, 0, 1, 0, 0, 6][..], b"s3cr3t"].concat(), &mut handler);
+            CONF.dispatch(&[&[b'y', 3, 1, 0, 0, 0, 4][..], b"home"].concat(), &mut handler);
+            assert_eq!(&[1, 2, 0], &CONF.dispatch(&[b'r', 3], &mut handler).as_bytes()[0..3]);
+
+            let res = CONF.dispatch(&[&[b'y', 3, 2, 0, 0, 0, 4][..], b"cafe"].concat(), &mut handler);
+            assert_eq!(&[0, CommandError::ListFull as u8], &res.as_bytes()[0..2]);
+            let res = CONF.dispatch(&[&[b'y', 3, 0, 2, 0, 0, 1][..], b"x"].concat(), &mut handler);
+            assert_eq!(&[0, CommandError::InvalidData as u8], &res.as_bytes()[0..2]);
+            let res = CONF.dispatch(&[b'g', 0, 0], &mut handler);
+            assert_eq!(&[0, CommandError::NotSupported as u8], &res.as_bytes()[0..2]);
+            let res = CONF.dispatch(b"w\x03\x01", &mut handler);
+            assert_eq!(&[0, CommandError::NotSupported as u8], &res.as_bytes()[0..2]);
+
+            // Rows longer than a message are paged, secrets only tell whether they are set
+            let row = read_paged(&CONF, &[b'g', 3, 0], &mut handler);
+            let mut expected = std::vec![ssid.len() as u8];
+            expected.extend_from_slice(ssid.as_bytes());
+            expected.extend_from_slice(b"\x01\x01\0");
+            assert_eq!(&expected, &row[..expected.len()]);
+
+            CONF.dispatch(&[b'x', 3, 0], &mut handler);
+            assert_eq!(b"\x04home\x01\0", &read_paged(&CONF, &[b'g', 3, 0], &mut handler)[..7]);
+            assert_eq!(&[1, 1, 0], &CONF.dispatch(&[b'r', 3], &mut handler).as_bytes()[0..3]);
+        }
+    }
+
+    mod secrets {
+        use crate::command::CommandError;
+        use crate::store::{RamStore, StoreHandler};
+
+        use super::fixture::*;
+
+        #[test]
+        fn secret_props() {
+            let mut handler = StoreHandler::new(RamStore::<4>::new(), NoInfo);
+            assert_eq!(&[1, 0, 0], &CONF.dispatch(&[b'r', 4], &mut handler).as_bytes()[0..3]);
+            assert_eq!(1, CONF.dispatch(b"w\x041234", &mut handler).as_bytes()[0]);
+            assert_eq!(&[1, 1, 0], &CONF.dispatch(&[b'r', 4], &mut handler).as_bytes()[0..3]);
+
+            // Emptying a secret takes the clear command
+            let res = CONF.dispatch(b"w\x04\0", &mut handler);
+            assert_eq!(&[0, CommandError::InvalidValue as u8], &res.as_bytes()[0..2]);
+            assert_eq!(1, CONF.dispatch(&[b'c', 4], &mut handler).as_bytes()[0]);
+            assert_eq!(&[1, 0, 0], &CONF.dispatch(&[b'r', 4], &mut handler).as_bytes()[0..3]);
+            let res = CONF.dispatch(&[b'c', 0], &mut handler);
+            assert_eq!(&[0, CommandError::NotSupported as u8], &res.as_bytes()[0..2]);
+        }
+    }
+
+    mod defaults {
+        use crate::command::CommandError;
+        use crate::store::{RamStore, StoreHandler};
+
+        use super::fixture::*;
+
+        #[test]
+        fn defaults_and_reset() {
+            let mut handler = StoreHandler::new(RamStore::<4>::new(), NoInfo);
+            assert_eq!(b"\x01\x06ntp.se\0", &CONF.dispatch(&[b'q', b'c', 2, b'd'], &mut handler).as_bytes()[0..9]);
+            assert_eq!(&[1, 0], &CONF.dispatch(&[b'q', b'c', 0, b'd'], &mut handler).as_bytes()[0..2]);
+            let res = CONF.dispatch(&[b'q', b's', 0, b'd'], &mut handler);
+            assert_eq!(&[0, CommandError::InvalidQuery as u8], &res.as_bytes()[0..2]);
+
+            CONF.dispatch(b"w\x02\x08pool.org", &mut handler);
+            assert_eq!(b"\x01\x08pool.org", &CONF.dispatch(&[b'r', 2], &mut handler).as_bytes()[0..10]);
+            assert_eq!(1, CONF.dispatch(&[b'z', 2], &mut handler).as_bytes()[0]);
+            assert_eq!(b"\x01\x06ntp.se", &CONF.dispatch(&[b'r', 2], &mut handler).as_bytes()[0..8]);
+
+            // Tables lose their rows
+            CONF.dispatch(&[&[b'y', 3, 0, 0, 0, 0, 4][..], b"home"].concat(), &mut handler);
+            CONF.dispatch(&[&[b'y', 3, 1, 0, 0, 0, 4][..], b"cafe"].concat(), &mut handler);
+            assert_eq!(&[1, 2], &CONF.dispatch(&[b'r', 3], &mut handler).as_bytes()[0..2]);
+            assert_eq!(1, CONF.dispatch(&[b'z', 3], &mut handler).as_bytes()[0]);
+            assert_eq!(&[1, 0], &CONF.dispatch(&[b'r', 3], &mut handler).as_bytes()[0..2]);
+
+            CONF.dispatch(b"w\x01https://example.com", &mut handler);
+            CONF.dispatch(b"w\x02\x08pool.org", &mut handler);
+            CONF.dispatch(&[&[b'y', 3, 0, 0, 0, 0, 4][..], b"home"].concat(), &mut handler);
+            assert_eq!(1, CONF.dispatch(b"Z", &mut handler).as_bytes()[0]);
+            assert_eq!(&[1, 0], &CONF.dispatch(&[b'r', 1], &mut handler).as_bytes()[0..2]);
+            assert_eq!(b"\x01\x06ntp.se", &CONF.dispatch(&[b'r', 2], &mut handler).as_bytes()[0..8]);
+            assert_eq!(&[1, 0], &CONF.dispatch(&[b'r', 3], &mut handler).as_bytes()[0..2]);
+        }
     }
 }
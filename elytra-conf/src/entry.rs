@@ -3,10 +3,12 @@ use core::{ops::Range};
 use bitflags::bitflags;
 use elytra_bytepack::{Buf, pack};
 use crate::{
-    values::DefaultValue,
+    time::SECONDS_PER_DAY,
+    text::TextFormat,
+    values::{DefaultValue, MAX_SCALE, NumberFormat},
     command::CommandResponse, 
     entry::options::{OptionValueProvider}, 
-    config::MESSAGE_LENGTH
+    config::{MESSAGE_LENGTH, VALUE_LENGTH}
 };
 
 pub mod options;
@@ -30,6 +32,8 @@ bitflags! {
         const HasIcon = 1 << 2;
         const HasOptions = 1 << 3;
         const IsMulti = 1 << 4;
+        const HasUnit = 1 << 5;
+        const HasPattern = 1 << 6;
     }
 }
 
@@ -85,9 +89,13 @@ impl ValueConstraints {
 #[derive(Debug)]
 pub enum Constraints {
     None,
-    Range(Range<i32>),
+    /// Bounds of integer and decimal values, the end is inclusive. For text, the end is
+    /// the maximum length.
+    Range(Range<i64>),
     Length(u64),
-    Values(ValueConstraints)
+    Values(ValueConstraints),
+    /// Columns of a table, see [`table`](crate::entry::table).
+    Columns(&'static [EntryDesc]),
 }
 
 #[cfg(feature = "defmt")]
@@ -98,19 +106,25 @@ impl defmt::Format for Constraints {
             Constraints::Range(range) => defmt::write!(fmt, "RangeConstraint({}, {})", range.start, range.end),
             Constraints::Length(len) => defmt::write!(fmt, "LengthConstraints({})", len),
             Constraints::Values(ovp) => defmt::write!(fmt, "ValuesConstraints({}, {}, {}, {})", ovp.value_provider.len(), ovp.suggested, ovp.min, ovp.max),
+            Constraints::Columns(columns) => defmt::write!(fmt, "ColumnsConstraints({})", columns.len()),
         }
     }
 }
 
+fn saturate_i32(value: i64) -> i32 {
+    value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
 impl Constraints {
 
     pub fn bits(&self) -> [u8; 8] {
         match self {
             Constraints::None => [0; 8],
+            // The full bounds are sent with the number format
             Constraints::Range(Range { start, end}) => {
                 pack!(
-                    start.to_le_bytes(),
-                    end.to_le_bytes()
+                    saturate_i32(*start).to_le_bytes(),
+                    saturate_i32(*end).to_le_bytes()
                 )
             },
             Constraints::Length(len) => len.to_le_bytes(),
@@ -121,28 +135,27 @@ impl Constraints {
                     constr.max_or_suggested.to_le_bytes()
                 )
             },
+            Constraints::Columns(columns) => pack!(
+                u32::to_le_bytes(columns.len() as u32),
+                [0u8; 4]
+            ),
         }
     }
 
     pub fn is_values(&self) -> bool {
-        match self {
-            Self::Values(_) => {true},
-            _ => {false},
-        }
+        matches!(self, Self::Values(_))
     }
 
     pub fn is_length(&self) -> bool {
-        match self {
-            Self::Length(_) => {true},
-            _ => {false},
-        }
+        matches!(self, Self::Length(_))
     }
 
     pub fn is_range(&self) -> bool {
-        match self {
-            Self::Range(_) => {true},
-            _ => {false},
-        }
+        matches!(self, Self::Range(_))
+    }
+
+    pub fn is_columns(&self) -> bool {
+        matches!(self, Self::Columns(_))
     }
 }
 
@@ -157,9 +170,15 @@ pub struct EntryDesc {
     pub icon: Option<&'static str>,
     pub default: DefaultValue,
     pub multi: bool,
+    /// Largest number of items of a list, see [`EntryDesc::with_max_items`], or rows of
+    /// a table, see [`EntryDesc::with_max_rows`].
+    pub max_items: u8,
+    pub format: NumberFormat,
+    pub text: TextFormat,
 }
 
 impl EntryDesc {
+    #[allow(clippy::too_many_arguments)]
     pub const fn new(
         name: &'static str,
         variant: EntryVariant,
@@ -170,8 +189,8 @@ impl EntryDesc {
         default: DefaultValue,
         multi: bool,
     ) -> Self {
-        if name.len() == 0 { panic!("name is required"); }
-        if name.as_bytes().len() > Self::MAX_ENTRY_NAME_LEN { panic!("name is too long") }
+        if name.is_empty() { panic!("name is required"); }
+        if name.len() > Self::MAX_ENTRY_NAME_LEN { panic!("name is too long") }
         if let EntryVariant::Field(value_type) = variant {
             match (value_type, &default) {
                 (ValueType::Bytes, DefaultValue::Bytes(_)) => {},
@@ -179,17 +198,32 @@ impl EntryDesc {
                 (ValueType::Text, DefaultValue::Text(_)) => {},
                 (ValueType::Secret, DefaultValue::Text(_)) => {},
                 (ValueType::Options, DefaultValue::Options(_)) => {},
+                (ValueType::Bool, DefaultValue::Bool(_)) => {},
+                (ValueType::Decimal, DefaultValue::Decimal(_)) => {},
+                (ValueType::Ipv4, DefaultValue::Bytes(bytes)) if bytes.len() == 4 => {},
+                (ValueType::Ipv6, DefaultValue::Bytes(bytes)) if bytes.len() == 16 => {},
+                (ValueType::Cidr, DefaultValue::Bytes(bytes)) if bytes.len() == 2 + 4 || bytes.len() == 2 + 16 => {},
+                (ValueType::Mac, DefaultValue::Bytes(bytes)) if bytes.len() == 6 => {},
+                (ValueType::Hostname, DefaultValue::Text(_)) => {},
+                (ValueType::Time | ValueType::Date | ValueType::Timestamp | ValueType::Duration, DefaultValue::Integer(_)) => {},
+                (ValueType::Color | ValueType::ColorAlpha, DefaultValue::Color(_)) => {},
+                (ValueType::Flags, DefaultValue::Integer(_)) => {},
+                (ValueType::Ipv4 | ValueType::Ipv6 | ValueType::Cidr | ValueType::Mac, DefaultValue::Bytes(_)) => {
+                    panic!("Address default has the wrong length")
+                },
                 (_, DefaultValue::Empty) => {},
                 (ValueType::Status, _) => panic!("Status value type cannot have a default value"),
                 (_, DefaultValue::Integer(_)) => panic!("Integer is not a valid default value for this field"),
                 (_, DefaultValue::Bytes(_)) => panic!("Bytes is not a valid default value for this field"),
                 (_, DefaultValue::Text(_)) => panic!("Text is not a valid default value for this field"),
                 (_, DefaultValue::Options(_)) => panic!("Options is not a valid default value for this field"),
+                (_, DefaultValue::Bool(_)) => panic!("Bool is not a valid default value for this field"),
+                (_, DefaultValue::Decimal(_)) => panic!("Decimal is not a valid default value for this field"),
+                (_, DefaultValue::Color(_)) => panic!("Color is not a valid default value for this field"),
             }
         }
-        match (&constraints, &default) {
-            (Constraints::Values(_), DefaultValue::Empty) => panic!("Option value type needs a default value"),
-            _ => {},
+        if let (Constraints::Values(_), DefaultValue::Empty) = (&constraints, &default) {
+            panic!("Option value type needs a default value")
         }
 
         Self {
@@ -201,6 +235,80 @@ impl EntryDesc {
             icon,
             default,
             multi,
+            max_items: 0,
+            format: NumberFormat::DEFAULT,
+            text: TextFormat::DEFAULT,
+        }
+    }
+
+    /// Turns the field into a list of up to `max_items` values of its type. Items are
+    /// stored with a length byte each and share the space of a single value.
+    pub const fn with_max_items(self, max_items: u8) -> Self {
+        match self.variant {
+            EntryVariant::Field(ValueType::Secret | ValueType::Status | ValueType::Options | ValueType::Table) => {
+                panic!("secret, status, option and table fields can't be lists")
+            },
+            EntryVariant::Field(_) => {},
+            _ => panic!("only fields can be lists"),
+        }
+        if max_items == 0 || max_items as usize > VALUE_LENGTH / 2 { panic!("lists hold 1 to 128 items") }
+        Self { multi: true, max_items, ..self }
+    }
+
+    /// Limits a table to `max_rows` rows, each stored under its own key.
+    pub const fn with_max_rows(self, max_rows: u8) -> Self {
+        let EntryVariant::Field(ValueType::Table) = self.variant else {
+            panic!("only tables have rows")
+        };
+        if max_rows == 0 { panic!("tables hold at least one row") }
+        Self { max_items: max_rows, ..self }
+    }
+
+    pub const fn with_format(self, format: NumberFormat) -> Self {
+        let numeric = match self.variant {
+            EntryVariant::Field(value_type) => value_type.is_integer(),
+            _ => false,
+        };
+        if format.scale > MAX_SCALE { panic!("scale is too large") }
+        if format.scale != 0 && !matches!(self.variant, EntryVariant::Field(ValueType::Decimal)) {
+            panic!("only decimal fields have a scale")
+        }
+        if !matches!(format.radix, 2 | 8 | 10 | 16) { panic!("radix has to be 2, 8, 10 or 16") }
+        if (format.step != 0 || format.radix != 10 || format.unsigned) && !numeric {
+            panic!("only numeric fields have a step, radix or sign")
+        }
+        if format.unsigned && format.scale != 0 { panic!("decimal fields can't be unsigned") }
+        Self { format, ..self }
+    }
+
+    pub const fn with_text_format(self, text: TextFormat) -> Self {
+        let is_text = match self.variant {
+            EntryVariant::Field(value_type) => value_type.is_text(),
+            _ => false,
+        };
+        if (text.chars.bits() != TextFormat::DEFAULT.chars.bits() || text.pattern.is_some()) && !is_text {
+            panic!("only text fields have accepted characters or a pattern")
+        }
+        if let Some(pattern) = text.pattern && pattern.is_empty() { panic!("pattern is empty") }
+        Self { text, ..self }
+    }
+
+    /// Inclusive bounds of numeric values, as `u64` bit patterns for unsigned values.
+    pub fn bounds(&self) -> (i64, i64) {
+        match (&self.constraints, self.format.unsigned, &self.variant) {
+            (Constraints::Range(range), _, _) => (range.start, range.end),
+            (_, true, _) => (0, u64::MAX as i64),
+            (_, _, EntryVariant::Field(ValueType::Time)) => (0, SECONDS_PER_DAY - 1),
+            (_, _, EntryVariant::Field(ValueType::Duration)) => (0, i64::MAX),
+            (_, false, _) => (i64::MIN, i64::MAX),
+        }
+    }
+
+    /// Columns of a table, empty for every other entry.
+    pub fn columns(&self) -> &'static [EntryDesc] {
+        match self.constraints {
+            Constraints::Columns(columns) => columns,
+            _ => &[],
         }
     }
 
@@ -211,6 +319,8 @@ impl EntryDesc {
         flags.set(ExtraFlags::HasIcon, self.icon.is_some());
         flags.set(ExtraFlags::HasOptions, self.constraints.is_values());
         flags.set(ExtraFlags::IsMulti, self.multi);
+        flags.set(ExtraFlags::HasUnit, self.format.unit.is_some());
+        flags.set(ExtraFlags::HasPattern, self.text.pattern.is_some());
         flags
     }
 
@@ -5,17 +5,17 @@ use crate::{entry::{Constraints, EntryDesc, EntryVariant}, values::DefaultValue}
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[allow(unused)]
 pub enum ActionVariant {
-    Main = 'm' as u8,
-    Normal = 'n' as u8,
-    Danger = 'd' as u8,
-    Green = 'g' as u8,
-    Blue = 'b' as u8,
-    Teal = 't' as u8,
-    Pink = 'p' as u8,
-    Purple = 'l' as u8,
-    Cyan = 'c' as u8,
-    Orange = 'o' as u8,
-    Yellow = 'y' as u8,
+    Main = b'm',
+    Normal = b'n',
+    Danger = b'd',
+    Green = b'g',
+    Blue = b'b',
+    Teal = b't',
+    Pink = b'p',
+    Purple = b'l',
+    Cyan = b'c',
+    Orange = b'o',
+    Yellow = b'y',
 }
 
 pub struct ActionEntry {
@@ -1,11 +1,14 @@
-use core::ops::Range;
+use core::{ops::Range, time::Duration};
 
 use crate::{
+    color::Color,
     traits::{PropIndex, InfoIndex},
     entry::{Constraints, EntryDesc, EntryVariant, ValueConstraints}, 
     prelude::OptionValueProvider, 
     config::EntryType, 
-    values::{DefaultValue, ValueType}
+    text::{CharClass, TextFormat},
+    time::{Date, TimeOfDay},
+    values::{DefaultValue, NumberFormat, ValueType, decimal_units}
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -36,6 +39,9 @@ pub struct FieldEntry {
     pub icon: Option<&'static str>,
     pub default: DefaultValue,
     pub multi: bool,
+    pub max_items: u8,
+    pub format: NumberFormat,
+    pub text: TextFormat,
 }
 
 #[allow(unused)]
@@ -44,7 +50,7 @@ impl FieldEntry {
         let Some(readonly) = self.readonly else {
             panic!("proto field writable configuration is ambigous: use .writable() or .readonly()") 
         };
-        EntryDesc::new(
+        let entry = EntryDesc::new(
             self.name, 
             EntryVariant::Field(self.value_type), 
             readonly,
@@ -53,7 +59,12 @@ impl FieldEntry {
             self.icon,
             self.default,
             self.multi,
-        )
+        ).with_format(self.format).with_text_format(self.text);
+        match (self.multi, self.value_type) {
+            (true, _) => entry.with_max_items(self.max_items),
+            (false, ValueType::Table) => entry.with_max_rows(self.max_items),
+            (false, _) => entry,
+        }
     }
     pub const fn with_icon(self, icon: &'static str) -> Self {
         Self {
@@ -67,18 +78,114 @@ impl FieldEntry {
             ..self
         }
     }
-    pub const fn with_range(self, range: Range<i32>) -> Self {
+    /// Limits integer values to `start..=end`, or the length of text values to `end`. The
+    /// range of decimal values is given in stored units, see [`FieldEntry::with_decimal_range`].
+    pub const fn with_range(self, range: Range<i64>) -> Self {
         Self {
             constraints: Constraints::Range(range),
             ..self
         }
     }
+
+    /// Limits the length of text values to `min..=max` bytes. Hosts writing a value of
+    /// another length get [`CommandError::InvalidValue`](crate::command::CommandError::InvalidValue).
+    pub const fn with_length(self, min: u8, max: u8) -> Self {
+        if !self.value_type.is_text() { panic!("only text fields have a length") }
+        if min > max { panic!("minimum length is larger than the maximum") }
+        self.with_range(min as i64..max as i64)
+    }
+
+    /// Only accepts text made of the given classes of characters.
+    pub const fn with_chars(self, chars: CharClass) -> Self {
+        Self {
+            text: TextFormat { chars, ..self.text },
+            ..self
+        }
+    }
+
+    /// Only accepts text matching `pattern`, see [`crate::text`].
+    pub const fn with_pattern(self, pattern: &'static str) -> Self {
+        Self {
+            text: TextFormat { pattern: Some(pattern), ..self.text },
+            ..self
+        }
+    }
+
+    /// Limits unsigned integer values to `start..=end`, see [`FieldEntry::unsigned`].
+    pub const fn with_unsigned_range(self, range: Range<u64>) -> Self {
+        self.unsigned().with_range(range.start as i64..range.end as i64)
+    }
+
+    /// Limits decimal values to `min..=max`, converted to stored units with the field scale.
+    pub const fn with_decimal_range(self, min: f64, max: f64) -> Self {
+        let range = decimal_units(min, self.format.scale)..decimal_units(max, self.format.scale);
+        self.with_range(range)
+    }
+
+    /// Limits time of day values to `start..=end`.
+    pub const fn with_time_range(self, start: TimeOfDay, end: TimeOfDay) -> Self {
+        self.with_range(start.seconds()..end.seconds())
+    }
+
+    /// Limits date values to `start..=end`.
+    pub const fn with_date_range(self, start: Date, end: Date) -> Self {
+        self.with_range(start.days()..end.days())
+    }
+
+    /// Limits duration values to `min..=max`, in whole seconds.
+    pub const fn with_duration_range(self, min: Duration, max: Duration) -> Self {
+        self.with_range(min.as_secs() as i64..max.as_secs() as i64)
+    }
+
+    pub const fn with_unit(self, unit: &'static str) -> Self {
+        Self {
+            format: NumberFormat { unit: Some(unit), ..self.format },
+            ..self
+        }
+    }
+
+    /// Only accepts multiples of `step` from the start of the range, in stored units for
+    /// decimal values. Other values are rounded to the nearest step.
+    pub const fn with_step(self, step: u64) -> Self {
+        Self {
+            format: NumberFormat { step, ..self.format },
+            ..self
+        }
+    }
+
+    /// Asks hosts to show and edit the value in another radix, such as 16 for register values.
+    pub const fn with_radix(self, radix: u8) -> Self {
+        Self {
+            format: NumberFormat { radix, ..self.format },
+            ..self
+        }
+    }
+
+    /// Treats the stored integer as `u64`, for values that don't fit an `i64`.
+    pub const fn unsigned(self) -> Self {
+        Self {
+            format: NumberFormat { unsigned: true, ..self.format },
+            ..self
+        }
+    }
+
     pub const fn with_type(self, value_type: ValueType) -> Self {
         Self {
             value_type,
             ..self
         }
     }
+    /// Holds a list of up to `max_items` values instead of a single one, see
+    /// [`EntryDesc::with_max_items`]. A default value becomes the only item of the list,
+    /// also once all items are removed.
+    pub const fn list(self, max_items: u8) -> Self {
+        Self {
+            multi: true,
+            max_items,
+            ..self
+        }
+    }
+
     pub const fn writable(self) -> Self {
         Self {
             readonly: Some(false),
@@ -132,6 +239,62 @@ impl FieldEntry {
         }
     }
 
+    pub const fn with_default_decimal(self, value: f64) -> Self {
+        Self {
+            default: DefaultValue::Decimal(decimal_units(value, self.format.scale)),
+            ..self
+        }
+    }
+
+    pub const fn with_default_bool(self, value: bool) -> Self {
+        Self {
+            default: DefaultValue::Bool(value),
+            ..self
+        }
+    }
+
+    pub const fn with_default_time(self, value: TimeOfDay) -> Self {
+        Self {
+            default: DefaultValue::Integer(value.seconds()),
+            ..self
+        }
+    }
+
+    pub const fn with_default_date(self, value: Date) -> Self {
+        Self {
+            default: DefaultValue::Integer(value.days()),
+            ..self
+        }
+    }
+
+    pub const fn with_default_duration(self, value: Duration) -> Self {
+        Self {
+            default: DefaultValue::Integer(value.as_secs() as i64),
+            ..self
+        }
+    }
+
+    /// Bits that are set by default, bit `n` is the `n`th name of the field.
+    pub const fn with_default_flags(self, value: u64) -> Self {
+        Self {
+            default: DefaultValue::Integer(value as i64),
+            ..self
+        }
+    }
+
+    pub const fn with_default_color(self, value: Color) -> Self {
+        Self {
+            default: DefaultValue::Color(value),
+            ..self
+        }
+    }
+
+    /// Stores an alpha channel with the colour, see [`color`].
+    pub const fn with_alpha(self) -> Self {
+        if !matches!(self.value_type, ValueType::Color) { panic!("only color fields have an alpha channel") }
+        self.with_type(ValueType::ColorAlpha)
+    }
+
     pub const fn with_default_options(self, value: &'static [u16]) -> Self {
         Self {
             default: DefaultValue::Options(value),
@@ -167,6 +330,9 @@ pub const fn bytes(name: &'static str, size: u8) -> FieldEntry {
         icon: None,
         default: DefaultValue::Empty,
         multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
     }
 }
 
@@ -181,6 +347,9 @@ pub const fn secret(name: &'static str) -> PropEntry {
         icon: None,
         default: DefaultValue::Empty,
         multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
     }
 }
 
@@ -195,6 +364,9 @@ pub const fn status(name: &'static str) -> InfoEntry {
         icon: None,
         default: DefaultValue::Empty,
         multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
     }
 }
 
@@ -209,6 +381,9 @@ pub const fn integer(name: &'static str) -> InfoEntry {
         icon: None,
         default: DefaultValue::Empty,
         multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
     }
 }
 
@@ -226,6 +401,287 @@ pub const fn option(name: &'static str, value_provider: &'static dyn OptionValue
         icon: None,
         default: DefaultValue::Empty,
         multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// On/off switch, sent as a single byte that is `1` when enabled.
+#[allow(unused)]
+pub const fn toggle(name: &'static str) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Bool,
+        constraints: Constraints::None,
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Fixed point number with `scale` decimal places, stored as an integer count of
+/// `10^-scale` units.
+#[allow(unused)]
+pub const fn decimal(name: &'static str, scale: u8) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Decimal,
+        constraints: Constraints::None,
+        readonly: None,
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat { scale, ..NumberFormat::DEFAULT },
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// IPv4 address, sent as 4 bytes in network order. Defaults are set with
+/// [`FieldEntry::with_default_bytes`].
+#[allow(unused)]
+pub const fn ipv4(name: &'static str) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Ipv4,
+        constraints: Constraints::None,
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// IPv6 address, sent as 16 bytes in network order.
+#[allow(unused)]
+pub const fn ipv6(name: &'static str) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Ipv6,
+        constraints: Constraints::None,
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// IPv4 or IPv6 network prefix such as `192.168.1.10/24`, encoded by
+/// [`net::cidr_bytes`](crate::net::cidr_bytes).
+#[allow(unused)]
+pub const fn cidr(name: &'static str) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Cidr,
+        constraints: Constraints::None,
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Hardware address of 6 bytes.
+#[allow(unused)]
+pub const fn mac(name: &'static str) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Mac,
+        constraints: Constraints::None,
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Text that has to be a valid hostname, see [`net::is_hostname`](crate::net::is_hostname).
+#[allow(unused)]
+pub const fn hostname(name: &'static str) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Hostname,
+        constraints: Constraints::None,
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Time of day without a time zone, see [`TimeOfDay`].
+#[allow(unused)]
+pub const fn time(name: &'static str) -> InfoEntry {
+    InfoEntry {
+        name,
+        value_type: ValueType::Time,
+        constraints: Constraints::None,
+        readonly: None,
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Calendar date, see [`Date`].
+#[allow(unused)]
+pub const fn date(name: &'static str) -> InfoEntry {
+    InfoEntry {
+        name,
+        value_type: ValueType::Date,
+        constraints: Constraints::None,
+        readonly: None,
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Date and time in UTC, see [`DateTime`](crate::time::DateTime).
+#[allow(unused)]
+pub const fn timestamp(name: &'static str) -> InfoEntry {
+    InfoEntry {
+        name,
+        value_type: ValueType::Timestamp,
+        constraints: Constraints::None,
+        readonly: None,
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Length of time in whole seconds.
+#[allow(unused)]
+pub const fn duration(name: &'static str) -> InfoEntry {
+    InfoEntry {
+        name,
+        value_type: ValueType::Duration,
+        constraints: Constraints::None,
+        readonly: None,
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// RGB colour, or RGBA with [`FieldEntry::with_alpha`]. A palette can be suggested to hosts
+/// with [`FieldEntry::with_suggestions`], its entries are hex colours that may be preceded
+/// by a name, such as `Warm white #ffd8a8`.
+#[allow(unused)]
+pub const fn color(name: &'static str) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Color,
+        constraints: Constraints::None,
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Set of up to 64 bits named by `names`, such as the weekdays an alarm is enabled on.
+/// Hosts can look up the names with the option query.
+#[allow(unused)]
+pub const fn flags(name: &'static str, names: &'static dyn OptionValueProvider) -> PropEntry {
+    PropEntry {
+        name,
+        value_type: ValueType::Flags,
+        constraints: Constraints::Values(ValueConstraints {
+            value_provider: names,
+            min: 0, max_or_suggested: 0
+        }),
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Integer(0),
+        multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
+    }
+}
+
+/// Table of up to `max_rows` records, such as a list of known networks. Every record holds
+/// a value for each of the `columns`, a const array of entries declared with the other
+/// field builders. Rows are read and written with the row commands, see [`crate::table`].
+///
+/// Columns can't be lists, tables or have options.
+#[allow(unused)]
+pub const fn table(name: &'static str, columns: &'static [EntryDesc], max_rows: u8) -> PropEntry {
+    if columns.is_empty() { panic!("tables need at least one column") }
+    let mut i = 0;
+    while i < columns.len() {
+        let column = &columns[i];
+        match column.variant {
+            EntryVariant::Field(ValueType::Table | ValueType::Status) => panic!("columns can't be tables or status"),
+            EntryVariant::Field(_) => {},
+            _ => panic!("columns have to be fields"),
+        }
+        if column.multi { panic!("columns can't be lists") }
+        if let Constraints::Values(_) = column.constraints { panic!("columns can't have options") }
+        i += 1;
+    }
+    PropEntry {
+        name,
+        value_type: ValueType::Table,
+        constraints: Constraints::Columns(columns),
+        readonly: Some(false),
+        help: None,
+        icon: None,
+        default: DefaultValue::Empty,
+        multi: false,
+        max_items: max_rows,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
     }
 }
 
@@ -241,6 +697,9 @@ pub const fn info(name: &'static str) -> InfoEntry {
         icon: None,
         default: DefaultValue::Empty,
         multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
     }
 }
 
@@ -256,5 +715,8 @@ pub const fn prop(name: &'static str) -> PropEntry {
         icon: None,
         default: DefaultValue::Empty,
         multi: false,
+        max_items: 0,
+        format: NumberFormat::DEFAULT,
+        text: TextFormat::DEFAULT,
     }
 }
\ No newline at end of file
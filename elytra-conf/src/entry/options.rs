@@ -4,6 +4,9 @@ use core::option::Option;
 pub trait OptionValueProvider: Debug + Send + Sync {
     fn get(&self, index: usize) -> Option<&'static str>;
     fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 impl OptionValueProvider for [&'static str] {
@@ -1,10 +1,9 @@
-use core::{prelude::rust_2024::*};
-#[cfg(feature = "alloc")]
-use alloc::borrow::ToOwned;
+use core::{net::{IpAddr, Ipv4Addr, Ipv6Addr}, prelude::rust_2024::*, time::Duration};
 use log::warn;
 use elytra_bytepack::Cursor;
 use crate::{
-    command::CommandResponse, config::MESSAGE_LENGTH, entry::{Constraints, EntryDesc, EntryVariant}, values::{DefaultValue, ValueType}
+    color::Color,
+    command::{CommandError, CommandResponse, ListEdit}, config::VALUE_LENGTH, entry::{Constraints, EntryDesc, EntryVariant}, net, text, time::{Date, DateTime, TimeOfDay}, values::{DefaultValue, ValueType, decimal_units, scale_factor}
 };
 
 pub struct Options {
@@ -12,26 +11,51 @@ pub struct Options {
     len: u8
 }
 impl Options {
-    fn as_slice(&self) -> &[u16] {
+    pub fn as_slice(&self) -> &[u16] {
         &self.buf[0..self.len as usize]
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone)]
 pub struct FieldValue {
     desc: &'static EntryDesc,
-    data: [u8; MESSAGE_LENGTH],
+    data: [u8; VALUE_LENGTH],
+    /// Holds the items of a list field rather than a single value.
+    list: bool,
+}
+
+// Secrets are left out, they would otherwise end up in the logs of commands
+impl core::fmt::Debug for FieldValue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut s = f.debug_struct("FieldValue");
+        s.field("desc", &self.desc);
+        match self.is_secret() {
+            true => s.field("data", &"<secret>"),
+            false => s.field("data", &self.data),
+        };
+        s.field("list", &self.list).finish()
+    }
 }
 
 impl FieldValue{
+    /// Empty value of the field, an empty list for list fields.
     pub const fn new(desc: &'static EntryDesc) -> Self {
         Self {
             desc,
-            data: [0u8; MESSAGE_LENGTH]
+            data: [0u8; VALUE_LENGTH],
+            list: desc.multi,
+        }
+    }
+
+    /// Single value of a list field, see [`FieldValue::push_item`].
+    pub const fn new_item(desc: &'static EntryDesc) -> Self {
+        Self {
+            desc,
+            data: [0u8; VALUE_LENGTH],
+            list: false,
         }
     }
 
-    #[allow(unused)]
     const fn len(&self) -> usize {
         self.data[0] as usize
     }
@@ -40,74 +64,329 @@ impl FieldValue{
         self.data[0] = len as u8
     }
 
-    pub fn from_store(desc: &'static EntryDesc, bytes: [u8; MESSAGE_LENGTH]) -> Self {
-        let mut fv = Self {
+    pub fn from_store(desc: &'static EntryDesc, bytes: [u8; VALUE_LENGTH]) -> Self {
+        let fv = Self {
             desc,
-            data: bytes
+            data: bytes,
+            list: desc.multi,
         };
-        if fv.is_empty() {
-            use DefaultValue::{*};
-
-            fv.data[0] = match desc.default {
-                Bytes(bytes) => {
-                    fv.data[1..].copy_from_slice(bytes);
-                    bytes.len() as u8
-                },
-                Empty => 0,
-                Text(text) => {
-                    fv.data[1..].copy_from_slice(text.as_bytes());
-                    text.len() as u8
-                },
-                Integer(integer) => {
-                    fv.data[1..].copy_from_slice(&integer.to_le_bytes());
-                    4
+        match fv.is_empty() {
+            true if fv.list => {
+                let mut list = Self::new(desc);
+                let default = Self::default_item(desc);
+                if !default.is_empty() {
+                    let _ = list.push_item(default);
                 }
-                Options(items) => {
-                    let mut cursor = Cursor::new(&mut fv.data[1..]);
-                    for i in 0..items.len() {
-                        cursor.write(&items[i].to_le_bytes()).unwrap();
-                    }
-                    items.len() as u8
-                },
-            }
+                list
+            },
+            true => Self::default_item(desc),
+            false => fv,
         }
-        fv
     }
 
-    pub fn from_message(desc: &'static EntryDesc, bytes: &[u8]) -> Self {
-        let mut fv = Self {
-            desc,
-            data: [0u8; 64]
+    /// Default value of the field, see [`DefaultValue`].
+    pub fn from_default(desc: &'static EntryDesc) -> Self {
+        Self::from_store(desc, [0u8; VALUE_LENGTH])
+    }
+
+    /// Value of `desc` from the bytes returned by [`FieldValue::get_bytes`], the default
+    /// of the field if they are empty.
+    pub(crate) fn from_bytes(desc: &'static EntryDesc, bytes: &[u8]) -> Self {
+        let mut data = [0u8; VALUE_LENGTH];
+        let len = bytes.len().min(VALUE_LENGTH - 1);
+        data[1..=len].copy_from_slice(&bytes[..len]);
+        data[0] = match desc.variant {
+            EntryVariant::Field(vt) if vt.is_options() => len / 2,
+            _ => len,
+        } as u8;
+        Self::from_store(desc, data)
+    }
+
+    fn default_item(desc: &'static EntryDesc) -> Self {
+        use DefaultValue::{*};
+
+        let mut fv = Self::new_item(desc);
+        fv.data[0] = match desc.default {
+            Bytes(bytes) => {
+                fv.data[1..=bytes.len()].copy_from_slice(bytes);
+                bytes.len() as u8
+            },
+            Empty => 0,
+            Text(text) => {
+                fv.data[1..=text.len()].copy_from_slice(text.as_bytes());
+                text.len() as u8
+            },
+            Integer(integer) | Decimal(integer) => {
+                fv.data[1..=8].copy_from_slice(&integer.to_le_bytes());
+                8
+            }
+            Bool(value) => {
+                fv.data[1] = value as u8;
+                1
+            }
+            Color(color) => {
+                fv.data[1..=4].copy_from_slice(&color.bytes());
+                fv.color_len() as u8
+            }
+            Options(items) => {
+                let mut cursor = Cursor::new(&mut fv.data[1..]);
+                for item in items {
+                    cursor.write(&item.to_le_bytes()).unwrap();
+                }
+                items.len() as u8
+            },
         };
+        fv
+    }
+
+    /// Parses a value written by the host, network values that are malformed fail with
+    /// [`CommandError::InvalidData`], everything else is clamped to the field.
+    ///
+    /// Lists are sent as their items with a 1 byte length each, up to the first empty item.
+    pub fn from_message(desc: &'static EntryDesc, bytes: &[u8]) -> Result<Self, CommandError> {
+        Self::from_message_clamped(desc, bytes).map(|(fv, _)| fv)
+    }
+
+    /// Parses a value like [`FieldValue::from_message`], also telling whether clamping
+    /// changed it so the host can be warned.
+    pub fn from_message_clamped(desc: &'static EntryDesc, bytes: &[u8]) -> Result<(Self, bool), CommandError> {
+        if !desc.multi {
+            return Self::item_from_message_clamped(desc, bytes);
+        }
+        let mut list = Self::new(desc);
+        let mut clamped = false;
+        let mut bytes = bytes;
+        while let Some((&len, rest)) = bytes.split_first().filter(|(len, _)| **len != 0) {
+            let item = rest.get(..len as usize).ok_or(CommandError::InvalidData)?;
+            let (value, item_clamped) = Self::item_from_message_clamped(desc, item)?;
+            list.push_item(value)?;
+            clamped |= item_clamped;
+            bytes = &rest[item.len()..];
+        }
+        Ok((list, clamped))
+    }
+
+    /// Parses a single value of a list field written by the host, see [`FieldValue::from_message`].
+    pub fn item_from_message(desc: &'static EntryDesc, bytes: &[u8]) -> Result<Self, CommandError> {
+        Self::item_from_message_clamped(desc, bytes).map(|(fv, _)| fv)
+    }
+
+    fn item_from_message_clamped(desc: &'static EntryDesc, bytes: &[u8]) -> Result<(Self, bool), CommandError> {
+        let mut fv = Self::new_item(desc);
         
         fv.data[0] = match desc.variant {
+            // Rows are written with the row commands
+            EntryVariant::Field(ValueType::Table) => {
+                return Err(CommandError::NotSupported)
+            },
             EntryVariant::Field(vt) if vt.is_options() => {
                 bytes.len() as u8 / 2
             },
+            EntryVariant::Field(vt) if vt.is_network() => {
+                net::validate(vt, bytes)? as u8
+            },
+            // Text ends at the padding of the message
+            EntryVariant::Field(vt) if vt.is_text() => {
+                bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len()) as u8
+            },
             _ => {
                 bytes.len() as u8
             }
         };
-        for i in 0..bytes.len() {
-            fv.data[i + 1] = bytes[i]
-        }
+        let len = fv.byte_len();
+        fv.data[1..=len].copy_from_slice(&bytes[..len]);
+        fv.validate_text(false)?;
+        let written = fv.data;
         fv.clamp();
-        fv
+        // Anything written past the clamped value that is not padding was dropped
+        let len = fv.byte_len();
+        let clamped = fv.data[1..=len] != written[1..=len] || written[len + 1..].iter().any(|b| *b != 0);
+        fv.data[len + 1..].fill(0);
+        Ok((fv, clamped))
     }
 
-    pub fn into_store_bytes(self) -> [u8; MESSAGE_LENGTH] {
+    pub fn into_store_bytes(self) -> [u8; VALUE_LENGTH] {
         self.data
     }
 
-    pub fn into_message_bytes(mut self) -> [u8; MESSAGE_LENGTH] {
-        if self.desc.variant == EntryVariant::Field(ValueType::Secret) {
-            let max_len = MESSAGE_LENGTH.min(self.data[0] as usize);
-            for i in 1..max_len {
-                self.data[i] = '*' as u8;
+    /// Number of value bytes following the length byte.
+    fn byte_len(&self) -> usize {
+        match self.desc.variant {
+            EntryVariant::Field(vt) if vt.is_options() => 2 * self.len(),
+            _ => self.len(),
+        }.min(VALUE_LENGTH - 1)
+    }
+
+    /// The value as sent to the host. Secrets are write-only and sent as a single byte
+    /// that is `1` when they are set.
+    pub fn message_payload(&self) -> impl Iterator<Item = u8> + '_ {
+        let (set, bytes) = match self.is_secret() {
+            true => (Some(!self.get_bytes().is_empty() as u8), &[][..]),
+            false => (None, self.get_bytes()),
+        };
+        set.into_iter().chain(bytes.iter().copied())
+    }
+
+    fn is_secret(&self) -> bool {
+        self.desc.variant == EntryVariant::Field(ValueType::Secret)
+    }
+
+    /// Replaces the value from byte `offset` on with `bytes`, for values that are
+    /// written in several messages. Text is checked as far as its start allows, see
+    /// [`text::validate`].
+    pub fn splice(&mut self, offset: usize, bytes: &[u8]) -> Result<(), CommandError> {
+        if !self.can_splice() {
+            return Err(CommandError::NotSupported);
+        }
+        let end = offset + bytes.len();
+        if offset > self.len() || end >= VALUE_LENGTH {
+            return Err(CommandError::InvalidData);
+        }
+        self.data[1 + offset..=end].copy_from_slice(bytes);
+        self.data[end + 1..].fill(0);
+        self.set_len(end);
+        self.validate_text(true)?;
+        if self.desc.variant == EntryVariant::Field(ValueType::Hostname) && !net::is_hostname_prefix(self.get_text()) {
+            return Err(CommandError::InvalidData);
+        }
+        Ok(())
+    }
+
+    /// Checks a value put together with [`FieldValue::splice`] against all constraints of
+    /// the field, each chunk was only checked as the start of a value.
+    pub fn validate_spliced(&self) -> Result<(), CommandError> {
+        self.validate_text(false)
+    }
+
+    /// Checks text written by the host against the constraints of the field, with
+    /// `partial` the value may end in the middle of a character.
+    fn validate_text(&self, partial: bool) -> Result<(), CommandError> {
+        let EntryVariant::Field(vt) = self.desc.variant else { return Ok(()) };
+        if !vt.is_text() {
+            return Ok(());
+        }
+        let bytes = self.text_bytes();
+        let text = match core::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) if partial && e.error_len().is_none() => {
+                core::str::from_utf8(&bytes[..e.valid_up_to()]).or(Err(CommandError::InvalidData))?
+            },
+            Err(_) => return Err(CommandError::InvalidData),
+        };
+        text::validate(self.desc, text, partial)
+    }
+
+    /// Whether the value can be written in several messages, see [`FieldValue::splice`].
+    pub fn can_splice(&self) -> bool {
+        !self.list && matches!(self.desc.variant, EntryVariant::Field(ValueType::Text | ValueType::Secret | ValueType::Bytes | ValueType::Hostname))
+    }
+
+    /// Whether the value holds the items of a list field, see [`EntryDesc::with_max_items`].
+    pub fn is_list(&self) -> bool {
+        self.list
+    }
+
+    /// Items of a list, each holding a single value of the field.
+    pub fn items(&self) -> impl Iterator<Item = FieldValue> + '_ {
+        self.item_bytes().map(|bytes| {
+            let mut item = Self::new_item(self.desc);
+            item.set_bytes(bytes);
+            item
+        })
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.item_bytes().count()
+    }
+
+    pub fn get_item(&self, index: usize) -> Option<FieldValue> {
+        self.items().nth(index)
+    }
+
+    /// Adds `item` to the end of the list, fails with [`CommandError::ListFull`] if the
+    /// list holds the maximum number of items or the items don't fit the value.
+    pub fn push_item(&mut self, item: FieldValue) -> Result<(), CommandError> {
+        self.data = self.encode_items(self.items().chain([item]))?;
+        Ok(())
+    }
+
+    pub fn set_item(&mut self, index: usize, item: FieldValue) -> Result<(), CommandError> {
+        if index >= self.item_count() {
+            return Err(CommandError::InvalidData);
+        }
+        let mut item = Some(item);
+        let items = self.items().enumerate()
+            .map(|(i, current)| if i == index { item.take().unwrap() } else { current });
+        self.data = self.encode_items(items)?;
+        Ok(())
+    }
+
+    pub fn remove_item(&mut self, index: usize) -> Result<(), CommandError> {
+        if index >= self.item_count() {
+            return Err(CommandError::InvalidData);
+        }
+        self.data = self.encode_items(self.items().enumerate().filter(|(i, _)| *i != index).map(|(_, item)| item))?;
+        Ok(())
+    }
+
+    pub fn clear_items(&mut self) {
+        self.data = [0u8; VALUE_LENGTH];
+    }
+
+    /// Applies a `EditList` command to the list.
+    pub fn edit_items(&mut self, edit: ListEdit) -> Result<(), CommandError> {
+        if !self.list {
+            return Err(CommandError::NotSupported);
+        }
+        match edit {
+            ListEdit::Append(item) => self.push_item(item),
+            ListEdit::Replace(index, item) => self.set_item(index as usize, item),
+            ListEdit::Remove(index) => self.remove_item(index as usize),
+            ListEdit::Clear => {
+                self.clear_items();
+                Ok(())
+            },
+        }
+    }
+
+    /// Value bytes of the items of a list, a truncated item ends the list.
+    fn item_bytes(&self) -> impl Iterator<Item = &[u8]> {
+        let mut items = self.data[1..=self.len()].iter().as_slice();
+        core::iter::from_fn(move || {
+            let (&len, rest) = items.split_first().filter(|(len, _)| **len != 0)?;
+            let item = rest.get(..len as usize)?;
+            items = &rest[item.len()..];
+            Some(item)
+        })
+    }
+
+    fn encode_items(&self, items: impl Iterator<Item = FieldValue>) -> Result<[u8; VALUE_LENGTH], CommandError> {
+        let mut data = [0u8; VALUE_LENGTH];
+        let mut end = 0;
+        for (count, item) in items.enumerate() {
+            let bytes = item.get_bytes();
+            if bytes.is_empty() {
+                return Err(CommandError::InvalidData);
+            }
+            if count >= self.desc.max_items as usize || end + 1 + bytes.len() >= VALUE_LENGTH {
+                return Err(CommandError::ListFull);
             }
+            data[end + 1] = bytes.len() as u8;
+            data[end + 2..=end + 1 + bytes.len()].copy_from_slice(bytes);
+            end += 1 + bytes.len();
         }
-        self.data[0] = 1;
-        self.data
+        data[0] = end as u8;
+        Ok(data)
+    }
+
+    /// Number of rows of a table, see [`crate::table`].
+    pub fn row_count(&self) -> u8 {
+        self.data[1]
+    }
+
+    pub fn set_row_count(&mut self, rows: u8) {
+        self.data[1] = rows.min(self.desc.max_items);
+        self.set_len(1);
     }
 
     pub fn with_integer(mut self, value: i64) -> Self {
@@ -120,24 +399,236 @@ impl FieldValue{
         i64::from_le_bytes(value_bytes)
     }
 
+    /// Sets an integer, clamped to the range of the field and rounded to its step. Unsigned
+    /// fields store the bits of a `u64`, see [`FieldValue::set_unsigned`].
     pub fn set_integer(&mut self, value: i64) {
-        let value_bytes = if let Constraints::Range(range) = &self.desc.constraints {
-            value.clamp(range.start as i64, range.end as i64) 
-        } else { 
-            value 
-        }.to_le_bytes();
-        for i in 0..8 {
-            self.data[i + 1] = value_bytes[i];
+        let format = &self.desc.format;
+        let wide = |value: i64| if format.unsigned { value as u64 as i128 } else { value as i128 };
+        let (min, max) = self.desc.bounds();
+        let (min, max) = (wide(min), wide(max));
+        let mut value = wide(value).clamp(min, max);
+        if format.step > 1 {
+            let step = format.step as i128;
+            value = min + (value - min + step / 2) / step * step;
+            if value > max {
+                value -= step;
+            }
         }
+        self.data[1..=8].copy_from_slice(&(value as i64).to_le_bytes());
+        self.set_len(8);
+    }
+
+    pub fn get_unsigned(&self) -> u64 {
+        self.get_integer() as u64
+    }
+
+    pub fn set_unsigned(&mut self, value: u64) {
+        self.set_integer(value as i64);
+    }
+
+    pub fn with_decimal(mut self, value: f64) -> Self {
+        self.set_decimal(value);
+        self
+    }
+
+    /// Decimal value, the stored units are available with [`FieldValue::get_integer`].
+    pub fn get_decimal(&self) -> f64 {
+        self.get_integer() as f64 / scale_factor(self.desc.format.scale) as f64
+    }
+
+    /// Sets a decimal value, rounded to the field scale and clamped to its range.
+    pub fn set_decimal(&mut self, value: f64) {
+        self.set_integer(decimal_units(value, self.desc.format.scale));
+    }
+
+    pub fn with_bool(mut self, value: bool) -> Self {
+        self.set_bool(value);
+        self
+    }
+
+    pub fn get_bool(&self) -> bool {
+        self.data[1] != 0
+    }
+
+    pub fn set_bool(&mut self, value: bool) {
+        self.data[1] = value as u8;
+        self.set_len(1);
+    }
+
+    pub fn with_flags(mut self, bits: u64) -> Self {
+        self.set_flags(bits);
+        self
+    }
+
+    pub fn get_flags(&self) -> u64 {
+        self.get_integer() as u64
+    }
+
+    /// Sets the bits of a flags field, bits without a name are cleared.
+    pub fn set_flags(&mut self, bits: u64) {
+        let count = match &self.desc.constraints {
+            Constraints::Values(constraints) => constraints.value_provider.len().min(64),
+            _ => 64,
+        };
+        let mask = u64::MAX.checked_shr(64 - count as u32).unwrap_or(0);
+        self.data[1..=8].copy_from_slice(&(bits & mask).to_le_bytes());
         self.set_len(8);
     }
 
+    pub fn get_flag(&self, bit: u8) -> bool {
+        self.get_flags().checked_shr(bit as u32).unwrap_or(0) & 1 != 0
+    }
+
+    pub fn set_flag(&mut self, bit: u8, value: bool) {
+        let flag = 1u64.checked_shl(bit as u32).unwrap_or(0);
+        self.set_flags(if value { self.get_flags() | flag } else { self.get_flags() & !flag });
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.set_color(color);
+        self
+    }
+
+    /// Colour of the field, always opaque unless it is a [`ValueType::ColorAlpha`] field.
+    pub fn get_color(&self) -> Color {
+        match self.color_len() {
+            4 => Color::rgba(self.data[1], self.data[2], self.data[3], self.data[4]),
+            _ => Color::rgb(self.data[1], self.data[2], self.data[3]),
+        }
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.set_bytes(&color.bytes()[..self.color_len()]);
+    }
+
+    fn color_len(&self) -> usize {
+        match self.desc.variant {
+            EntryVariant::Field(ValueType::ColorAlpha) => 4,
+            _ => 3,
+        }
+    }
+
+    pub fn with_time(mut self, time: TimeOfDay) -> Self {
+        self.set_time(time);
+        self
+    }
+
+    pub fn get_time(&self) -> TimeOfDay {
+        TimeOfDay::from_seconds(self.get_integer())
+    }
+
+    /// Sets a time of day, clamped to the range of the field.
+    pub fn set_time(&mut self, time: TimeOfDay) {
+        self.set_integer(time.seconds());
+    }
+
+    pub fn with_date(mut self, date: Date) -> Self {
+        self.set_date(date);
+        self
+    }
+
+    pub fn get_date(&self) -> Date {
+        Date::from_days(self.get_integer())
+    }
+
+    pub fn set_date(&mut self, date: Date) {
+        self.set_integer(date.days());
+    }
+
+    pub fn with_datetime(mut self, datetime: DateTime) -> Self {
+        self.set_datetime(datetime);
+        self
+    }
+
+    /// Date and time of a timestamp, the seconds since the epoch are available with
+    /// [`FieldValue::get_integer`].
+    pub fn get_datetime(&self) -> DateTime {
+        DateTime::from_timestamp(self.get_integer())
+    }
+
+    pub fn set_datetime(&mut self, datetime: DateTime) {
+        self.set_integer(datetime.timestamp());
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.set_duration(duration);
+        self
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        Duration::from_secs(self.get_integer().max(0) as u64)
+    }
+
+    /// Sets a duration in whole seconds, clamped to the range of the field.
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.set_integer(duration.as_secs().min(i64::MAX as u64) as i64);
+    }
+
+    pub fn with_ipv4(mut self, addr: Ipv4Addr) -> Self {
+        self.set_ipv4(addr);
+        self
+    }
+
+    pub fn get_ipv4(&self) -> Ipv4Addr {
+        Ipv4Addr::from(<[u8; 4]>::try_from(&self.data[1..=4]).unwrap())
+    }
+
+    pub fn set_ipv4(&mut self, addr: Ipv4Addr) {
+        self.set_bytes(&addr.octets());
+    }
+
+    pub fn with_ipv6(mut self, addr: Ipv6Addr) -> Self {
+        self.set_ipv6(addr);
+        self
+    }
+
+    pub fn get_ipv6(&self) -> Ipv6Addr {
+        Ipv6Addr::from(<[u8; 16]>::try_from(&self.data[1..=16]).unwrap())
+    }
+
+    pub fn set_ipv6(&mut self, addr: Ipv6Addr) {
+        self.set_bytes(&addr.octets());
+    }
+
+    pub fn with_cidr(mut self, addr: IpAddr, prefix: u8) -> Self {
+        self.set_cidr(addr, prefix);
+        self
+    }
+
+    /// Address and prefix length of a network, `None` if the value is unset.
+    pub fn get_cidr(&self) -> Option<(IpAddr, u8)> {
+        net::decode_cidr(self.get_bytes())
+    }
+
+    /// Sets a network prefix, the prefix length is limited to the size of the address.
+    pub fn set_cidr(&mut self, addr: IpAddr, prefix: u8) {
+        let mut len = 0;
+        for (dst, byte) in self.data[1..].iter_mut().zip(net::cidr_bytes(addr, prefix)) {
+            *dst = byte;
+            len += 1;
+        }
+        self.set_len(len);
+    }
+
+    pub fn with_mac(mut self, mac: [u8; 6]) -> Self {
+        self.set_mac(mac);
+        self
+    }
+
+    pub fn get_mac(&self) -> [u8; 6] {
+        self.data[1..=6].try_into().unwrap()
+    }
+
+    pub fn set_mac(&mut self, mac: [u8; 6]) {
+        self.set_bytes(&mac);
+    }
+
     pub fn get_options(&self) -> Options {
         let len = self.data[0];
         let mut buf = [0u16; 31];
-        for i in 0..len as usize{
-            let offset = 2 * i as usize;
-            buf[i] = u16::from_le_bytes([self.data[offset+1], self.data[offset+2]]);
+        for (i, item) in buf.iter_mut().enumerate().take(len as usize) {
+            let offset = 2 * i;
+            *item = u16::from_le_bytes([self.data[offset+1], self.data[offset+2]]);
         }
         Options {
             len,
@@ -174,74 +665,106 @@ impl FieldValue{
     }
 
     pub fn get_text(&self) -> &str {
-        use core::str;
+        core::str::from_utf8(self.text_bytes()).unwrap_or_default()
+    }
 
-        let end = self.data.iter().position(|&b| b == 0).unwrap_or(self.data.len());
-        str::from_utf8(&self.data[1..end]).unwrap_or_default()
+    /// Value bytes up to the first zero byte, which ends text padded by older hosts.
+    fn text_bytes(&self) -> &[u8] {
+        let bytes = self.get_bytes();
+        &bytes[..bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len())]
     }
 
     pub fn set_status(&mut self, code: u8, text: &str) {
-        let max_len = text.floor_char_boundary(62);
+        let max_len = text.floor_char_boundary(VALUE_LENGTH - 2);
         let value = if text.len() != max_len {
             &text[0..max_len]
         } else {text};
         let value_bytes = value.as_bytes();
         self.data[1] = code;
-        for i in 0..value_bytes.len() {
-            self.data[i + 2] = value_bytes[i];
-        }
+        self.data[2..2 + value_bytes.len()].copy_from_slice(value_bytes);
         self.set_len(value_bytes.len() + 1);
     }
 
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.data[1..=self.byte_len()]
+    }
+
     pub fn set_bytes(&mut self, bytes: &[u8]) {
-        let clamped_len: usize = bytes.len().min(63);
-        for i in 0..clamped_len {
-            self.data[i + 1] = bytes[i];
-        }
+        let clamped_len: usize = bytes.len().min(VALUE_LENGTH - 1);
+        self.data[1..=clamped_len].copy_from_slice(&bytes[..clamped_len]);
         self.set_len(clamped_len);
     }
 
+    /// Sets text, cut to the maximum length of the field. Text that still breaks the
+    /// constraints of the field is replaced by its default, see [`FieldValue::try_set_text`].
     pub fn set_text(&mut self, value: &str) {
+        let value = &value[..value.floor_char_boundary(VALUE_LENGTH - 1)];
+        self.data[1..=value.len()].copy_from_slice(value.as_bytes());
+        self.data[value.len() + 1..].fill(0);
+        self.set_len(value.len());
+        self.clamp_text();
+    }
+
+    /// Sets text that has to meet the constraints of the field, see [`text::validate`].
+    pub fn try_set_text(&mut self, value: &str) -> Result<(), CommandError> {
+        text::validate(self.desc, value, false)?;
+        self.set_text(value);
+        Ok(())
+    }
 
+    fn clamp_text(&mut self) {
         let max_len = match &self.desc.constraints {
-            Constraints::Range(range) => range.end as usize,
-            _ => value.len(),
+            Constraints::Range(range) => range.end.max(0) as usize,
+            _ => VALUE_LENGTH - 1,
         };
-        let max_len = value.floor_char_boundary(max_len);
-        let value = if value.len() != max_len {
-            &value[0..max_len]
-        } else {value};
-        let value_bytes = value.as_bytes();
-        for i in 0..value_bytes.len() {
-            self.data[i + 1] = value_bytes[i];
+        let len = self.get_text().floor_char_boundary(max_len);
+        self.data[len + 1..].fill(0);
+        self.set_len(len);
+        if self.validate_text(false).is_err() {
+            *self = Self::default_item(self.desc);
         }
-        self.set_len(value_bytes.len());
-
-        // TODO: handle values constraints: not suggested and min/max
-        // if let Constraints::Values(value_constr) = &self.desc.constraints {
-             
-        // }
     }
 
+    /// Clamps the value to the constraints of the field. Lists are clamped item by item
+    /// and cut to the maximum number of items.
     pub fn clamp(&mut self) {
+        if self.list {
+            let items = self.items()
+                .map(|mut item| { item.clamp(); item })
+                .filter(|item| !item.is_empty())
+                .take(self.desc.max_items as usize);
+            if let Ok(data) = self.encode_items(items) {
+                self.data = data;
+            }
+            return;
+        }
         match self.desc.variant {
             EntryVariant::Field(field_type) => match field_type {
-                ValueType::Integer => {
+                ValueType::Integer | ValueType::Decimal | ValueType::Time | ValueType::Date
+                    | ValueType::Timestamp | ValueType::Duration => {
                     self.set_integer(self.get_integer());
                 },
-                ValueType::Text => {
-                    #[cfg(feature = "alloc")]
-                    self.set_text(&self.get_text().to_owned());
-                },
-                ValueType::Secret => {
-                    #[cfg(feature = "alloc")]
-                    self.set_text(&self.get_text().to_owned());
+                ValueType::Text | ValueType::Secret | ValueType::Hostname => {
+                    self.clamp_text();
                 },
                 ValueType::Status => {},
                 ValueType::Bytes => {},
                 ValueType::Options => {
                     self.set_options(self.get_options().as_slice());
-                }
+                },
+                ValueType::Bool => {
+                    self.set_bool(self.get_bool());
+                },
+                ValueType::Ipv4 | ValueType::Ipv6 | ValueType::Cidr | ValueType::Mac => {},
+                ValueType::Color | ValueType::ColorAlpha => {
+                    self.set_color(self.get_color());
+                },
+                ValueType::Flags => {
+                    self.set_flags(self.get_flags());
+                },
+                ValueType::Table => {
+                    self.set_row_count(self.row_count());
+                },
             },
             _ => {
                 warn!("tried to clamp entity variant {:?}", self.desc.variant)
@@ -257,19 +780,61 @@ impl FieldValue{
 
 impl From<FieldValue> for CommandResponse {
     fn from(value: FieldValue) -> Self {
-        Self::from_field_value(value)
+        Self::from_field_value(value, 0)
     }
 }
 
 #[cfg(test)]
 mod test {
 
+    use core::net::{IpAddr, Ipv4Addr};
+
+    use crate::command::CommandError;
     use crate::entry::{EntryDesc, integer};
+    use crate::config::VALUE_LENGTH;
     use crate::prelude::*;
 
     const DESC_STRVAL1: EntryDesc = prop("strval").as_entry();
     const DESC_INTVAL1: EntryDesc = integer("strval").writable().as_entry();
-    const OPT1_PROVIDER: [&'static str; 3] = ["item 1", "item 2", "item 3"];
+    const DESC_BOOLVAL1: EntryDesc = toggle("boolval").with_default_bool(true).as_entry();
+    const DESC_DECVAL1: EntryDesc = decimal("decval", 1)
+        .writable()
+        .with_unit("°C")
+        .with_decimal_range(-10.0, 40.0)
+        .with_default_decimal(21.5)
+        .as_entry();
+    const DESC_STEPVAL1: EntryDesc = integer("stepval")
+        .writable()
+        .with_range(-50..900)
+        .with_step(100)
+        .as_entry();
+    const DESC_U64VAL1: EntryDesc = integer("u64val")
+        .writable()
+        .with_unsigned_range(1 << 40..u64::MAX - 1)
+        .with_radix(16)
+        .as_entry();
+    const DESC_IPVAL1: EntryDesc = ipv4("ipval").with_default_bytes(&[192, 168, 1, 1]).as_entry();
+    const DESC_NETVAL1: EntryDesc = cidr("netval").as_entry();
+    const DESC_HOSTVAL1: EntryDesc = hostname("hostval").as_entry();
+    const PALETTE: [&str; 2] = ["Red #ff0000", "Warm white #ffd8a8"];
+    const DESC_COLORVAL1: EntryDesc = color("colorval")
+        .with_suggestions(&PALETTE)
+        .with_default_color(Color::hex(0xffd8a8))
+        .as_entry();
+    const DESC_COLORVAL2: EntryDesc = color("colorval").with_alpha().as_entry();
+    const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    const DESC_FLAGVAL1: EntryDesc = flags("flagval", &WEEKDAYS).with_default_flags(0b0011111).as_entry();
+    const DESC_LISTVAL1: EntryDesc = integer("listval").writable().with_range(0..10).list(3).as_entry();
+    const DESC_CODEVAL1: EntryDesc = prop("codeval")
+        .with_length(4, 8)
+        .with_chars(CharClass::Alphanumeric)
+        .with_default_text("0000")
+        .as_entry();
+    const DESC_PHONEVAL1: EntryDesc = prop("phoneval").with_pattern("+## *").as_entry();
+    const MODES: [&str; 2] = ["eco", "boost"];
+    const DESC_MODEVAL1: EntryDesc = prop("modeval").with_options(&MODES).with_default_text("eco").as_entry();
+    const DESC_MODEVAL2: EntryDesc = prop("modeval").with_suggestions(&MODES).with_default_text("eco").as_entry();
+    const OPT1_PROVIDER: [&str; 3] = ["item 1", "item 2", "item 3"];
     const OPT1_DEFAULT: [u16; 0] = [];
     const DESC_OPTVAL1: EntryDesc = option("strval", &OPT1_PROVIDER)
         .with_default_options(&OPT1_DEFAULT)
@@ -308,4 +873,234 @@ mod test {
         assert_eq!(3, fv.len());
         assert_eq!(&[1, 2, 0], fv.get_options().as_slice());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn field_value_bool_roundtrip() {
+        let fv = FieldValue::from_store(&DESC_BOOLVAL1, [0u8; VALUE_LENGTH]);
+        assert!(fv.get_bool());
+
+        let fv = FieldValue::new(&DESC_BOOLVAL1).with_bool(false);
+        assert_eq!(1, fv.len());
+        let fv = FieldValue::from_store(&DESC_BOOLVAL1, fv.into_store_bytes());
+        assert!(!fv.get_bool());
+
+        let fv = FieldValue::from_message(&DESC_BOOLVAL1, &[7]).unwrap();
+        assert_eq!(&[1], fv.get_bytes());
+    }
+
+    #[test]
+    fn field_value_decimal_roundtrip() {
+        let fv = FieldValue::from_store(&DESC_DECVAL1, [0u8; VALUE_LENGTH]);
+        assert_eq!(215, fv.get_integer());
+        assert_eq!(21.5, fv.get_decimal());
+
+        let fv = FieldValue::new(&DESC_DECVAL1).with_decimal(-3.26);
+        assert_eq!(-33, fv.get_integer());
+        let fv = FieldValue::from_store(&DESC_DECVAL1, fv.into_store_bytes());
+        assert_eq!(-3.3, fv.get_decimal());
+
+        // Clamped to the range in stored units
+        let fv = FieldValue::new(&DESC_DECVAL1).with_decimal(100.0);
+        assert_eq!(400, fv.get_integer());
+        let fv = FieldValue::from_message(&DESC_DECVAL1, &(-1000i64).to_le_bytes()).unwrap();
+        assert_eq!(-100, fv.get_integer());
+    }
+
+    #[test]
+    fn field_value_integer_step() {
+        let mut fv = FieldValue::new(&DESC_STEPVAL1);
+        fv.set_integer(120);
+        assert_eq!(150, fv.get_integer());
+        fv.set_integer(99);
+        assert_eq!(50, fv.get_integer());
+        fv.set_integer(-400);
+        assert_eq!(-50, fv.get_integer());
+        // The last step that fits the range
+        fv.set_integer(10_000);
+        assert_eq!(850, fv.get_integer());
+    }
+
+    #[test]
+    fn field_value_unsigned_range() {
+        let mut fv = FieldValue::new(&DESC_U64VAL1);
+        fv.set_unsigned(u64::MAX);
+        assert_eq!(u64::MAX - 1, fv.get_unsigned());
+        fv.set_unsigned(3);
+        assert_eq!(1 << 40, fv.get_unsigned());
+        fv.set_unsigned(i64::MAX as u64 + 7);
+        assert_eq!(i64::MAX as u64 + 7, fv.get_unsigned());
+    }
+
+    #[test]
+    fn field_value_network_roundtrip() {
+        let fv = FieldValue::from_store(&DESC_IPVAL1, [0u8; VALUE_LENGTH]);
+        assert_eq!(Ipv4Addr::new(192, 168, 1, 1), fv.get_ipv4());
+
+        let fv = FieldValue::from_message(&DESC_IPVAL1, &[10, 0, 0, 7, 0, 0, 0]).unwrap();
+        assert_eq!(4, fv.len());
+        let fv = FieldValue::from_store(&DESC_IPVAL1, fv.into_store_bytes());
+        assert_eq!(Ipv4Addr::new(10, 0, 0, 7), fv.get_ipv4());
+
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10));
+        let fv = FieldValue::new(&DESC_NETVAL1).with_cidr(addr, 24);
+        assert_eq!(&[4, 24, 192, 168, 1, 10], fv.get_bytes());
+        let fv = FieldValue::from_message(&DESC_NETVAL1, fv.get_bytes()).unwrap();
+        assert_eq!(Some((addr, 24)), fv.get_cidr());
+    }
+
+    #[test]
+    fn field_value_network_invalid() {
+        assert!(matches!(FieldValue::from_message(&DESC_IPVAL1, &[10, 0, 0, 7, 1]), Err(CommandError::InvalidData)));
+        assert!(matches!(FieldValue::from_message(&DESC_NETVAL1, &[4, 33, 10, 0, 0, 0]), Err(CommandError::InvalidData)));
+        assert!(matches!(FieldValue::from_message(&DESC_HOSTVAL1, b"bad_host"), Err(CommandError::InvalidData)));
+
+        assert!(FieldValue::from_message(&DESC_HOSTVAL1, b"clock-").is_err());
+
+        // Hostnames written in several messages may end with a hyphen in between
+        let mut fv = FieldValue::new(&DESC_HOSTVAL1);
+        fv.splice(0, b"clock-").unwrap();
+        fv.splice(6, b"01.local").unwrap();
+        assert_eq!("clock-01.local", fv.get_text());
+        assert!(fv.splice(0, b"-clock").is_err());
+    }
+
+    #[test]
+    fn field_value_text_constraints() {
+        let invalid = |desc, bytes| matches!(FieldValue::from_message(desc, bytes), Err(CommandError::InvalidValue));
+        assert_eq!("ab12", FieldValue::from_message(&DESC_CODEVAL1, b"ab12\0\0\0").unwrap().get_text());
+        assert!(invalid(&DESC_CODEVAL1, b"ab1"));
+        assert!(invalid(&DESC_CODEVAL1, b"ab12ab12a"));
+        assert!(invalid(&DESC_CODEVAL1, b"ab-12"));
+        assert!(FieldValue::from_message(&DESC_PHONEVAL1, b"+46 8 123 456").is_ok());
+        assert!(invalid(&DESC_PHONEVAL1, b"046 8 123 456"));
+        assert!(FieldValue::from_message(&DESC_MODEVAL1, b"boost").is_ok());
+        assert!(invalid(&DESC_MODEVAL1, b"turbo"));
+        assert!(invalid(&DESC_MODEVAL1, b""));
+        assert!(FieldValue::from_message(&DESC_MODEVAL2, b"turbo").is_ok());
+        assert!(matches!(FieldValue::from_message(&DESC_STRVAL1, &[0xff, b'x']), Err(CommandError::InvalidData)));
+
+        // Values written in several messages are checked as far as they go
+        let mut fv = FieldValue::new(&DESC_MODEVAL1);
+        fv.splice(0, b"boo").unwrap();
+        fv.splice(3, b"st").unwrap();
+        assert!(matches!(fv.splice(0, b"tu"), Err(CommandError::InvalidValue)));
+        let mut fv = FieldValue::new(&DESC_STRVAL1);
+        fv.splice(0, &"é".as_bytes()[..1]).unwrap();
+        fv.splice(1, &"é".as_bytes()[1..]).unwrap();
+        assert_eq!("é", fv.get_text());
+
+        // The device sets text that is cut to length or falls back to the default
+        let mut fv = FieldValue::new(&DESC_CODEVAL1);
+        fv.set_text("abcd1234xyz");
+        assert_eq!("abcd1234", fv.get_text());
+        fv.set_text("ab 12");
+        assert_eq!("0000", fv.get_text());
+        assert!(matches!(fv.try_set_text("ab 12"), Err(CommandError::InvalidValue)));
+        fv.try_set_text("abc123").unwrap();
+        assert_eq!("abc123", fv.get_text());
+        let mut fv = FieldValue::new(&DESC_MODEVAL1);
+        fv.set_text("turbo");
+        assert_eq!("eco", fv.get_text());
+    }
+
+    #[test]
+    fn field_value_color_roundtrip() {
+        let fv = FieldValue::from_store(&DESC_COLORVAL1, [0u8; VALUE_LENGTH]);
+        assert_eq!(Color::hex(0xffd8a8), fv.get_color());
+        assert_eq!(3, fv.len());
+
+        // Colours without alpha are opaque
+        let fv = FieldValue::new(&DESC_COLORVAL1).with_color(Color::rgba(1, 2, 3, 4));
+        assert_eq!(&[1, 2, 3], fv.get_bytes());
+        assert_eq!(Color::rgb(1, 2, 3), fv.get_color());
+
+        let fv = FieldValue::from_message(&DESC_COLORVAL2, &[1, 2, 3, 4, 0, 0]).unwrap();
+        assert_eq!(4, fv.len());
+        let fv = FieldValue::from_store(&DESC_COLORVAL2, fv.into_store_bytes());
+        assert_eq!(Color::rgba(1, 2, 3, 4), fv.get_color());
+    }
+
+    #[test]
+    fn field_value_flags() {
+        let mut fv = FieldValue::from_store(&DESC_FLAGVAL1, [0u8; VALUE_LENGTH]);
+        assert_eq!(0b0011111, fv.get_flags());
+        fv.set_flag(6, true);
+        fv.set_flag(0, false);
+        assert!(fv.get_flag(6) && !fv.get_flag(0));
+        fv.set_flag(40, true);
+        assert_eq!(0b1011110, fv.get_flags());
+
+        // Bits past the names are dropped
+        let fv = FieldValue::from_message(&DESC_FLAGVAL1, &u64::MAX.to_le_bytes()).unwrap();
+        assert_eq!(0b1111111, fv.get_flags());
+    }
+
+    #[test]
+    fn field_value_list() {
+        let fv = FieldValue::from_store(&DESC_LISTVAL1, [0u8; VALUE_LENGTH]);
+        assert!(fv.is_list());
+        assert_eq!(0, fv.item_count());
+
+        let mut fv = FieldValue::new(&DESC_LISTVAL1);
+        for value in [3, 42, -1] {
+            fv.push_item(FieldValue::new_item(&DESC_LISTVAL1).with_integer(value)).unwrap();
+        }
+        let item = FieldValue::new_item(&DESC_LISTVAL1).with_integer(5);
+        assert!(matches!(fv.push_item(item), Err(CommandError::ListFull)));
+
+        fv.set_item(0, FieldValue::new_item(&DESC_LISTVAL1).with_integer(7)).unwrap();
+        fv.remove_item(1).unwrap();
+        let fv = FieldValue::from_store(&DESC_LISTVAL1, fv.into_store_bytes());
+        let items: [i64; 2] = core::array::from_fn(|i| fv.get_item(i).unwrap().get_integer());
+        assert_eq!([7, 0], items);
+        assert!(fv.get_item(2).is_none());
+
+        // Items written by the host are clamped one by one
+        let mut bytes = [0u8; 18];
+        bytes[0] = 8;
+        bytes[1..9].copy_from_slice(&99i64.to_le_bytes());
+        bytes[9] = 8;
+        let fv = FieldValue::from_message(&DESC_LISTVAL1, &bytes).unwrap();
+        assert_eq!(2, fv.item_count());
+        assert_eq!(10, fv.get_item(0).unwrap().get_integer());
+        assert!(FieldValue::from_message(&DESC_LISTVAL1, &bytes[..12]).is_err());
+    }
+
+    #[test]
+    fn field_value_clamped_message() {
+        let clamped = |desc, bytes: &[u8]| FieldValue::from_message_clamped(desc, bytes).unwrap().1;
+        let padded = |value: i64| {
+            let mut bytes = [0u8; 62];
+            bytes[..8].copy_from_slice(&value.to_le_bytes());
+            bytes
+        };
+        assert!(!clamped(&DESC_STEPVAL1, &padded(250)));
+        assert!(clamped(&DESC_STEPVAL1, &padded(120)));
+        assert!(clamped(&DESC_DECVAL1, &padded(-1000)));
+        assert!(!clamped(&DESC_BOOLVAL1, &[1, 0, 0]));
+        assert!(clamped(&DESC_BOOLVAL1, &[7]));
+
+        assert!(!clamped(&DESC_CODEVAL1, b"ab12\0\0\0"));
+        assert!(!clamped(&DESC_FLAGVAL1, &0b101u64.to_le_bytes()));
+        assert!(clamped(&DESC_FLAGVAL1, &u64::MAX.to_le_bytes()));
+
+        // Invalid option indexes are dropped
+        assert!(!clamped(&DESC_OPTVAL1, &[1, 0, 2, 0, 0, 0]));
+        let (fv, clamped_options) = FieldValue::from_message_clamped(&DESC_OPTVAL1, &[1, 0, 7, 0]).unwrap();
+        assert!(clamped_options);
+        assert_eq!(&[1], fv.get_options().as_slice());
+        assert!(fv.into_store_bytes()[3..].iter().all(|b| *b == 0));
+        let (fv, clamped_bool) = FieldValue::from_message_clamped(&DESC_BOOLVAL1, &[1, 7, 7]).unwrap();
+        assert!(clamped_bool);
+        assert!(fv.into_store_bytes()[2..].iter().all(|b| *b == 0));
+
+        let mut bytes = [0u8; 18];
+        bytes[0] = 8;
+        bytes[1] = 3;
+        bytes[9] = 8;
+        bytes[10] = 4;
+        assert!(!clamped(&DESC_LISTVAL1, &bytes));
+        bytes[10] = 12;
+        assert!(clamped(&DESC_LISTVAL1, &bytes));
+    }
+}
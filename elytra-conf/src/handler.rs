@@ -0,0 +1,228 @@
+use core::prelude::rust_2024::{*};
+use core::future::Future;
+use core::time::Duration;
+
+use crate::{
+    command::CommandError,
+    field::FieldValue,
+    table::Record,
+    identity::SerialNumber,
+    traits::{ActionIndex, InfoIndex, PropIndex},
+};
+
+/// Application logic for the commands that [`Config::dispatch`](crate::config::Config::dispatch)
+/// cannot answer on its own. `Meta`, `Query` and `Dump` are handled by the config itself.
+///
+/// Every method defaults to [`CommandError::NotSupported`], so a handler only needs
+/// to implement the commands the firmware actually supports.
+#[allow(unused_variables)]
+pub trait CommandHandler<P: PropIndex, I: InfoIndex, A: ActionIndex> {
+    fn read_prop(&mut self, prop: P) -> Result<FieldValue, CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    fn write_prop(&mut self, prop: P, value: FieldValue) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Row `row` of the table `prop`, [`CommandError::NoContent`] past the last row.
+    fn read_row(&mut self, prop: P, row: u8) -> Result<Record, CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Replaces row `row` of the table `prop`, or appends it if `row` is the number of rows.
+    fn write_row(&mut self, prop: P, row: u8, record: Record) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Removes row `row` of the table `prop`, moving the rows after it up.
+    fn delete_row(&mut self, prop: P, row: u8) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Starts staging prop writes instead of applying them, see [`crate::transaction`].
+    fn begin_transaction(&mut self) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Applies the prop writes staged since [`begin_transaction`](Self::begin_transaction),
+    /// all of them or none.
+    fn commit_transaction(&mut self) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Applies the staged prop writes like [`commit_transaction`](Self::commit_transaction)
+    /// and reverts them unless [`confirm_transaction`](Self::confirm_transaction) follows
+    /// `within`.
+    fn commit_transaction_pending(&mut self, within: Duration) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Keeps the prop writes of a pending commit.
+    fn confirm_transaction(&mut self) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Drops the prop writes staged since [`begin_transaction`](Self::begin_transaction),
+    /// or reverts a pending commit.
+    fn abort_transaction(&mut self) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    fn read_info(&mut self, info: I) -> Result<FieldValue, CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    fn write_info(&mut self, info: I, value: FieldValue) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    fn do_action(&mut self, action: A) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Serial number or UUID reported by the `Identity` command.
+    fn serial_number(&mut self) -> Option<SerialNumber> {
+        None
+    }
+
+    fn noop(&mut self) {}
+
+    /// Resets every prop to its default and deletes all table rows, all of them or none.
+    fn factory_reset(&mut self) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Buffer for values written in several `WritePropAt` commands, so that
+    /// [`write_prop`](Self::write_prop) only sees the whole value. Without one, values have
+    /// to fit a single `WriteProp`.
+    fn chunk_buffer(&mut self) -> Option<&mut ChunkBuffer> {
+        None
+    }
+}
+
+/// Async counterpart of [`CommandHandler`], used with
+/// [`Config::dispatch_async`](crate::config::Config::dispatch_async) so that handlers
+/// can await storage or network operations instead of blocking the transport task.
+///
+/// Implementors can use `async fn` for the methods they override.
+#[allow(unused_variables)]
+pub trait AsyncCommandHandler<P: PropIndex, I: InfoIndex, A: ActionIndex> {
+    fn read_prop(&mut self, prop: P) -> impl Future<Output = Result<FieldValue, CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn write_prop(&mut self, prop: P, value: FieldValue) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn read_row(&mut self, prop: P, row: u8) -> impl Future<Output = Result<Record, CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn write_row(&mut self, prop: P, row: u8, record: Record) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn delete_row(&mut self, prop: P, row: u8) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn begin_transaction(&mut self) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn commit_transaction(&mut self) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn commit_transaction_pending(&mut self, within: Duration) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn confirm_transaction(&mut self) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn abort_transaction(&mut self) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn read_info(&mut self, info: I) -> impl Future<Output = Result<FieldValue, CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn write_info(&mut self, info: I, value: FieldValue) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn do_action(&mut self, action: A) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    fn serial_number(&mut self) -> impl Future<Output = Option<SerialNumber>> {
+        async { None }
+    }
+
+    fn noop(&mut self) -> impl Future<Output = ()> {
+        async {}
+    }
+
+    fn factory_reset(&mut self) -> impl Future<Output = Result<(), CommandError>> {
+        async { Err(CommandError::NotSupported) }
+    }
+
+    /// See [`CommandHandler::chunk_buffer`].
+    fn chunk_buffer(&mut self) -> Option<&mut ChunkBuffer> {
+        None
+    }
+}
+
+/// Collects the chunks of a prop value written in several `WritePropAt` commands until
+/// the last one arrives, see [`CommandHandler::chunk_buffer`].
+#[derive(Default)]
+pub struct ChunkBuffer {
+    /// Index of the prop, total length and the bytes received so far.
+    staged: Option<(usize, u16, FieldValue)>,
+}
+
+impl ChunkBuffer {
+    pub const fn new() -> Self {
+        Self { staged: None }
+    }
+
+    /// Adds `chunk` at `offset` of a value of `total` bytes and returns the whole value
+    /// with its last chunk. A chunk at offset 0 starts over, any error drops the value.
+    pub fn push<P: PropIndex>(&mut self, prop: P, offset: u16, total: u16, chunk: &[u8]) -> Result<Option<FieldValue>, CommandError> {
+        match self.append(prop, offset, total, chunk) {
+            Ok(false) => Ok(None),
+            result => {
+                let staged = self.staged.take();
+                result.map(|_| staged.map(|(_, _, value)| value))
+            },
+        }
+    }
+
+    /// Splices `chunk` into the staged value and returns whether it is complete.
+    fn append<P: PropIndex>(&mut self, prop: P, offset: u16, total: u16, chunk: &[u8]) -> Result<bool, CommandError> {
+        if offset == 0 {
+            self.staged = Some((prop.as_index(), total, FieldValue::new(prop.get_entry())));
+        }
+        let Some((index, expected, value)) = &mut self.staged else {
+            return Err(CommandError::InvalidData);
+        };
+        if *index != prop.as_index() || *expected != total {
+            return Err(CommandError::InvalidData);
+        }
+        value.splice(offset as usize, chunk)?;
+        let len = value.get_bytes().len();
+        if len > total as usize {
+            return Err(CommandError::InvalidData);
+        }
+        if len < total as usize {
+            return Ok(false);
+        }
+        value.validate_spliced()?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,126 @@
+use core::prelude::rust_2024::{*};
+
+/// Largest serial number a handler can report.
+pub const SERIAL_LENGTH: usize = 32;
+
+/// Longest identity string, as they are sent with a 1 byte length.
+pub const IDENTITY_TEXT_LENGTH: usize = u8::MAX as usize;
+
+/// Product and firmware the config describes, returned by the `Identity` command.
+#[derive(Debug, Clone, Copy)]
+pub struct Identity {
+    pub vendor: &'static str,
+    pub product: &'static str,
+    pub firmware: &'static str,
+    pub hardware: Option<&'static str>,
+}
+
+impl Identity {
+    pub(crate) const UNKNOWN: Self = Self::new("", "", "");
+
+    pub const fn new(vendor: &'static str, product: &'static str, firmware: &'static str) -> Self {
+        if vendor.len() > IDENTITY_TEXT_LENGTH { panic!("vendor is too long") }
+        if product.len() > IDENTITY_TEXT_LENGTH { panic!("product is too long") }
+        if firmware.len() > IDENTITY_TEXT_LENGTH { panic!("firmware is too long") }
+        Self {
+            vendor,
+            product,
+            firmware,
+            hardware: None,
+        }
+    }
+
+    pub const fn with_hardware(self, revision: &'static str) -> Self {
+        if revision.len() > IDENTITY_TEXT_LENGTH { panic!("hardware revision is too long") }
+        Self { hardware: Some(revision), ..self }
+    }
+
+    /// Serializes the identity as length prefixed strings (1 byte each): vendor, product,
+    /// firmware and hardware revision (empty if unknown), followed by `serial`.
+    pub(crate) fn bytes(self, serial: Option<SerialNumber>) -> impl Iterator<Item = u8> {
+        let hardware = self.hardware.unwrap_or_default();
+        let serial = serial.unwrap_or(SerialNumber::NONE);
+        [self.vendor, self.product, self.firmware, hardware].into_iter()
+            .flat_map(|text| [text.len() as u8].into_iter().chain(text.bytes()))
+            .chain(serial.into_bytes())
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SerialKind {
+    None = 0,
+    Text = 1,
+    Bytes = 2,
+}
+
+/// Serial number or UUID of the device, provided at runtime by
+/// [`CommandHandler::serial_number`](crate::handler::CommandHandler::serial_number).
+#[derive(Debug, Clone, Copy)]
+pub struct SerialNumber {
+    kind: SerialKind,
+    len: u8,
+    buf: [u8; SERIAL_LENGTH],
+}
+
+impl SerialNumber {
+    const NONE: Self = Self { kind: SerialKind::None, len: 0, buf: [0; SERIAL_LENGTH] };
+
+    /// Text serial number, truncated to [`SERIAL_LENGTH`] bytes.
+    pub fn text(text: &str) -> Self {
+        let text = &text[..text.floor_char_boundary(SERIAL_LENGTH)];
+        Self::new(SerialKind::Text, text.as_bytes())
+    }
+
+    /// Binary serial number such as a chip id, truncated to [`SERIAL_LENGTH`] bytes.
+    pub fn bytes(bytes: &[u8]) -> Self {
+        Self::new(SerialKind::Bytes, &bytes[..bytes.len().min(SERIAL_LENGTH)])
+    }
+
+    pub fn uuid(uuid: [u8; 16]) -> Self {
+        Self::bytes(&uuid)
+    }
+
+    fn new(kind: SerialKind, bytes: &[u8]) -> Self {
+        let mut buf = [0; SERIAL_LENGTH];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Self { kind, len: bytes.len() as u8, buf }
+    }
+
+    pub fn kind(&self) -> SerialKind {
+        self.kind
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len as usize]
+    }
+
+    fn into_bytes(self) -> impl Iterator<Item = u8> {
+        [self.kind as u8, self.len].into_iter().chain(self.buf.into_iter().take(self.len as usize))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IDENTITY_TEXT_LENGTH, Identity};
+
+    const LONG: &str = match str::from_utf8(&[b'x'; IDENTITY_TEXT_LENGTH + 1]) {
+        Ok(text) => text,
+        Err(_) => panic!(),
+    };
+
+    #[test]
+    fn identity_bytes() {
+        let text = &LONG[..IDENTITY_TEXT_LENGTH];
+        let identity = Identity::new("Elytra", text, "1.0");
+        assert!(identity.bytes(None).take(8).eq(*b"\x06Elytra\xff"));
+        assert_eq!(1 + 6 + 1 + 255 + 1 + 3 + 1 + 2, identity.bytes(None).count());
+    }
+
+    #[test]
+    #[should_panic(expected = "product is too long")]
+    fn identity_rejects_long_text() {
+        Identity::new("Elytra", LONG, "1.0");
+    }
+}
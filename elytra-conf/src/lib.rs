@@ -4,11 +4,23 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod color;
 pub mod config;
 pub mod traits;
 pub mod entry;
 pub mod field;
 pub mod command;
+pub mod handler;
+pub mod identity;
+pub mod net;
+pub mod store;
+pub mod table;
+pub mod text;
+pub mod transaction;
+pub mod time;
 pub mod values;
 pub mod prelude;
 #[cfg(feature = "macros")]
@@ -1,6 +1,6 @@
 #[macro_export]
 macro_rules! indexed_entry {
-    ($indexty:ty: $name:ident { $( $s:ident: $sx:expr ),+ }) => {
+    ($indexty:ty: $name:ident { $( $s:ident $( ( id = $id:expr ) )?: $sx:expr ),+ }) => {
         #[derive(Clone, Copy, Debug, PartialEq, Eq)]
         pub enum $name {
             $(
@@ -25,12 +25,23 @@ macro_rules! indexed_entry {
             fn count() -> usize {
                 Self::ENTRIES.len()
             }
+            fn id(self) -> u32 {
+                Self::IDS[self.as_index()]
+            }
+            fn from_id(id: u32) -> Option<Self> {
+                Self::IDS.iter().position(|i| *i == id).and_then(|i| Self::from_byte(i as u8))
+            }
         }
         impl $name {
             pub const ENTRIES: [$crate::entry::EntryDesc; ${count($sx)}] = [$(
                 $sx.as_entry(),
             )*];
+            pub const IDS: [u32; ${count($sx)}] = [$(
+                $crate::entry_id!($s $(, $id)?),
+            )*];
         }
+        const _: () = assert!($crate::traits::ids_valid(&$name::IDS), concat!("duplicate or reserved entry id in ", stringify!($name)));
+        const _: () = assert!($crate::traits::row_keys_valid(&$name::ENTRIES, &$name::IDS), concat!("table row key collides with an entry id in ", stringify!($name)));
     };
     ($indexty:ty: $name:ident) => {
         #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -48,10 +59,27 @@ macro_rules! indexed_entry {
             fn count() -> usize {
                 0
             }
+            fn id(self) -> u32 {
+                panic!("empty index")
+            }
+            fn from_id(_: u32) -> Option<Self> {
+                None
+            }
         }
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! entry_id {
+    ($s:ident) => {
+        $crate::traits::entry_id(stringify!($s))
+    };
+    ($s:ident, $id:expr) => {
+        $id
+    };
+}
+
 #[macro_export(local_inner_macros)]
 macro_rules! sections {
     ($name:ident {}) => {
@@ -81,10 +109,10 @@ macro_rules! actions {
 #[macro_export(local_inner_macros)]
 macro_rules! infos {
     ($name:ident {}) => {
-        indexed_entry!($crate::InfoIndex: $name)
+        indexed_entry!($crate::InfoIndex: $name);
     };
     ($name:ident) => {
-        indexed_entry!($crate::InfoIndex: $name)
+        indexed_entry!($crate::InfoIndex: $name);
     };
     ($name:ident { $($s:tt)+ }) => {
         indexed_entry!($crate::InfoIndex: $name { $($s)+ } );
@@ -107,6 +135,8 @@ macro_rules! props {
 #[macro_export(local_inner_macros)]
 macro_rules! elytra {
     ($cvis:vis $cident:ident: $tident:ident {
+        $( version: $version:expr, )?
+        $( identity: $identity:expr, )?
         info: $info:ty,
         props: $props:ty,
         sections: $sections:ty,
@@ -121,7 +151,7 @@ macro_rules! elytra {
                 ($ls, $lf),
                 )*
             )*],
-        );
+        )$( .with_version($version) )?$( .with_identity($identity) )?;
     };
     ( $cvis:vis $cident:ident: $tident:ident {
         info: { $($ix:tt)+ },
@@ -139,6 +169,8 @@ macro_rules! elytra {
         });
     };
     ( $cvis:vis $cident:ident: $tident:ident {
+        $( version: $version:expr, )?
+        $( identity: $identity:expr, )?
         info: $i:ident { $($ix:tt)* },
         props: $p:ident { $($px:tt)* },
         sections: $s:ident { $($sx:tt)* },
@@ -158,7 +190,7 @@ macro_rules! elytra {
                 ($ls, $lf),
                 )*
             )*],
-        );
+        )$( .with_version($version) )?$( .with_identity($identity) )?;
     };
 }
 
@@ -237,4 +269,23 @@ mod test {
         assert_eq!(3, S::count());
         assert_eq!("Top", S::ENTRIES[0].name);
     }
+
+    #[test]
+    fn test_entry_ids() {
+        props!(Before {
+            One: prop("One"),
+            Two(id = 7): prop("Two")
+        });
+        props!(After {
+            Two(id = 7): prop("Two"),
+            Zero: prop("Zero"),
+            One: prop("One")
+        });
+        assert_eq!(7, Before::Two.id());
+        assert_eq!(Before::One.id(), After::One.id());
+        assert_ne!(After::Zero.id(), After::One.id());
+        assert_eq!(Some(After::One), After::from_id(Before::One.id()));
+        assert_eq!(Some(After::Two), After::from_id(7));
+        assert_eq!(None, After::from_id(8));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,157 @@
+use core::prelude::rust_2024::{*};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::{command::CommandError, values::ValueType};
+
+/// Longest hostname, in bytes and without a trailing dot.
+pub const MAX_HOSTNAME_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+const CIDR_V4: u8 = 4;
+const CIDR_V6: u8 = 6;
+
+/// Encodes a network prefix as a [`ValueType::Cidr`] value: the address family (`4` or `6`),
+/// the prefix length and the address bytes. The prefix length is limited to the address size.
+pub fn cidr_bytes(addr: IpAddr, prefix: u8) -> impl Iterator<Item = u8> {
+    let mut octets = [0u8; 16];
+    let (family, len) = match addr {
+        IpAddr::V4(addr) => {
+            octets[..4].copy_from_slice(&addr.octets());
+            (CIDR_V4, 4)
+        },
+        IpAddr::V6(addr) => {
+            octets = addr.octets();
+            (CIDR_V6, 16)
+        },
+    };
+    [family, prefix.min(max_prefix(addr))].into_iter().chain(octets.into_iter().take(len))
+}
+
+/// Decodes a [`ValueType::Cidr`] value, `None` if the family or prefix length is invalid.
+pub fn decode_cidr(bytes: &[u8]) -> Option<(IpAddr, u8)> {
+    let (family, prefix, octets) = match bytes {
+        [family, prefix, octets @ ..] => (*family, *prefix, octets),
+        _ => return None,
+    };
+    let addr = match family {
+        CIDR_V4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(octets.get(..4)?).ok()?)),
+        CIDR_V6 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(octets.get(..16)?).ok()?)),
+        _ => return None,
+    };
+    (prefix <= max_prefix(addr)).then_some((addr, prefix))
+}
+
+/// Length of the longest prefix of `addr`, 32 for IPv4 and 128 for IPv6.
+pub const fn max_prefix(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// Checks `text` is a hostname of dot separated labels made of letters, digits and hyphens,
+/// as in RFC 1123. A single trailing dot is allowed.
+pub fn is_hostname(text: &str) -> bool {
+    check_hostname(text, false)
+}
+
+/// Like [`is_hostname`], but the last label may end with a hyphen because the rest of the
+/// name has not been written yet.
+pub(crate) fn is_hostname_prefix(text: &str) -> bool {
+    check_hostname(text, true)
+}
+
+fn check_hostname(text: &str, partial: bool) -> bool {
+    let text = text.strip_suffix('.').unwrap_or(text);
+    if text.is_empty() || text.len() > MAX_HOSTNAME_LEN {
+        return false;
+    }
+    let mut labels = text.split('.').peekable();
+    while let Some(label) = labels.next() {
+        let last = labels.peek().is_none();
+        let valid = !label.is_empty()
+            && label.len() <= MAX_LABEL_LEN
+            && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            && !label.starts_with('-')
+            && (!label.ends_with('-') || (partial && last));
+        if !valid {
+            return false;
+        }
+    }
+    true
+}
+
+/// Validates a network value received from the host and returns its length, anything after
+/// the value has to be zero padding.
+pub(crate) fn validate(value_type: ValueType, bytes: &[u8]) -> Result<usize, CommandError> {
+    let len = match value_type {
+        ValueType::Ipv4 => 4,
+        ValueType::Ipv6 => 16,
+        ValueType::Mac => 6,
+        ValueType::Cidr => match decode_cidr(bytes).ok_or(CommandError::InvalidData)? {
+            (IpAddr::V4(_), _) => 2 + 4,
+            (IpAddr::V6(_), _) => 2 + 16,
+        },
+        ValueType::Hostname => {
+            let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            let text = core::str::from_utf8(&bytes[..len]).map_err(|_| CommandError::InvalidData)?;
+            if !text.is_empty() && !is_hostname(text) {
+                return Err(CommandError::InvalidData);
+            }
+            len
+        },
+        _ => return Ok(bytes.len()),
+    };
+    if bytes.len() < len || bytes[len..].iter().any(|b| *b != 0) {
+        return Err(CommandError::InvalidData);
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod test {
+    use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use crate::values::ValueType;
+    use super::{cidr_bytes, decode_cidr, is_hostname, validate};
+
+    #[test]
+    fn hostnames() {
+        assert!(is_hostname("ntp.se"));
+        assert!(is_hostname("pool.ntp.org."));
+        assert!(is_hostname("my-clock-01"));
+        assert!(!is_hostname(""));
+        assert!(!is_hostname("-clock"));
+        assert!(!is_hostname("clock-.local"));
+        assert!(!is_hostname("two..dots"));
+        assert!(!is_hostname("under_score"));
+        assert!(!is_hostname(core::str::from_utf8(&[b'a'; 64]).unwrap()));
+    }
+
+    #[test]
+    fn cidr_roundtrip() {
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10));
+        let mut bytes = [0u8; 18];
+        bytes.iter_mut().zip(cidr_bytes(addr, 24)).for_each(|(dst, b)| *dst = b);
+        assert_eq!(&[4, 24, 192, 168, 1, 10, 0], &bytes[..7]);
+        assert_eq!(Some((addr, 24)), decode_cidr(&bytes));
+        assert!(matches!(validate(ValueType::Cidr, &bytes), Ok(6)));
+
+        let addr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        bytes.iter_mut().zip(cidr_bytes(addr, 200)).for_each(|(dst, b)| *dst = b);
+        assert_eq!(Some((addr, 128)), decode_cidr(&bytes));
+
+        assert_eq!(None, decode_cidr(&[4, 33, 10, 0, 0, 1]));
+        assert_eq!(None, decode_cidr(&[5, 8, 10, 0, 0, 1]));
+    }
+
+    #[test]
+    fn validate_padding() {
+        assert!(validate(ValueType::Ipv4, &[10, 0, 0, 1, 0, 0]).is_ok());
+        assert!(validate(ValueType::Ipv4, &[10, 0, 0]).is_err());
+        assert!(validate(ValueType::Mac, &[1, 2, 3, 4, 5, 6, 7]).is_err());
+        assert!(validate(ValueType::Hostname, b"clock\0\0").is_ok());
+        assert!(validate(ValueType::Hostname, b"clock\0x").is_err());
+        assert!(validate(ValueType::Hostname, b"cl ock").is_err());
+    }
+}
@@ -10,9 +10,28 @@ pub use super::traits::{*};
 #[allow(unused_imports)] 
 pub use super::config::Config;
 
+#[allow(unused_imports)] 
+pub use super::identity::{Identity, SerialNumber};
+
+#[allow(unused_imports)] 
+pub use super::color::Color;
+
+#[allow(unused_imports)] 
+pub use super::time::{Date, DateTime, TimeOfDay};
+
+#[allow(unused_imports)] 
+pub use super::handler::{AsyncCommandHandler, ChunkBuffer, CommandHandler};
+
+#[allow(unused_imports)] 
+pub use super::table::Record;
+
+#[allow(unused_imports)] 
+pub use super::text::CharClass;
+
 #[allow(unused_imports)] 
 pub use super::entry::{
     ActionEntry, ActionVariant, FieldEntry, InfoEntry, PropEntry, SectionEntry, Field, 
-    info, bytes, section, action, secret, status, integer, option, prop,
+    info, bytes, section, action, secret, status, integer, option, prop, toggle, decimal,
+    ipv4, ipv6, cidr, mac, hostname, time, date, timestamp, duration, color, flags, table,
     options::OptionValueProvider,
 };
\ No newline at end of file
@@ -0,0 +1,331 @@
+use core::prelude::rust_2024::{*};
+
+use crate::{
+    command::CommandError,
+    config::VALUE_LENGTH,
+    field::FieldValue,
+    handler::{AsyncCommandHandler, ChunkBuffer, CommandHandler},
+    identity::SerialNumber,
+    table::{Record, row_key},
+    traits::{ActionIndex, InfoIndex, PropIndex},
+};
+
+mod ram;
+mod journal;
+mod migrate;
+#[cfg(feature = "std")]
+mod file;
+
+pub use self::ram::RamStore;
+pub use self::journal::{JournalError, JournalStore};
+pub use self::migrate::{Migration, Migrator, VERSION_KEY, migrate, stored_version};
+#[cfg(feature = "std")]
+pub use self::file::FileStore;
+
+/// Raw value bytes as produced by [`FieldValue::into_store_bytes`].
+pub type StoreBytes = [u8; VALUE_LENGTH];
+
+/// Backend for persisting prop values.
+///
+/// Implementations only deal with raw bytes addressed by a numeric key, the provided
+/// methods take care of mapping props to keys and applying entry defaults.
+pub trait PropStore {
+    /// Returns the bytes stored for `key`, or `None` if nothing has been saved.
+    fn load_key(&mut self, key: u32) -> Result<Option<StoreBytes>, CommandError>;
+    fn save_key(&mut self, key: u32, bytes: &StoreBytes) -> Result<(), CommandError>;
+    fn erase_key(&mut self, key: u32) -> Result<(), CommandError>;
+
+    /// Erases every value but the schema version under [`VERSION_KEY`], all at once so a
+    /// failure leaves the store as it was. Fails with [`CommandError::NotSupported`] for
+    /// stores that can't.
+    fn erase_all(&mut self) -> Result<(), CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    /// Loads the value of `prop`, using the entry default if nothing is stored.
+    fn load<P: PropIndex>(&mut self, prop: P) -> Result<FieldValue, CommandError> where Self: Sized {
+        let bytes = self.load_key(prop_key(prop))?.unwrap_or([0u8; VALUE_LENGTH]);
+        Ok(FieldValue::from_store(prop.get_entry(), bytes))
+    }
+
+    fn save<P: PropIndex>(&mut self, prop: P, value: FieldValue) -> Result<(), CommandError> where Self: Sized {
+        self.save_key(prop_key(prop), &value.into_store_bytes())
+    }
+
+    fn erase<P: PropIndex>(&mut self, prop: P) -> Result<(), CommandError> where Self: Sized {
+        self.erase_key(prop_key(prop))
+    }
+
+    /// Loads row `row` of the table `prop`, fails with [`CommandError::NoContent`] past
+    /// the last row.
+    fn load_row<P: PropIndex>(&mut self, prop: P, row: u8) -> Result<Record, CommandError> where Self: Sized {
+        if row >= self.load(prop)?.row_count() {
+            return Err(CommandError::NoContent);
+        }
+        let bytes = self.load_key(row_key(prop_key(prop), row))?.unwrap_or([0u8; VALUE_LENGTH]);
+        Ok(Record::from_store(prop.get_entry(), bytes))
+    }
+
+    /// Saves row `row` of the table `prop`, appending it if `row` is the number of rows.
+    /// Fails with [`CommandError::ListFull`] if the table holds its maximum number of rows.
+    fn save_row<P: PropIndex>(&mut self, prop: P, row: u8, record: Record) -> Result<(), CommandError> where Self: Sized {
+        let mut table = self.load(prop)?;
+        let rows = table.row_count();
+        if row > rows {
+            return Err(CommandError::InvalidData);
+        }
+        if row == rows && rows >= prop.get_entry().max_items {
+            return Err(CommandError::ListFull);
+        }
+        self.save_key(row_key(prop_key(prop), row), &record.into_store_bytes())?;
+        if row == rows {
+            table.set_row_count(rows + 1);
+            self.save(prop, table)?;
+        }
+        Ok(())
+    }
+
+    /// Removes row `row` of the table `prop` and moves the rows after it up.
+    fn erase_row<P: PropIndex>(&mut self, prop: P, row: u8) -> Result<(), CommandError> where Self: Sized {
+        let mut table = self.load(prop)?;
+        let rows = table.row_count();
+        if row >= rows {
+            return Err(CommandError::InvalidData);
+        }
+        let key = prop_key(prop);
+        for next in row + 1..rows {
+            let bytes = self.load_key(row_key(key, next))?.unwrap_or([0u8; VALUE_LENGTH]);
+            self.save_key(row_key(key, next - 1), &bytes)?;
+        }
+        table.set_row_count(rows - 1);
+        self.save(prop, table)?;
+        self.erase_key(row_key(key, rows - 1))
+    }
+}
+
+pub(crate) fn prop_key<P: PropIndex>(prop: P) -> u32 {
+    prop.id()
+}
+
+/// Serves `ReadProp`, `WriteProp` and the table row commands directly from `store` and
+/// forwards all other commands to `handler`.
+pub struct StoreHandler<S, H> {
+    pub store: S,
+    pub handler: H,
+    chunks: ChunkBuffer,
+}
+
+impl <S, H> StoreHandler<S, H> {
+    pub const fn new(store: S, handler: H) -> Self {
+        Self { store, handler, chunks: ChunkBuffer::new() }
+    }
+}
+
+impl <P, I, A, S, H> CommandHandler<P, I, A> for StoreHandler<S, H> where
+    P: PropIndex, I: InfoIndex, A: ActionIndex, S: PropStore, H: CommandHandler<P, I, A>
+{
+    fn read_prop(&mut self, prop: P) -> Result<FieldValue, CommandError> {
+        self.store.load(prop)
+    }
+
+    fn write_prop(&mut self, prop: P, value: FieldValue) -> Result<(), CommandError> {
+        self.store.save(prop, value)
+    }
+
+    fn read_row(&mut self, prop: P, row: u8) -> Result<Record, CommandError> {
+        self.store.load_row(prop, row)
+    }
+
+    fn write_row(&mut self, prop: P, row: u8, record: Record) -> Result<(), CommandError> {
+        self.store.save_row(prop, row, record)
+    }
+
+    fn delete_row(&mut self, prop: P, row: u8) -> Result<(), CommandError> {
+        self.store.erase_row(prop, row)
+    }
+
+    fn read_info(&mut self, info: I) -> Result<FieldValue, CommandError> {
+        self.handler.read_info(info)
+    }
+
+    fn write_info(&mut self, info: I, value: FieldValue) -> Result<(), CommandError> {
+        self.handler.write_info(info, value)
+    }
+
+    fn do_action(&mut self, action: A) -> Result<(), CommandError> {
+        self.handler.do_action(action)
+    }
+
+    fn serial_number(&mut self) -> Option<SerialNumber> {
+        self.handler.serial_number()
+    }
+
+    fn noop(&mut self) {
+        self.handler.noop()
+    }
+
+    fn factory_reset(&mut self) -> Result<(), CommandError> {
+        self.store.erase_all()
+    }
+
+    fn chunk_buffer(&mut self) -> Option<&mut ChunkBuffer> {
+        Some(&mut self.chunks)
+    }
+}
+
+impl <P, I, A, S, H> AsyncCommandHandler<P, I, A> for StoreHandler<S, H> where
+    P: PropIndex, I: InfoIndex, A: ActionIndex, S: PropStore, H: AsyncCommandHandler<P, I, A>
+{
+    async fn read_prop(&mut self, prop: P) -> Result<FieldValue, CommandError> {
+        self.store.load(prop)
+    }
+
+    async fn write_prop(&mut self, prop: P, value: FieldValue) -> Result<(), CommandError> {
+        self.store.save(prop, value)
+    }
+
+    async fn read_row(&mut self, prop: P, row: u8) -> Result<Record, CommandError> {
+        self.store.load_row(prop, row)
+    }
+
+    async fn write_row(&mut self, prop: P, row: u8, record: Record) -> Result<(), CommandError> {
+        self.store.save_row(prop, row, record)
+    }
+
+    async fn delete_row(&mut self, prop: P, row: u8) -> Result<(), CommandError> {
+        self.store.erase_row(prop, row)
+    }
+
+    async fn read_info(&mut self, info: I) -> Result<FieldValue, CommandError> {
+        self.handler.read_info(info).await
+    }
+
+    async fn write_info(&mut self, info: I, value: FieldValue) -> Result<(), CommandError> {
+        self.handler.write_info(info, value).await
+    }
+
+    async fn do_action(&mut self, action: A) -> Result<(), CommandError> {
+        self.handler.do_action(action).await
+    }
+
+    async fn serial_number(&mut self) -> Option<SerialNumber> {
+        self.handler.serial_number().await
+    }
+
+    async fn noop(&mut self) {
+        self.handler.noop().await
+    }
+
+    async fn factory_reset(&mut self) -> Result<(), CommandError> {
+        self.store.erase_all()
+    }
+
+    fn chunk_buffer(&mut self) -> Option<&mut ChunkBuffer> {
+        Some(&mut self.chunks)
+    }
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod test {
+    use crate::command::CommandError;
+    use crate::entry::EntryDesc;
+    use crate::prelude::*;
+    use crate::store::{PropStore, RamStore, StoreHandler};
+
+    const NETWORK: [EntryDesc; 2] = [
+        prop("SSID").as_entry(),
+        integer("Priority").writable().as_entry(),
+    ];
+
+    crate::elytra!(CONF: TestConf {
+        info: InfoField {
+            Uptime: integer("Uptime").readonly()
+        },
+        props: PropField {
+            Server: prop("Server").with_default_text("ntp.se"),
+            Level: integer("Level").writable().with_default_integer(7),
+            Networks: table("Networks", &NETWORK, 3)
+        },
+        sections: Section {
+            Main: section("Main")
+        },
+        actions: Action { },
+        layout: {
+            Section::Main: [
+                Field::Prop(PropField::Server),
+                Field::Prop(PropField::Level)
+            ]
+        }
+    });
+
+    struct InfoHandler;
+    impl CommandHandler<PropField, InfoField, Action> for InfoHandler {
+        fn read_info(&mut self, info: InfoField) -> Result<FieldValue, CommandError> {
+            Ok(FieldValue::new(info.get_entry()).with_integer(1234))
+        }
+    }
+
+    #[test]
+    fn ram_store_defaults_and_erase() {
+        let mut store = RamStore::<2>::new();
+        assert_eq!("ntp.se", store.load(PropField::Server).unwrap().get_text());
+        assert_eq!(7, store.load(PropField::Level).unwrap().get_integer());
+
+        let mut fv = FieldValue::new(PropField::Server.get_entry());
+        fv.set_text("pool.ntp.org");
+        store.save(PropField::Server, fv).unwrap();
+        assert_eq!("pool.ntp.org", store.load(PropField::Server).unwrap().get_text());
+
+        store.erase(PropField::Server).unwrap();
+        assert_eq!("ntp.se", store.load(PropField::Server).unwrap().get_text());
+    }
+
+    #[test]
+    fn ram_store_capacity() {
+        let mut store = RamStore::<1>::new();
+        store.save(PropField::Level, FieldValue::new(PropField::Level.get_entry()).with_integer(1)).unwrap();
+        let res = store.save(PropField::Server, FieldValue::new(PropField::Server.get_entry()));
+        assert!(matches!(res, Err(CommandError::Failed)));
+    }
+
+    #[test]
+    fn store_table_rows() {
+        let mut store = RamStore::<8>::new();
+        let table = PropField::Networks;
+        assert!(matches!(store.load_row(table, 0), Err(CommandError::NoContent)));
+
+        for (row, name) in ["home", "office", "cafe"].into_iter().enumerate() {
+            let mut ssid = FieldValue::new(&table.get_entry().columns()[0]);
+            ssid.set_text(name);
+            let mut record = Record::new(table.get_entry());
+            record.set(0, ssid).unwrap();
+            store.save_row(table, row as u8, record).unwrap();
+        }
+        assert_eq!(3, store.load(table).unwrap().row_count());
+        assert!(matches!(store.save_row(table, 3, Record::new(table.get_entry())), Err(CommandError::ListFull)));
+        assert!(matches!(store.save_row(table, 5, Record::new(table.get_entry())), Err(CommandError::InvalidData)));
+
+        // Later rows move up
+        store.erase_row(table, 0).unwrap();
+        assert_eq!(2, store.load(table).unwrap().row_count());
+        assert_eq!("office", store.load_row(table, 0).unwrap().get(0).unwrap().get_text());
+        assert_eq!("cafe", store.load_row(table, 1).unwrap().get(0).unwrap().get_text());
+        assert!(matches!(store.load_row(table, 2), Err(CommandError::NoContent)));
+        assert!(matches!(store.erase_row(table, 2), Err(CommandError::InvalidData)));
+    }
+
+    #[test]
+    fn store_handler_dispatch() {
+        let mut handler = StoreHandler::new(RamStore::<2>::new(), InfoHandler);
+
+        let res = CONF.dispatch(&[b'r', 1], &mut handler);
+        assert_eq!(&[1, 7, 0], &res.as_bytes()[0..3]);
+
+        CONF.dispatch(&[b'w', 1, 42, 0, 0, 0, 0, 0, 0, 0], &mut handler);
+        let res = CONF.dispatch(&[b'r', 1], &mut handler);
+        assert_eq!(&[1, 42, 0], &res.as_bytes()[0..3]);
+
+        let res = CONF.dispatch(&[b'R', 0], &mut handler);
+        assert_eq!(&[1, 0xd2, 0x04], &res.as_bytes()[0..3]);
+    }
+}
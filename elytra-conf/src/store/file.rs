@@ -0,0 +1,143 @@
+use std::{fs, io, path::PathBuf, vec::Vec};
+use log::warn;
+
+use crate::{command::CommandError, config::VALUE_LENGTH, store::{PropStore, StoreBytes, VERSION_KEY}};
+
+const RECORD_SIZE: usize = 4 + VALUE_LENGTH;
+const MAGIC: [u8; 4] = *b"ELYF";
+
+/// Store backed by a single file of fixed size records (`key` followed by the value bytes).
+/// The records follow a header of a magic and the [`VALUE_LENGTH`] they were written
+/// with, files with other record sizes, like those written before the header, are
+/// rejected instead of misread.
+///
+/// The whole file is rewritten on every change, which is fine for host side mocks but
+/// not intended for anything resembling flash.
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn read_records(&self) -> io::Result<Vec<(u32, StoreBytes)>> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let records = match data.strip_prefix(&header()) {
+            Some(records) if records.len().is_multiple_of(RECORD_SIZE) => records,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                std::format!("{} is not a store file with values of {} bytes", self.path.display(), VALUE_LENGTH))),
+        };
+        Ok(records.chunks_exact(RECORD_SIZE).map(|record| {
+            let (key, bytes) = record.split_at(4);
+            (
+                u32::from_le_bytes(key.try_into().unwrap()),
+                bytes.try_into().unwrap()
+            )
+        }).collect())
+    }
+
+    fn write_records(&self, records: &[(u32, StoreBytes)]) -> io::Result<()> {
+        let data: Vec<u8> = header().into_iter()
+            .chain(records.iter().flat_map(|(key, bytes)| key.to_le_bytes().into_iter().chain(bytes.iter().copied())))
+            .collect();
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, data)?;
+        fs::rename(tmp_path, &self.path)
+    }
+
+    fn update(&self, key: u32, bytes: Option<&StoreBytes>) -> io::Result<()> {
+        let mut records = self.read_records()?;
+        records.retain(|(k, _)| *k != key);
+        if let Some(bytes) = bytes {
+            records.push((key, *bytes));
+        }
+        self.write_records(&records)
+    }
+}
+
+fn header() -> [u8; 6] {
+    let [len_lo, len_hi] = (VALUE_LENGTH as u16).to_le_bytes();
+    [MAGIC[0], MAGIC[1], MAGIC[2], MAGIC[3], len_lo, len_hi]
+}
+
+fn store_error(e: io::Error) -> CommandError {
+    warn!("file store failed: {}", e);
+    CommandError::Failed
+}
+
+impl PropStore for FileStore {
+    fn load_key(&mut self, key: u32) -> Result<Option<StoreBytes>, CommandError> {
+        let records = self.read_records().map_err(store_error)?;
+        Ok(records.into_iter().find(|(k, _)| *k == key).map(|(_, bytes)| bytes))
+    }
+
+    fn save_key(&mut self, key: u32, bytes: &StoreBytes) -> Result<(), CommandError> {
+        self.update(key, Some(bytes)).map_err(store_error)
+    }
+
+    fn erase_key(&mut self, key: u32) -> Result<(), CommandError> {
+        self.update(key, None).map_err(store_error)
+    }
+
+    fn erase_all(&mut self) -> Result<(), CommandError> {
+        let mut records = self.read_records().map_err(store_error)?;
+        records.retain(|(key, _)| *key == VERSION_KEY);
+        self.write_records(&records).map_err(store_error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use crate::command::CommandError;
+    use crate::config::VALUE_LENGTH;
+    use crate::store::{FileStore, PropStore, VERSION_KEY};
+
+    #[test]
+    fn file_store_persists() {
+        let path = env::temp_dir().join(std::format!("elytra-file-store-{}.bin", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut store = FileStore::new(&path);
+        assert_eq!(None, store.load_key(3).unwrap());
+        store.save_key(3, &[3u8; VALUE_LENGTH]).unwrap();
+        store.save_key(5, &[5u8; VALUE_LENGTH]).unwrap();
+        store.save_key(3, &[4u8; VALUE_LENGTH]).unwrap();
+
+        let mut store = FileStore::new(&path);
+        assert_eq!(Some([4u8; VALUE_LENGTH]), store.load_key(3).unwrap());
+        assert_eq!(Some([5u8; VALUE_LENGTH]), store.load_key(5).unwrap());
+
+        store.erase_key(3).unwrap();
+        assert_eq!(None, store.load_key(3).unwrap());
+        assert_eq!(Some([5u8; VALUE_LENGTH]), store.load_key(5).unwrap());
+
+        store.save_key(VERSION_KEY, &[2u8; VALUE_LENGTH]).unwrap();
+        store.erase_all().unwrap();
+        assert_eq!(None, store.load_key(5).unwrap());
+        assert_eq!(Some([2u8; VALUE_LENGTH]), store.load_key(VERSION_KEY).unwrap());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_store_rejects_other_record_sizes() {
+        let path = env::temp_dir().join(std::format!("elytra-file-store-old-{}.bin", process::id()));
+        // A record of a 64 byte value, written before the header
+        fs::write(&path, [[3, 0, 0, 0].as_slice(), &[3u8; 64]].concat()).unwrap();
+
+        let mut store = FileStore::new(&path);
+        assert!(matches!(store.load_key(3), Err(CommandError::Failed)));
+        assert!(matches!(store.save_key(3, &[4u8; VALUE_LENGTH]), Err(CommandError::Failed)));
+        assert_eq!(68, fs::metadata(&path).unwrap().len());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,564 @@
+use core::prelude::rust_2024::{*};
+use embedded_storage::nor_flash::NorFlash;
+use log::warn;
+
+use crate::{command::CommandError, config::VALUE_LENGTH, store::{PropStore, StoreBytes, VERSION_KEY}};
+
+const MAGIC: u32 = u32::from_le_bytes(*b"ELYJ");
+const SECTOR_HEADER_LEN: usize = 12;
+const RECORD_HEADER_LEN: usize = 12;
+const MAX_WRITE_SIZE: usize = 32;
+const MAX_RECORD_LEN: usize = RECORD_HEADER_LEN + VALUE_LENGTH + MAX_WRITE_SIZE;
+
+const KIND_VALUE: u16 = 0x5601;
+const KIND_ERASED: u16 = 0x5602;
+
+/// Log structured [`PropStore`] for NOR flash.
+///
+/// Two equally sized sectors are used, one of them active at a time. Every save appends
+/// a record (`key`, length, kind, CRC and the value with trailing zeroes trimmed) to the
+/// active sector, and lookups use the last valid record for a key. When the active sector
+/// is full, the latest record of each key is copied to the spare sector, which is then
+/// stamped with a higher sequence number before the old sector is erased.
+///
+/// Records or sector headers torn by a power cut fail their CRC and are ignored, so a
+/// value always reads back as either the old or the new one.
+pub struct JournalStore<F> {
+    flash: F,
+    base: u32,
+    sector_size: u32,
+    active: u32,
+    seq: u32,
+    write_pos: u32,
+}
+
+#[derive(Debug)]
+pub enum JournalError<E> {
+    Flash(E),
+    Full,
+}
+
+impl <E> From<E> for JournalError<E> {
+    fn from(e: E) -> Self {
+        Self::Flash(e)
+    }
+}
+
+struct Record {
+    key: u32,
+    kind: u16,
+    len: usize,
+    next: u32,
+    payload: [u8; VALUE_LENGTH],
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Scan {
+    /// Erased flash, nothing has been written from here on.
+    End,
+    /// Unusable data, the rest of the sector cannot be trusted.
+    Corrupt,
+    /// A torn or otherwise invalid record that should be skipped.
+    Skip(u32),
+    Record(Record),
+}
+
+impl <F: NorFlash> JournalStore<F> {
+    /// Mounts the journal in the two sectors starting at `base`, formatting them if
+    /// neither contains a valid journal.
+    pub fn new(flash: F, base: u32, sector_size: u32) -> Result<Self, F::Error> {
+        assert!(F::WRITE_SIZE <= MAX_WRITE_SIZE && F::WRITE_SIZE.is_multiple_of(F::READ_SIZE), "unsupported flash write size");
+        assert!((base as usize).is_multiple_of(F::ERASE_SIZE) && (sector_size as usize).is_multiple_of(F::ERASE_SIZE), "sectors must be aligned to the erase size");
+
+        let mut store = Self { flash, base, sector_size, active: 0, seq: 0, write_pos: 0 };
+        match [store.read_header(0)?, store.read_header(1)?] {
+            [None, None] => {
+                store.flash.erase(base, base + sector_size)?;
+                store.write_header(0, 1)?;
+                store.seq = 1;
+            },
+            [Some(a), Some(b)] if b > a => (store.active, store.seq) = (1, b),
+            [Some(a), _] => (store.active, store.seq) = (0, a),
+            [None, Some(b)] => (store.active, store.seq) = (1, b),
+        }
+        store.write_pos = store.find_end()?;
+        Ok(store)
+    }
+
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    fn align(len: usize) -> usize {
+        len.div_ceil(F::WRITE_SIZE) * F::WRITE_SIZE
+    }
+
+    fn sector_offset(&self, sector: u32) -> u32 {
+        self.base + sector * self.sector_size
+    }
+
+    fn read_header(&mut self, sector: u32) -> Result<Option<u32>, F::Error> {
+        let mut buf = [0u8; SECTOR_HEADER_LEN + MAX_WRITE_SIZE];
+        let len = Self::align(SECTOR_HEADER_LEN);
+        self.flash.read(self.sector_offset(sector), &mut buf[..len])?;
+        let magic = u32::from_le_bytes(buf[0..4].try_into().unwrap());
+        let seq = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+        let crc = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+        Ok((magic == MAGIC && crc == crc32(&[&buf[0..8]])).then_some(seq))
+    }
+
+    fn write_header(&mut self, sector: u32, seq: u32) -> Result<(), F::Error> {
+        let mut buf = [0xffu8; SECTOR_HEADER_LEN + MAX_WRITE_SIZE];
+        buf[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        buf[4..8].copy_from_slice(&seq.to_le_bytes());
+        let crc = crc32(&[&buf[0..8]]);
+        buf[8..12].copy_from_slice(&crc.to_le_bytes());
+        let len = Self::align(SECTOR_HEADER_LEN);
+        self.flash.write(self.sector_offset(sector), &buf[..len])
+    }
+
+    fn first_record(&self) -> u32 {
+        Self::align(SECTOR_HEADER_LEN) as u32
+    }
+
+    fn read_record(&mut self, sector: u32, pos: u32) -> Result<Scan, F::Error> {
+        let header_len = Self::align(RECORD_HEADER_LEN);
+        if pos as usize + header_len > self.sector_size as usize {
+            return Ok(Scan::End);
+        }
+        let offset = self.sector_offset(sector) + pos;
+        let mut buf = [0xffu8; MAX_RECORD_LEN];
+        self.flash.read(offset, &mut buf[..header_len])?;
+        if buf[..RECORD_HEADER_LEN].iter().all(|b| *b == 0xff) {
+            return Ok(Scan::End);
+        }
+
+        let key = u32::from_le_bytes(buf[0..4].try_into().unwrap());
+        let len = u16::from_le_bytes(buf[4..6].try_into().unwrap()) as usize;
+        let kind = u16::from_le_bytes(buf[6..8].try_into().unwrap());
+        let crc = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+        if len > VALUE_LENGTH {
+            return Ok(Scan::Corrupt);
+        }
+        let total = Self::align(RECORD_HEADER_LEN + len);
+        if pos as usize + total > self.sector_size as usize {
+            return Ok(Scan::Corrupt);
+        }
+        if total > header_len {
+            self.flash.read(offset + header_len as u32, &mut buf[header_len..total])?;
+        }
+
+        let next = pos + total as u32;
+        let payload = &buf[RECORD_HEADER_LEN..RECORD_HEADER_LEN + len];
+        if crc != crc32(&[&buf[0..8], payload]) || !matches!(kind, KIND_VALUE | KIND_ERASED) {
+            return Ok(Scan::Skip(next));
+        }
+        let mut record = Record { key, kind, len, next, payload: [0u8; VALUE_LENGTH] };
+        record.payload[..len].copy_from_slice(payload);
+        Ok(Scan::Record(record))
+    }
+
+    fn write_record(&mut self, sector: u32, pos: u32, key: u32, kind: u16, payload: &[u8]) -> Result<u32, F::Error> {
+        let mut buf = [0xffu8; MAX_RECORD_LEN];
+        buf[0..4].copy_from_slice(&key.to_le_bytes());
+        buf[4..6].copy_from_slice(&(payload.len() as u16).to_le_bytes());
+        buf[6..8].copy_from_slice(&kind.to_le_bytes());
+        buf[RECORD_HEADER_LEN..RECORD_HEADER_LEN + payload.len()].copy_from_slice(payload);
+        let crc = crc32(&[&buf[0..8], payload]);
+        buf[8..12].copy_from_slice(&crc.to_le_bytes());
+
+        let total = Self::align(RECORD_HEADER_LEN + payload.len());
+        let offset = self.sector_offset(sector) + pos;
+        self.flash.write(offset, &buf[..total])?;
+        Ok(total as u32)
+    }
+
+    fn find_end(&mut self) -> Result<u32, F::Error> {
+        let mut pos = self.first_record();
+        loop {
+            match self.read_record(self.active, pos)? {
+                Scan::End => return Ok(pos),
+                Scan::Corrupt => return Ok(self.sector_size),
+                Scan::Skip(next) => pos = next,
+                Scan::Record(record) => pos = record.next,
+            }
+        }
+    }
+
+    /// Finds the latest record for `key` at or after `pos` in `sector`.
+    fn find(&mut self, sector: u32, key: u32, mut pos: u32) -> Result<Option<Record>, F::Error> {
+        let mut found = None;
+        loop {
+            match self.read_record(sector, pos)? {
+                Scan::End | Scan::Corrupt => return Ok(found),
+                Scan::Skip(next) => pos = next,
+                Scan::Record(record) => {
+                    pos = record.next;
+                    if record.key == key {
+                        found = Some(record);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Copies the live records of the keys that pass `keep` to the spare sector and makes
+    /// it the active one. Fails with [`JournalError::Full`] before anything is erased if
+    /// they do not fit.
+    fn collect(&mut self, keep: impl Fn(u32) -> bool) -> Result<(), JournalError<F::Error>> {
+        let (from, to) = (self.active, 1 - self.active);
+        let to_offset = self.sector_offset(to);
+        self.flash.erase(to_offset, to_offset + self.sector_size)?;
+
+        let mut write_pos = self.first_record();
+        let mut pos = self.first_record();
+        loop {
+            let record = match self.read_record(from, pos)? {
+                Scan::End | Scan::Corrupt => break,
+                Scan::Skip(next) => {
+                    pos = next;
+                    continue
+                },
+                Scan::Record(record) => record,
+            };
+            pos = record.next;
+            if record.kind != KIND_VALUE || !keep(record.key) || self.find(from, record.key, record.next)?.is_some() {
+                continue;
+            }
+            if write_pos as usize + Self::align(RECORD_HEADER_LEN + record.len) > self.sector_size as usize {
+                return Err(JournalError::Full);
+            }
+            write_pos += self.write_record(to, write_pos, record.key, record.kind, &record.payload[..record.len])?;
+        }
+
+        self.write_header(to, self.seq + 1)?;
+        let from_offset = self.sector_offset(from);
+        self.flash.erase(from_offset, from_offset + self.sector_size)?;
+        self.active = to;
+        self.seq += 1;
+        self.write_pos = write_pos;
+        Ok(())
+    }
+
+    fn append(&mut self, key: u32, kind: u16, payload: &[u8]) -> Result<(), JournalError<F::Error>> {
+        let total = Self::align(RECORD_HEADER_LEN + payload.len()) as u32;
+        if self.write_pos + total > self.sector_size {
+            self.collect(|_| true)?;
+            if self.write_pos + total > self.sector_size {
+                return Err(JournalError::Full);
+            }
+        }
+        let pos = self.write_pos;
+        // Whatever happens, the space might be partially programmed now
+        self.write_pos += total;
+        self.write_record(self.active, pos, key, kind, payload)?;
+        Ok(())
+    }
+
+    fn load(&mut self, key: u32) -> Result<Option<StoreBytes>, F::Error> {
+        let first = self.first_record();
+        Ok(self.find(self.active, key, first)?
+            .filter(|record| record.kind == KIND_VALUE)
+            .map(|record| record.payload))
+    }
+}
+
+fn flash_error<E: core::fmt::Debug>(e: JournalError<E>) -> CommandError {
+    warn!("journal store failed: {:?}", e);
+    CommandError::Failed
+}
+
+impl <F: NorFlash> PropStore for JournalStore<F> {
+    fn load_key(&mut self, key: u32) -> Result<Option<StoreBytes>, CommandError> {
+        self.load(key).map_err(|e| flash_error(e.into()))
+    }
+
+    fn save_key(&mut self, key: u32, bytes: &StoreBytes) -> Result<(), CommandError> {
+        if self.load_key(key)?.as_ref() == Some(bytes) {
+            return Ok(());
+        }
+        let len = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        self.append(key, KIND_VALUE, &bytes[..len]).map_err(flash_error)
+    }
+
+    fn erase_key(&mut self, key: u32) -> Result<(), CommandError> {
+        if self.load_key(key)?.is_none() {
+            return Ok(());
+        }
+        self.append(key, KIND_ERASED, &[]).map_err(flash_error)
+    }
+
+    /// Collects the journal without the erased values, which only take effect once the
+    /// new sector header is written.
+    fn erase_all(&mut self) -> Result<(), CommandError> {
+        self.collect(|key| key == VERSION_KEY).map_err(flash_error)
+    }
+}
+
+/// CRC-32 (IEEE) over the concatenation of `parts`.
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for part in parts {
+        for byte in *part {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+            }
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash};
+
+    use crate::config::VALUE_LENGTH;
+    use crate::store::{JournalStore, PropStore, StoreBytes, VERSION_KEY};
+
+    const SECTOR_SIZE: u32 = 256;
+
+    #[derive(Debug)]
+    struct PowerCut;
+
+    impl NorFlashError for PowerCut {
+        fn kind(&self) -> NorFlashErrorKind {
+            NorFlashErrorKind::Other
+        }
+    }
+
+    /// In-memory flash that loses power after `fuel` write or erase operations, leaving
+    /// the interrupted operation half done.
+    struct MockFlash {
+        data: [u8; 2 * SECTOR_SIZE as usize],
+        fuel: Option<usize>,
+        dead: bool,
+    }
+
+    impl MockFlash {
+        fn new(fuel: Option<usize>) -> Self {
+            Self { data: [0xa5; 2 * SECTOR_SIZE as usize], fuel, dead: false }
+        }
+
+        fn revive(&mut self) {
+            self.fuel = None;
+            self.dead = false;
+        }
+
+        /// Returns how many bytes of an operation get applied before power is lost.
+        fn budget(&mut self, len: usize) -> Result<usize, usize> {
+            match self.fuel {
+                _ if self.dead => Err(0),
+                Some(0) => {
+                    self.dead = true;
+                    Err(len / 2 / Self::WRITE_SIZE * Self::WRITE_SIZE)
+                },
+                Some(ref mut fuel) => {
+                    *fuel -= 1;
+                    Ok(len)
+                },
+                None => Ok(len),
+            }
+        }
+    }
+
+    impl ErrorType for MockFlash {
+        type Error = PowerCut;
+    }
+
+    impl ReadNorFlash for MockFlash {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            if self.dead {
+                return Err(PowerCut);
+            }
+            bytes.copy_from_slice(&self.data[offset as usize..offset as usize + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for MockFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 128;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            assert!((from as usize).is_multiple_of(Self::ERASE_SIZE) && (to as usize).is_multiple_of(Self::ERASE_SIZE));
+            let len = (to - from) as usize;
+            let (applied, res) = match self.budget(len) {
+                Ok(len) => (len, Ok(())),
+                Err(partial) => (partial, Err(PowerCut)),
+            };
+            self.data[from as usize..from as usize + applied].fill(0xff);
+            res
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            assert!((offset as usize).is_multiple_of(Self::WRITE_SIZE) && bytes.len().is_multiple_of(Self::WRITE_SIZE));
+            let (applied, res) = match self.budget(bytes.len()) {
+                Ok(len) => (len, Ok(())),
+                Err(partial) => (partial, Err(PowerCut)),
+            };
+            for (i, byte) in bytes[..applied].iter().enumerate() {
+                let cell = &mut self.data[offset as usize + i];
+                assert_eq!(*byte, *cell & *byte, "programming non-erased flash at {}", offset as usize + i);
+                *cell = *byte;
+            }
+            res
+        }
+    }
+
+    fn value(key: u32, round: u32) -> StoreBytes {
+        let mut bytes = [0u8; VALUE_LENGTH];
+        let len = 4 + ((key * 7 + round * 3) % 20) as usize;
+        for (i, byte) in bytes.iter_mut().take(len).enumerate() {
+            *byte = (key * 31 + round * 7 + i as u32) as u8 | 1;
+        }
+        bytes
+    }
+
+    enum Op {
+        /// Saves `value(key, round)`
+        Save(u32, u32),
+        Erase(u32),
+    }
+
+    fn workload() -> impl Iterator<Item = Op> {
+        (0..12).flat_map(|round| (0..3).map(move |key| {
+            if (round + key) % 5 == 4 {
+                Op::Erase(key)
+            } else {
+                Op::Save(key, round)
+            }
+        }))
+    }
+
+    #[test]
+    fn journal_roundtrip_and_collect() {
+        let mut flash = MockFlash::new(None);
+        let mut store = JournalStore::new(&mut flash, 0, SECTOR_SIZE).unwrap();
+        assert_eq!(None, store.load_key(1).unwrap());
+
+        for round in 0..20 {
+            for key in 0..3 {
+                store.save_key(key, &value(key, round)).unwrap();
+            }
+        }
+        store.erase_key(2).unwrap();
+        assert!(store.seq > 2, "expected the journal to be collected");
+
+        let mut store = JournalStore::new(&mut flash, 0, SECTOR_SIZE).unwrap();
+        assert_eq!(Some(value(0, 19)), store.load_key(0).unwrap());
+        assert_eq!(Some(value(1, 19)), store.load_key(1).unwrap());
+        assert_eq!(None, store.load_key(2).unwrap());
+    }
+
+    #[test]
+    fn journal_erase_all_is_atomic() {
+        let mut cut = 0;
+        loop {
+            let mut flash = MockFlash::new(None);
+            let mut store = JournalStore::new(&mut flash, 0, SECTOR_SIZE).unwrap();
+            store.save_key(VERSION_KEY, &value(0, 1)).unwrap();
+            for key in 0..3 {
+                store.save_key(key, &value(key, 1)).unwrap();
+            }
+            flash.fuel = Some(cut);
+            if let Ok(mut store) = JournalStore::new(&mut flash, 0, SECTOR_SIZE) {
+                let _ = store.erase_all();
+            }
+            let done = !flash.dead;
+
+            flash.revive();
+            let mut store = JournalStore::new(&mut flash, 0, SECTOR_SIZE).unwrap();
+            assert_eq!(Some(value(0, 1)), store.load_key(VERSION_KEY).unwrap(), "cut {}: version lost", cut);
+            let kept = (0..3).filter(|&key| store.load_key(key).unwrap() == Some(value(key, 1))).count();
+            let erased = (0..3).filter(|&key| store.load_key(key).unwrap().is_none()).count();
+            assert!(kept == 3 || erased == 3, "cut {}: {} values kept, {} erased", cut, kept, erased);
+            if done {
+                assert_eq!(3, erased);
+                break;
+            }
+            cut += 1;
+        }
+        assert!(cut > 3, "erase_all took only {} flash operations", cut);
+    }
+
+    #[test]
+    fn journal_skips_unchanged_values() {
+        let mut flash = MockFlash::new(None);
+        let mut store = JournalStore::new(&mut flash, 0, SECTOR_SIZE).unwrap();
+        store.save_key(1, &value(1, 1)).unwrap();
+        let pos = store.write_pos;
+        store.save_key(1, &value(1, 1)).unwrap();
+        store.erase_key(2).unwrap();
+        assert_eq!(pos, store.write_pos);
+    }
+
+    #[test]
+    fn journal_full_keeps_values() {
+        let mut flash = MockFlash::new(None);
+        let mut store = JournalStore::new(&mut flash, 0, SECTOR_SIZE).unwrap();
+        let mut saved = 0;
+        while store.save_key(saved, &value(saved, 0)).is_ok() {
+            saved += 1;
+            assert!(saved < 64, "expected the journal to fill up");
+        }
+        // Rewriting a value needs the space of both versions until the old one is collected
+        assert!(store.save_key(0, &value(0, 1)).is_err());
+
+        let mut store = JournalStore::new(&mut flash, 0, SECTOR_SIZE).unwrap();
+        for key in 0..saved {
+            assert_eq!(Some(value(key, 0)), store.load_key(key).unwrap(), "key {} was lost", key);
+        }
+        assert_eq!(None, store.load_key(saved).unwrap());
+    }
+
+    #[test]
+    fn journal_survives_power_cuts() {
+        let mut cut = 0;
+        loop {
+            let mut flash = MockFlash::new(Some(cut));
+            let mut committed: [Option<StoreBytes>; 3] = [None; 3];
+            let mut in_flight = None;
+
+            if let Ok(mut store) = JournalStore::new(&mut flash, 0, SECTOR_SIZE) {
+                for op in workload() {
+                    let (key, new_value, res) = match op {
+                        Op::Save(key, round) => (key, Some(value(key, round)), store.save_key(key, &value(key, round))),
+                        Op::Erase(key) => (key, None, store.erase_key(key)),
+                    };
+                    if res.is_err() {
+                        in_flight = Some((key, new_value));
+                        break;
+                    }
+                    committed[key as usize] = new_value;
+                }
+            }
+            if !flash.dead {
+                break;
+            }
+
+            flash.revive();
+            let mut store = JournalStore::new(&mut flash, 0, SECTOR_SIZE).unwrap();
+            for key in 0..3 {
+                let stored = store.load_key(key).unwrap();
+                match in_flight {
+                    Some((k, new_value)) if k == key => assert!(
+                        stored == committed[key as usize] || stored == new_value,
+                        "cut {}: key {} has neither the old nor the new value", cut, key),
+                    _ => assert_eq!(committed[key as usize], stored, "cut {}: key {} changed", cut, key),
+                }
+            }
+            store.save_key(0, &value(0, 99)).unwrap();
+            assert_eq!(Some(value(0, 99)), store.load_key(0).unwrap());
+
+            cut += 1;
+        }
+        assert!(cut > 40, "workload too small to exercise collection ({} operations)", cut);
+    }
+}
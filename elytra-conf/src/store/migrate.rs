@@ -0,0 +1,196 @@
+use core::prelude::rust_2024::{*};
+use log::{info, warn};
+
+use crate::{
+    command::CommandError,
+    config::VALUE_LENGTH,
+    field::FieldValue,
+    store::{PropStore, StoreBytes},
+    traits::{PropIndex, RESERVED_ID},
+};
+
+/// Store key holding the schema version the stored values were written with.
+pub const VERSION_KEY: u32 = RESERVED_ID;
+
+/// Upgrades stored values from schema version `from` to `from + 1`.
+///
+/// A migration step may run again if power is lost before the new version has been
+/// saved, so `run` should leave already migrated values alone.
+pub struct Migration {
+    pub from: u16,
+    pub run: fn(&mut Migrator) -> Result<(), CommandError>,
+}
+
+/// Access to the store while migrating, with helpers for the common schema changes.
+///
+/// Entries that no longer exist are addressed by their old id, which for entries without
+/// an explicit id is [`entry_id`](crate::traits::entry_id) of the old identifier.
+pub struct Migrator<'a> {
+    store: &'a mut dyn PropStore,
+}
+
+impl Migrator<'_> {
+    pub fn store(&mut self) -> &mut dyn PropStore {
+        self.store
+    }
+
+    /// Moves the value stored under `old` to `prop`.
+    pub fn rename<P: PropIndex>(&mut self, old: u32, prop: P) -> Result<(), CommandError> {
+        if let Some(bytes) = self.store.load_key(old)? {
+            self.store.save_key(prop.id(), &bytes)?;
+            self.store.erase_key(old)?;
+        }
+        Ok(())
+    }
+
+    /// Drops the value stored under `old`.
+    pub fn remove(&mut self, old: u32) -> Result<(), CommandError> {
+        self.store.erase_key(old)
+    }
+
+    /// Replaces the stored value of `prop` with the result of `convert`, which gets the
+    /// bytes written by the previous schema. Returning `None` resets `prop` to its default.
+    pub fn convert<P: PropIndex>(&mut self, prop: P, convert: impl FnOnce(StoreBytes) -> Option<FieldValue>) -> Result<(), CommandError> {
+        let Some(bytes) = self.store.load_key(prop.id())? else {
+            return Ok(());
+        };
+        match convert(bytes) {
+            Some(value) => self.store.save_key(prop.id(), &value.into_store_bytes()),
+            None => self.store.erase_key(prop.id()),
+        }
+    }
+}
+
+/// Returns the schema version of the values in `store`. Stores written before any
+/// version was saved are considered to be at version 1.
+pub fn stored_version(store: &mut dyn PropStore) -> Result<u16, CommandError> {
+    Ok(store.load_key(VERSION_KEY)?
+        .map(|bytes| u16::from_le_bytes([bytes[1], bytes[2]]))
+        .unwrap_or(1))
+}
+
+/// Runs the `migrations` needed to bring `store` up to `version`, saving the new version
+/// after every step. Versions without a migration are assumed to be compatible.
+pub fn migrate(store: &mut dyn PropStore, version: u16, migrations: &[Migration]) -> Result<(), CommandError> {
+    let mut current = stored_version(store)?;
+    if current > version {
+        warn!("stored schema version {} is newer than {}", current, version);
+        return Err(CommandError::Failed);
+    }
+
+    while current < version {
+        if let Some(migration) = migrations.iter().find(|m| m.from == current) {
+            info!("migrating stored values from schema version {}", current);
+            (migration.run)(&mut Migrator { store })?;
+        }
+        current += 1;
+        let mut bytes = [0u8; VALUE_LENGTH];
+        bytes[0] = 2;
+        bytes[1..3].copy_from_slice(&current.to_le_bytes());
+        store.save_key(VERSION_KEY, &bytes)?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod test {
+    use crate::command::CommandError;
+    use crate::prelude::*;
+    use crate::store::{Migration, Migrator, PropStore, RamStore, stored_version};
+
+    const OFFSETS: [&str; 3] = ["Darker", "Normal", "Brighter"];
+
+    // Version 1 of the schema
+    crate::props!(OldProp {
+        BrightOffset: integer("Brightness offset").writable(),
+        Name: prop("Name"),
+        Legacy: prop("Legacy")
+    });
+
+    // Version 3 of the schema
+    crate::elytra!(CONF: TestConf {
+        version: 3,
+        info: InfoField { },
+        props: PropField {
+            BrightOffset: option("Brightness offset", &OFFSETS).with_default_options(&[1]),
+            Label: prop("Label").with_default_text("clock")
+        },
+        sections: Section {
+            Main: section("Main")
+        },
+        actions: Action { },
+        layout: {
+            Section::Main: [
+                Field::Prop(PropField::BrightOffset),
+                Field::Prop(PropField::Label)
+            ]
+        }
+    });
+
+    const MIGRATIONS: [Migration; 2] = [
+        Migration { from: 1, run: offset_to_option },
+        Migration { from: 2, run: rename_name },
+    ];
+
+    fn offset_to_option(m: &mut Migrator) -> Result<(), CommandError> {
+        m.convert(PropField::BrightOffset, |bytes| {
+            let offset = i64::from_le_bytes(bytes[1..9].try_into().unwrap());
+            let mut value = FieldValue::new(PropField::BrightOffset.get_entry());
+            value.set_options(&[(offset.signum() + 1) as u16]);
+            Some(value)
+        })
+    }
+
+    fn rename_name(m: &mut Migrator) -> Result<(), CommandError> {
+        m.rename(entry_id("Name"), PropField::Label)?;
+        m.remove(entry_id("Legacy"))
+    }
+
+    fn old_store() -> RamStore<8> {
+        let mut store = RamStore::new();
+        store.save(OldProp::BrightOffset, FieldValue::new(OldProp::BrightOffset.get_entry()).with_integer(-3)).unwrap();
+        let mut name = FieldValue::new(OldProp::Name.get_entry());
+        name.set_text("kitchen");
+        store.save(OldProp::Name, name).unwrap();
+        let mut legacy = FieldValue::new(OldProp::Legacy.get_entry());
+        legacy.set_text("unused");
+        store.save(OldProp::Legacy, legacy).unwrap();
+        store
+    }
+
+    #[test]
+    fn migrate_type_change_rename_and_removal() {
+        let mut store = old_store();
+        assert_eq!(3, CONF.version);
+        CONF.migrate(&mut store, &MIGRATIONS).unwrap();
+
+        assert_eq!(3, stored_version(&mut store).unwrap());
+        assert_eq!(&[0], store.load(PropField::BrightOffset).unwrap().get_options().as_slice());
+        assert_eq!("kitchen", store.load(PropField::Label).unwrap().get_text());
+        assert_eq!(None, store.load_key(entry_id("Name")).unwrap());
+        assert_eq!(None, store.load_key(entry_id("Legacy")).unwrap());
+
+        // Migrations only run once
+        let mut label = FieldValue::new(PropField::Label.get_entry());
+        label.set_text("hallway");
+        store.save(PropField::Label, label).unwrap();
+        CONF.migrate(&mut store, &MIGRATIONS).unwrap();
+        assert_eq!("hallway", store.load(PropField::Label).unwrap().get_text());
+    }
+
+    #[test]
+    fn migrate_empty_store() {
+        let mut store = RamStore::<4>::new();
+        CONF.migrate(&mut store, &MIGRATIONS).unwrap();
+        assert_eq!(3, stored_version(&mut store).unwrap());
+        assert_eq!("clock", store.load(PropField::Label).unwrap().get_text());
+    }
+
+    #[test]
+    fn migrate_rejects_newer_store() {
+        let mut store = RamStore::<4>::new();
+        CONF.migrate(&mut store, &MIGRATIONS).unwrap();
+        let res = crate::store::migrate(&mut store, 2, &MIGRATIONS);
+        assert!(matches!(res, Err(CommandError::Failed)));
+    }
+}
@@ -0,0 +1,54 @@
+use core::prelude::rust_2024::{*};
+
+use crate::{command::CommandError, store::{PropStore, StoreBytes, VERSION_KEY}};
+
+/// Volatile store keeping up to `N` values in memory.
+pub struct RamStore<const N: usize> {
+    slots: [Option<(u32, StoreBytes)>; N],
+}
+
+impl <const N: usize> RamStore<N> {
+    pub const fn new() -> Self {
+        Self { slots: [None; N] }
+    }
+
+    fn slot(&self, key: u32) -> Option<usize> {
+        self.slots.iter().position(|slot| matches!(slot, Some((k, _)) if *k == key))
+    }
+}
+
+impl <const N: usize> Default for RamStore<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl <const N: usize> PropStore for RamStore<N> {
+    fn load_key(&mut self, key: u32) -> Result<Option<StoreBytes>, CommandError> {
+        Ok(self.slot(key).and_then(|i| self.slots[i]).map(|(_, bytes)| bytes))
+    }
+
+    fn save_key(&mut self, key: u32, bytes: &StoreBytes) -> Result<(), CommandError> {
+        let index = self.slot(key)
+            .or_else(|| self.slots.iter().position(Option::is_none))
+            .ok_or(CommandError::Failed)?;
+        self.slots[index] = Some((key, *bytes));
+        Ok(())
+    }
+
+    fn erase_key(&mut self, key: u32) -> Result<(), CommandError> {
+        if let Some(index) = self.slot(key) {
+            self.slots[index] = None;
+        }
+        Ok(())
+    }
+
+    fn erase_all(&mut self) -> Result<(), CommandError> {
+        for slot in &mut self.slots {
+            if slot.is_some_and(|(key, _)| key != VERSION_KEY) {
+                *slot = None;
+            }
+        }
+        Ok(())
+    }
+}
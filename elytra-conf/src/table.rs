@@ -0,0 +1,232 @@
+//! Records of [`table`](crate::entry::table) props.
+//!
+//! The value of a table prop is its number of rows, the rows themselves are stored under
+//! their own key, see [`row_key`]. Hosts read a row with the `ReadRow` command, which
+//! sends the value of every column with a 1 byte length, and change single cells with
+//! `WriteCell`. Writing to the row after the last one appends a row.
+use core::prelude::rust_2024::{*};
+
+use crate::{
+    command::CommandError,
+    config::VALUE_LENGTH,
+    entry::EntryDesc,
+    field::FieldValue,
+    traits::{fnv1a, fnv1a_bytes},
+};
+
+/// Row of a table, holding a value for every column.
+///
+/// Cells are stored with a 1 byte length each, an empty cell holds the column default.
+pub struct Record {
+    desc: &'static EntryDesc,
+    data: [u8; VALUE_LENGTH],
+}
+
+impl core::fmt::Debug for Record {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Record")
+            .field("desc", &self.desc)
+            .field("cells", &DebugCells(self))
+            .finish()
+    }
+}
+
+/// Cells of a record, formatted like [`FieldValue`]s so secrets are left out.
+struct DebugCells<'a>(&'a Record);
+
+impl core::fmt::Debug for DebugCells<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.0.cells()).finish()
+    }
+}
+
+impl Record {
+    /// Row with the default value in every column.
+    pub const fn new(desc: &'static EntryDesc) -> Self {
+        Self {
+            desc,
+            data: [0u8; VALUE_LENGTH],
+        }
+    }
+
+    pub fn from_store(desc: &'static EntryDesc, bytes: [u8; VALUE_LENGTH]) -> Self {
+        Self { desc, data: bytes }
+    }
+
+    pub fn into_store_bytes(self) -> [u8; VALUE_LENGTH] {
+        self.data
+    }
+
+    pub fn columns(&self) -> &'static [EntryDesc] {
+        self.desc.columns()
+    }
+
+    /// Value of `column`, `None` if the table has no such column.
+    pub fn get(&self, column: usize) -> Option<FieldValue> {
+        let desc = self.columns().get(column)?;
+        Some(FieldValue::from_bytes(desc, self.cell_bytes().nth(column).unwrap_or_default()))
+    }
+
+    /// Replaces the value of `column`, which has to be a value of the column entry. Fails
+    /// with [`CommandError::InvalidData`] if there is no such column or the record would
+    /// not fit a stored value.
+    pub fn set(&mut self, column: usize, value: FieldValue) -> Result<(), CommandError> {
+        if column >= self.columns().len() {
+            return Err(CommandError::InvalidData);
+        }
+        let data = {
+            let cells = self.cell_bytes().chain(core::iter::repeat(&[][..]))
+                .take(self.columns().len())
+                .enumerate()
+                .map(|(i, cell)| if i == column { value.get_bytes() } else { cell });
+            encode_cells(cells)?
+        };
+        self.data = data;
+        Ok(())
+    }
+
+    /// Values of all columns.
+    pub fn cells(&self) -> impl Iterator<Item = FieldValue> + '_ {
+        (0..self.columns().len()).filter_map(|column| self.get(column))
+    }
+
+    /// Applies a `WriteCell` command: replaces `column` from byte `offset` on for text
+    /// values that are written in several messages, or parses `bytes` as the whole value.
+    pub fn write_cell(&mut self, column: usize, offset: usize, bytes: &[u8]) -> Result<(), CommandError> {
+        let desc = self.columns().get(column).ok_or(CommandError::InvalidData)?;
+        let mut cell = FieldValue::new(desc);
+        let cell = match (cell.can_splice(), offset) {
+            (true, 0) => {
+                cell.splice(0, bytes)?;
+                cell
+            },
+            (true, _) => {
+                let mut cell = self.get(column).ok_or(CommandError::InvalidData)?;
+                cell.splice(offset, bytes)?;
+                cell
+            },
+            (false, 0) => FieldValue::from_message(desc, bytes)?,
+            (false, _) => return Err(CommandError::InvalidData),
+        };
+        self.set(column, cell)
+    }
+
+    /// The row as sent to the host: the value of every column with a 1 byte length, with
+    /// defaults filled in. Secrets only tell whether they are set, see
+    /// [`FieldValue::message_payload`].
+    pub fn message_payload(&self) -> impl Iterator<Item = u8> + '_ {
+        self.cells().flat_map(|cell| {
+            let mut bytes = [0u8; VALUE_LENGTH];
+            let mut len = 0;
+            for (dst, byte) in bytes[1..].iter_mut().zip(cell.message_payload()) {
+                *dst = byte;
+                len += 1;
+            }
+            bytes[0] = len as u8;
+            bytes.into_iter().take(1 + len)
+        })
+    }
+
+    /// Value bytes of the stored cells, which may be fewer than the columns for records
+    /// written by an older schema.
+    fn cell_bytes(&self) -> impl Iterator<Item = &[u8]> {
+        let mut cells = self.data[1..=self.data[0] as usize].iter().as_slice();
+        core::iter::from_fn(move || {
+            let (&len, rest) = cells.split_first()?;
+            let cell = rest.get(..len as usize)?;
+            cells = &rest[cell.len()..];
+            Some(cell)
+        })
+    }
+}
+
+fn encode_cells<'a>(cells: impl Iterator<Item = &'a [u8]>) -> Result<[u8; VALUE_LENGTH], CommandError> {
+    let mut data = [0u8; VALUE_LENGTH];
+    let mut end = 0;
+    for cell in cells {
+        if end + 1 + cell.len() >= VALUE_LENGTH {
+            return Err(CommandError::InvalidData);
+        }
+        data[end + 1] = cell.len() as u8;
+        data[end + 2..=end + 1 + cell.len()].copy_from_slice(cell);
+        end += 1 + cell.len();
+    }
+    data[0] = end as u8;
+    Ok(data)
+}
+
+/// Store key of row `row` of the table stored under `key`. Rows share the key space with
+/// the props, [`props!`](crate::props) checks that no row key collides with an id.
+pub const fn row_key(key: u32, row: u8) -> u32 {
+    fnv1a(row_prefix(key), row)
+}
+
+/// Hash shared by the row keys of the table stored under `key`, before the row byte.
+pub(crate) const fn row_prefix(key: u32) -> u32 {
+    let [a, b, c, d] = key.to_le_bytes();
+    fnv1a_bytes(&[a, b, c, d, b'['])
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use crate::command::CommandError;
+    use crate::entry::EntryDesc;
+    use crate::prelude::*;
+    use crate::table::{Record, row_key};
+    use crate::traits::row_keys_valid;
+
+    const COLUMNS: [EntryDesc; 3] = [
+        prop("SSID").with_default_text("guest").as_entry(),
+        secret("Password").as_entry(),
+        integer("Priority").writable().with_range(0..10).with_default_integer(5).as_entry(),
+    ];
+    const DESC_TABLE: EntryDesc = table("Networks", &COLUMNS, 4).as_entry();
+
+    #[test]
+    fn record_cells() {
+        let mut record = Record::new(&DESC_TABLE);
+        assert_eq!("guest", record.get(0).unwrap().get_text());
+        assert_eq!(5, record.get(2).unwrap().get_integer());
+        assert!(record.get(3).is_none());
+
+        record.write_cell(1, 0, b"hunter").unwrap();
+        record.write_cell(1, 6, b"2").unwrap();
+        record.write_cell(2, 0, &42i64.to_le_bytes()).unwrap();
+        let record = Record::from_store(&DESC_TABLE, record.into_store_bytes());
+        assert_eq!("hunter2", record.get(1).unwrap().get_text());
+        assert_eq!(10, record.get(2).unwrap().get_integer());
+        assert_eq!(3, record.cells().count());
+
+        // Secrets are only sent as set and defaults filled in for the host
+        let payload: [u8; 17] = core::array::from_fn(|i| record.message_payload().nth(i).unwrap());
+        assert_eq!(b"\x05guest\x01\x01\x08\x0a\0\0\0\0\0\0\0", &payload);
+        assert!(!std::format!("{:?}", record).contains("hunter2"));
+    }
+
+    #[test]
+    fn record_invalid_cells() {
+        let mut record = Record::new(&DESC_TABLE);
+        assert!(matches!(record.write_cell(3, 0, b"x"), Err(CommandError::InvalidData)));
+        assert!(matches!(record.write_cell(2, 4, b"x"), Err(CommandError::InvalidData)));
+        assert!(matches!(record.write_cell(0, 9, b"x"), Err(CommandError::InvalidData)));
+
+        let long = [b'x'; 200];
+        record.write_cell(0, 0, &long).unwrap();
+        assert!(matches!(record.write_cell(1, 0, &long), Err(CommandError::InvalidData)));
+        assert_eq!(200, record.get(0).unwrap().get_bytes().len());
+    }
+
+    #[test]
+    fn row_keys_collide() {
+        let entries = [DESC_TABLE, prop("Name").as_entry()];
+        assert!(row_keys_valid(&entries, &[1, 2]));
+        assert!(!row_keys_valid(&entries, &[1, row_key(1, 3)]));
+        // Rows past the limit are never stored
+        assert!(row_keys_valid(&entries, &[1, row_key(1, 4)]));
+        let tables = [DESC_TABLE, DESC_TABLE];
+        assert!(!row_keys_valid(&tables, &[1, row_key(1, 0)]));
+        assert!(row_keys_valid(&tables, &[1, 2]));
+    }
+}
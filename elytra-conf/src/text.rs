@@ -0,0 +1,178 @@
+//! Constraints of text values beyond their length: accepted characters and simple patterns.
+//!
+//! Patterns match the whole value, `?` matches any character, `*` any run of characters,
+//! `#` a digit and `@` a letter. Every other character matches itself, also the special
+//! ones when preceded by `\`.
+use core::prelude::rust_2024::{*};
+use core::str::Chars;
+use bitflags::bitflags;
+
+use crate::{
+    command::CommandError,
+    entry::{Constraints, EntryDesc},
+};
+
+bitflags! {
+    /// Characters accepted in a text value, see [`FieldEntry::with_chars`](crate::entry::FieldEntry::with_chars).
+    #[derive(Debug, Eq, PartialEq, Clone, Copy)]
+    pub struct CharClass: u8 {
+        const Digits = 1 << 0;
+        const Lower = 1 << 1;
+        const Upper = 1 << 2;
+        /// The ASCII space.
+        const Space = 1 << 3;
+        /// Printable ASCII that is not a letter, digit or space.
+        const Punctuation = 1 << 4;
+        /// Control characters and everything beyond ASCII.
+        const Other = 1 << 5;
+
+        const Letters = Self::Lower.bits() | Self::Upper.bits();
+        const Alphanumeric = Self::Letters.bits() | Self::Digits.bits();
+        const Ascii = Self::Alphanumeric.bits() | Self::Space.bits() | Self::Punctuation.bits();
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for CharClass {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "CharClass({=u8:#b})", self.bits())
+    }
+}
+
+impl CharClass {
+    /// Class of the character `c`.
+    pub fn of(c: char) -> Self {
+        match c {
+            '0'..='9' => Self::Digits,
+            'a'..='z' => Self::Lower,
+            'A'..='Z' => Self::Upper,
+            ' ' => Self::Space,
+            c if c.is_ascii_punctuation() => Self::Punctuation,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn accepts(&self, c: char) -> bool {
+        self.contains(Self::of(c))
+    }
+}
+
+/// Constraints of [`ValueType::Text`](crate::values::ValueType::Text),
+/// [`ValueType::Secret`](crate::values::ValueType::Secret) and
+/// [`ValueType::Hostname`](crate::values::ValueType::Hostname) values beyond their
+/// length, which is limited with a range.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TextFormat {
+    pub chars: CharClass,
+    /// Pattern the whole value has to match, see the [module docs](self).
+    pub pattern: Option<&'static str>,
+}
+
+impl TextFormat {
+    pub const DEFAULT: Self = Self {
+        chars: CharClass::all(),
+        pattern: None,
+    };
+}
+
+/// Whether `text` matches `pattern`, see the [module docs](self). With `partial`, whether
+/// `text` can still be completed to a match, for values written in several messages.
+pub fn matches_pattern(pattern: &str, text: &str, partial: bool) -> bool {
+    let mut pattern_chars = pattern.chars();
+    let mut text_chars = text.chars();
+    // Pattern after the last `*` and the text it was last tried at, to let the `*` take
+    // one more character when the rest of the pattern stops matching
+    let mut backtrack: Option<(Chars, Chars)> = None;
+    loop {
+        let text_rest = text_chars.clone();
+        let Some(c) = text_chars.next() else {
+            return partial || pattern_chars.all(|p| p == '*');
+        };
+        let matched = match pattern_chars.next() {
+            Some('*') => {
+                backtrack = Some((pattern_chars.clone(), text_rest.clone()));
+                text_chars = text_rest;
+                continue;
+            },
+            Some('?') => true,
+            Some('#') => c.is_ascii_digit(),
+            Some('@') => c.is_alphabetic(),
+            Some('\\') => pattern_chars.next() == Some(c),
+            Some(p) => p == c,
+            None => false,
+        };
+        if !matched {
+            let Some((after_star, star_text)) = &mut backtrack else {
+                return false;
+            };
+            star_text.next();
+            pattern_chars = after_star.clone();
+            text_chars = star_text.clone();
+        }
+    }
+}
+
+/// Checks a text value written by the host against the constraints of `desc`: its length,
+/// the option list unless the options are mere suggestions, the accepted characters and
+/// the pattern. Fails with [`CommandError::InvalidValue`].
+///
+/// With `partial`, `text` is the start of a value that is written in several messages,
+/// which is only rejected if it can't be completed to a valid value. The minimum length
+/// is not checked then.
+pub fn validate(desc: &EntryDesc, text: &str, partial: bool) -> Result<(), CommandError> {
+    let valid = match &desc.constraints {
+        Constraints::Range(range) => {
+            text.len() <= range.end.max(0) as usize && (partial || text.len() >= range.start.max(0) as usize)
+        },
+        Constraints::Values(constr) if !constr.is_suggested() => {
+            let mut options = (0..constr.value_provider.len()).filter_map(|i| constr.value_provider.get(i));
+            (text.is_empty() && constr.min == 0)
+                || options.any(|option| option == text || (partial && option.starts_with(text)))
+        },
+        _ => true,
+    };
+    let valid = valid
+        && text.chars().all(|c| desc.text.chars.accepts(c))
+        && desc.text.pattern.is_none_or(|pattern| matches_pattern(pattern, text, partial));
+    if valid { Ok(()) } else { Err(CommandError::InvalidValue) }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::text::{CharClass, matches_pattern};
+
+    #[test]
+    fn char_classes() {
+        assert!(CharClass::Alphanumeric.accepts('x'));
+        assert!(CharClass::Alphanumeric.accepts('7'));
+        assert!(!CharClass::Alphanumeric.accepts('-'));
+        assert!(CharClass::Punctuation.accepts('-'));
+        assert!(!CharClass::Ascii.accepts('é'));
+        assert!(CharClass::all().accepts('é'));
+    }
+
+    #[test]
+    fn patterns() {
+        assert!(matches_pattern("###-####", "555-1234", false));
+        assert!(!matches_pattern("###-####", "555-12345", false));
+        assert!(!matches_pattern("###-####", "555-123", false));
+        assert!(matches_pattern("*.local", "printer.local", false));
+        assert!(matches_pattern("*.local", ".local", false));
+        assert!(!matches_pattern("*.local", "printer.lan", false));
+        assert!(matches_pattern("@*-##", "room-a-12", false));
+        assert!(matches_pattern("\\#?", "#1", false));
+        assert!(!matches_pattern("\\#?", "11", false));
+        assert!(matches_pattern("a*b*c", "aXbYbZc", false));
+        assert!(matches_pattern("", "", false));
+        assert!(!matches_pattern("", "x", false));
+    }
+
+    #[test]
+    fn partial_patterns() {
+        assert!(matches_pattern("###-####", "555-1", true));
+        assert!(!matches_pattern("###-####", "55x", true));
+        assert!(matches_pattern("*.local", "printer.lo", true));
+        assert!(!matches_pattern("#*", "x", true));
+    }
+}
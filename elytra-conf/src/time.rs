@@ -0,0 +1,163 @@
+use core::prelude::rust_2024::{*};
+
+/// Seconds in a day, the exclusive upper bound of a [`TimeOfDay`].
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Time of day without a date or time zone, stored as seconds since midnight.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl TimeOfDay {
+    pub const MIDNIGHT: Self = Self::new(0, 0, 0);
+
+    pub const fn new(hour: u8, minute: u8, second: u8) -> Self {
+        if hour > 23 || minute > 59 || second > 59 { panic!("time of day out of range") }
+        Self { hour, minute, second }
+    }
+
+    /// Time of day `seconds` after midnight, wrapping around at the end of the day.
+    pub const fn from_seconds(seconds: i64) -> Self {
+        let seconds = seconds.rem_euclid(SECONDS_PER_DAY);
+        Self {
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+
+    pub const fn seconds(&self) -> i64 {
+        self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+}
+
+/// Date in the proleptic Gregorian calendar, stored as days since 1970-01-01.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    pub const EPOCH: Self = Self::new(1970, 1, 1);
+    pub const MIN: Self = Self::new(i32::MIN, 1, 1);
+    pub const MAX: Self = Self::new(i32::MAX, 12, 31);
+
+    pub const fn new(year: i32, month: u8, day: u8) -> Self {
+        match Self::try_new(year, month, day) {
+            Some(date) => date,
+            None => panic!("date out of range"),
+        }
+    }
+
+    /// Checked variant of [`Date::new`].
+    pub const fn try_new(year: i32, month: u8, day: u8) -> Option<Self> {
+        if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Self { year, month, day })
+    }
+
+    /// Days before [`Date::MIN`] or after [`Date::MAX`] are clamped to them.
+    // Civil calendar conversions from http://howardhinnant.github.io/date_algorithms.html
+    pub const fn from_days(days: i64) -> Self {
+        const MIN_DAYS: i64 = Date::MIN.days();
+        const MAX_DAYS: i64 = Date::MAX.days();
+        let days = if days < MIN_DAYS { MIN_DAYS } else if days > MAX_DAYS { MAX_DAYS } else { days };
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        Self { year: year as i32, month, day }
+    }
+
+    pub const fn days(&self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = self.month as i64;
+        let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+}
+
+/// Date and time in UTC, stored as seconds since the Unix epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DateTime {
+    pub date: Date,
+    pub time: TimeOfDay,
+}
+
+impl DateTime {
+    pub const fn new(date: Date, time: TimeOfDay) -> Self {
+        Self { date, time }
+    }
+
+    pub const fn from_timestamp(seconds: i64) -> Self {
+        Self {
+            date: Date::from_days(seconds.div_euclid(SECONDS_PER_DAY)),
+            time: TimeOfDay::from_seconds(seconds),
+        }
+    }
+
+    pub const fn timestamp(&self) -> i64 {
+        self.date.days() * SECONDS_PER_DAY + self.time.seconds()
+    }
+}
+
+const fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+pub const fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Date, DateTime, TimeOfDay};
+
+    #[test]
+    fn date_days_roundtrip() {
+        assert_eq!(0, Date::EPOCH.days());
+        assert_eq!(19_844, Date::new(2024, 5, 1).days());
+        assert_eq!(-1, Date::new(1969, 12, 31).days());
+        assert_eq!(Date::new(2000, 2, 29), Date::from_days(Date::new(2000, 2, 29).days()));
+        for days in (-800_000..800_000).step_by(97) {
+            assert_eq!(days, Date::from_days(days).days());
+        }
+        assert_eq!(None, Date::try_new(2023, 2, 29));
+        assert_eq!(Date::MAX, Date::from_days(Date::MAX.days()));
+        assert_eq!(Date::MIN, Date::from_days(Date::MIN.days()));
+        assert_eq!(Date::MAX, Date::from_days(i64::MAX));
+        assert_eq!(Date::MIN, Date::from_days(i64::MIN));
+    }
+
+    #[test]
+    fn timestamp_roundtrip() {
+        let dt = DateTime::new(Date::new(2024, 5, 1), TimeOfDay::new(7, 30, 15));
+        assert_eq!(1_714_548_615, dt.timestamp());
+        assert_eq!(dt, DateTime::from_timestamp(dt.timestamp()));
+        assert_eq!(TimeOfDay::new(23, 59, 59), DateTime::from_timestamp(-1).time);
+        assert_eq!(Date::MAX, DateTime::from_timestamp(i64::MAX).date);
+        assert_eq!(Date::MIN, DateTime::from_timestamp(i64::MIN).date);
+    }
+}
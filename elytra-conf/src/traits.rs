@@ -1,6 +1,8 @@
 use core::{fmt::Debug, prelude::rust_2024::*};
 
-use crate::entry::EntryDesc;
+use crate::entry::{EntryDesc, EntryVariant};
+use crate::table::row_prefix;
+use crate::values::ValueType;
 
 #[cfg(feature = "defmt")]
 pub trait Index: Sized + Copy + Eq + defmt::Format{}
@@ -13,6 +15,9 @@ pub trait ActionIndex: Sized + Copy + Eq + Debug {
     fn from_byte(byte: u8) -> Option<Self>;
     fn get_entry(self) -> &'static EntryDesc;
     fn count() -> usize;
+    /// Stable identifier of the entry, unaffected by declaration order.
+    fn id(self) -> u32;
+    fn from_id(id: u32) -> Option<Self>;
 }
 
 pub trait PropIndex: Sized + Copy + Eq + Debug {
@@ -20,6 +25,9 @@ pub trait PropIndex: Sized + Copy + Eq + Debug {
     fn from_byte(byte: u8) -> Option<Self>;
     fn get_entry(self) -> &'static EntryDesc;
     fn count() -> usize;
+    /// Stable identifier of the entry, unaffected by declaration order.
+    fn id(self) -> u32;
+    fn from_id(id: u32) -> Option<Self>;
 }
 
 pub trait SectionIndex: Sized + Copy + Eq + Debug  {
@@ -27,11 +35,114 @@ pub trait SectionIndex: Sized + Copy + Eq + Debug  {
     fn from_byte(byte: u8) -> Option<Self>;
     fn get_entry(self) -> &'static EntryDesc;
     fn count() -> usize;
+    /// Stable identifier of the entry, unaffected by declaration order.
+    fn id(self) -> u32;
+    fn from_id(id: u32) -> Option<Self>;
 }
 
 pub trait InfoIndex: Sized + Copy + Eq + Debug {
     fn as_index(self) -> usize;
     fn from_byte(byte: u8) -> Option<Self>;
-    fn get_entry<'s>(self) -> &'static EntryDesc;
+    fn get_entry(self) -> &'static EntryDesc;
     fn count() -> usize;
-}
\ No newline at end of file
+    /// Stable identifier of the entry, unaffected by declaration order.
+    fn id(self) -> u32;
+    fn from_id(id: u32) -> Option<Self>;
+}
+
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+/// Multiplicative inverse of [`FNV_PRIME`] modulo 2^32.
+const FNV_PRIME_INVERSE: u32 = 0x359c_449b;
+
+/// Continues the 32 bit FNV-1a `hash` with `byte`.
+pub(crate) const fn fnv1a(hash: u32, byte: u8) -> u32 {
+    (hash ^ byte as u32).wrapping_mul(FNV_PRIME)
+}
+
+/// 32 bit FNV-1a hash of `bytes`.
+pub(crate) fn fnv1a_hash(bytes: impl IntoIterator<Item = u8>) -> u32 {
+    bytes.into_iter().fold(FNV_OFFSET, fnv1a)
+}
+
+/// 32 bit FNV-1a hash of `bytes`, usable in constants.
+pub(crate) const fn fnv1a_bytes(bytes: &[u8]) -> u32 {
+    let mut hash = FNV_OFFSET;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = fnv1a(hash, bytes[i]);
+        i += 1;
+    }
+    hash
+}
+
+/// Default entry id, the 32 bit FNV-1a hash of the entry identifier.
+pub const fn entry_id(name: &str) -> u32 {
+    fnv1a_bytes(name.as_bytes())
+}
+
+/// Id reserved for bookkeeping, such as the stored schema version.
+pub const RESERVED_ID: u32 = u32::MAX;
+
+/// Returns `true` if no id occurs more than once in `ids` and none is [`RESERVED_ID`].
+pub const fn ids_valid(ids: &[u32]) -> bool {
+    let mut i = 0;
+    while i < ids.len() {
+        if ids[i] == RESERVED_ID {
+            return false;
+        }
+        let mut j = i + 1;
+        while j < ids.len() {
+            if ids[i] == ids[j] {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Returns `true` if the store keys of the rows of every table in `entries`, which has the
+/// ids `ids`, differ from each other, from all ids and from [`RESERVED_ID`].
+pub const fn row_keys_valid(entries: &[EntryDesc], ids: &[u32]) -> bool {
+    let mut i = 0;
+    while i < entries.len() {
+        if !is_table(&entries[i]) {
+            i += 1;
+            continue;
+        }
+        let rows = entries[i].max_items as u32;
+        let prefix = row_prefix(ids[i]);
+        // Undoing the last FNV-1a step gives the only row that could be stored under `id`
+        let mut j = 0;
+        while j <= ids.len() {
+            let id = if j < ids.len() { ids[j] } else { RESERVED_ID };
+            if prefix ^ id.wrapping_mul(FNV_PRIME_INVERSE) < rows {
+                return false;
+            }
+            j += 1;
+        }
+        // Rows of two tables collide if their prefixes differ in the row byte only
+        let mut j = i + 1;
+        while j < entries.len() {
+            let offset = prefix ^ row_prefix(ids[j]);
+            if is_table(&entries[j]) && offset <= u8::MAX as u32 {
+                let mut row = 0;
+                while row < rows {
+                    if row ^ offset < entries[j].max_items as u32 {
+                        return false;
+                    }
+                    row += 1;
+                }
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn is_table(entry: &EntryDesc) -> bool {
+    matches!(entry.variant, EntryVariant::Field(ValueType::Table))
+}
@@ -0,0 +1,624 @@
+//! Prop writes that are applied together or not at all, so a link that drops between
+//! related writes, like a network name and its password, leaves no half applied
+//! configuration behind.
+//!
+//! The host sends `Transaction` with `Begin`, any number of prop writes and `Commit`.
+//! [`TransactionHandler`] stages the writes in a slot per prop, reads of those props
+//! return the staged value and the wrapped handler only sees the writes on commit. A
+//! transaction that sees no commands for its timeout is aborted. Only the commands that
+//! reach the handler count, those the config answers itself, like `Query`, don't.
+//!
+//! Changes that can cut off the host, like network settings, are committed with
+//! `CommitPending` instead. The writes are applied right away but the previous values
+//! are kept and written back unless the host sends `Confirm` in time, which it can
+//! only do if it still reaches the device. Prop writes fail until then, the revert
+//! would overwrite them.
+use core::prelude::rust_2024::{*};
+use core::time::Duration;
+
+use crate::{
+    command::CommandError,
+    field::FieldValue,
+    handler::{AsyncCommandHandler, ChunkBuffer, CommandHandler},
+    identity::SerialNumber,
+    table::Record,
+    traits::{ActionIndex, InfoIndex, PropIndex},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Closed,
+    Open,
+    /// Committed, reverted unless confirmed within the given time.
+    Pending(Duration),
+}
+
+/// Stages prop writes between `Begin` and `Commit` and forwards everything else to
+/// `handler`. `N` is the number of staging slots and has to be at least the number of props.
+pub struct TransactionHandler<H, const N: usize> {
+    pub handler: H,
+    /// Staged values of an open transaction, previous values of a pending commit.
+    slots: [Option<FieldValue>; N],
+    state: State,
+    /// Time since the last command of an open transaction or since a pending commit.
+    idle: Duration,
+    timeout: Duration,
+}
+
+impl <H, const N: usize> TransactionHandler<H, N> {
+    /// Wraps `handler`, open transactions are aborted after `timeout` without commands.
+    pub const fn new(handler: H, timeout: Duration) -> Self {
+        Self { handler, slots: [const { None }; N], state: State::Closed, idle: Duration::ZERO, timeout }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state == State::Open
+    }
+
+    /// Whether a commit waits for the host to confirm it.
+    pub fn is_pending(&self) -> bool {
+        matches!(self.state, State::Pending(_))
+    }
+
+    fn begin<P: PropIndex>(&mut self) -> Result<(), CommandError> {
+        if P::count() > N {
+            return Err(CommandError::NotSupported);
+        }
+        // A pending commit has to be settled first, it would lose its previous values
+        if self.is_pending() {
+            return Err(CommandError::Failed);
+        }
+        // A host that lost the link begins again, dropping what it staged before
+        self.close();
+        self.state = State::Open;
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.state = State::Closed;
+        self.idle = Duration::ZERO;
+    }
+
+    /// Restarts the timeout of an open transaction, the time to confirm a pending commit
+    /// runs on.
+    fn touch(&mut self) {
+        if self.state == State::Open {
+            self.idle = Duration::ZERO;
+        }
+    }
+
+    /// Advances the idle time by `elapsed` and returns whether the timeout or confirm time
+    /// is over.
+    fn expired(&mut self, elapsed: Duration) -> bool {
+        let limit = match self.state {
+            State::Closed => return false,
+            State::Open => self.timeout,
+            State::Pending(within) => within,
+        };
+        self.idle = self.idle.saturating_add(elapsed);
+        self.idle >= limit
+    }
+
+    fn staged<P: PropIndex>(&mut self, prop: P) -> Option<FieldValue> {
+        self.touch();
+        match self.state {
+            State::Open => self.slots[prop.as_index()].clone(),
+            _ => None,
+        }
+    }
+
+    /// Stages `value`, or gives it back if no transaction is open. Fails while a commit
+    /// is pending, reverting it would overwrite the value.
+    fn stage<P: PropIndex>(&mut self, prop: P, value: FieldValue) -> Result<Option<FieldValue>, CommandError> {
+        self.touch();
+        match self.state {
+            State::Open => {
+                self.slots[prop.as_index()] = Some(value);
+                Ok(None)
+            },
+            State::Pending(_) => Err(CommandError::Failed),
+            State::Closed => Ok(Some(value)),
+        }
+    }
+
+    fn check_closed(&mut self) -> Result<(), CommandError> {
+        self.touch();
+        // Rows are written one by one and can't be staged
+        match self.state {
+            State::Open => Err(CommandError::NotSupported),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails while a transaction is open or a commit pending, their staged and previous
+    /// values would bring back what a factory reset erased.
+    fn check_settled(&mut self) -> Result<(), CommandError> {
+        self.touch();
+        match self.state {
+            State::Closed => Ok(()),
+            _ => Err(CommandError::Failed),
+        }
+    }
+
+    fn check_open(&self) -> Result<(), CommandError> {
+        match self.state {
+            State::Open => Ok(()),
+            _ => Err(CommandError::NoTransaction),
+        }
+    }
+}
+
+impl <H, const N: usize> TransactionHandler<H, N> {
+    /// Advances the time of an open transaction or pending commit by `elapsed`, aborting
+    /// the transaction or reverting the commit once its time is over. Firmware calls this
+    /// periodically, e.g. from its transport task. Returns whether anything was dropped.
+    pub fn elapse<P, I, A>(&mut self, elapsed: Duration) -> bool where
+        P: PropIndex, I: InfoIndex, A: ActionIndex, H: CommandHandler<P, I, A>
+    {
+        if !self.expired(elapsed) {
+            return false;
+        }
+        match self.state {
+            State::Pending(_) => self.revert::<P, I, A>(N),
+            _ => self.close(),
+        }
+        true
+    }
+
+    /// Writes the staged values to `handler`, the slots keep the previous values.
+    fn apply<P, I, A>(&mut self) -> Result<(), CommandError> where
+        P: PropIndex, I: InfoIndex, A: ActionIndex, H: CommandHandler<P, I, A>
+    {
+        self.check_open()?;
+        for index in 0..N {
+            let Some((value, prop)) = self.slots[index].take().zip(P::from_byte(index as u8)) else {
+                continue;
+            };
+            let result = self.handler.read_prop(prop)
+                .and_then(|previous| self.handler.write_prop(prop, value).map(|_| previous));
+            match result {
+                Ok(previous) => self.slots[index] = Some(previous),
+                Err(e) => {
+                    self.revert::<P, I, A>(index);
+                    return Err(e);
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes back the previous values in the slots before `until` and closes.
+    fn revert<P, I, A>(&mut self, until: usize) where
+        P: PropIndex, I: InfoIndex, A: ActionIndex, H: CommandHandler<P, I, A>
+    {
+        for (index, slot) in self.slots[..until].iter_mut().enumerate() {
+            if let Some((prop, previous)) = P::from_byte(index as u8).zip(slot.take()) {
+                let _ = self.handler.write_prop(prop, previous);
+            }
+        }
+        self.close();
+    }
+}
+
+impl <H, const N: usize> TransactionHandler<H, N> {
+    /// Async variant of [`elapse`](Self::elapse) for handlers implementing [`AsyncCommandHandler`].
+    pub async fn elapse_async<P, I, A>(&mut self, elapsed: Duration) -> bool where
+        P: PropIndex, I: InfoIndex, A: ActionIndex, H: AsyncCommandHandler<P, I, A>
+    {
+        if !self.expired(elapsed) {
+            return false;
+        }
+        match self.state {
+            State::Pending(_) => self.revert_async::<P, I, A>(N).await,
+            _ => self.close(),
+        }
+        true
+    }
+
+    async fn apply_async<P, I, A>(&mut self) -> Result<(), CommandError> where
+        P: PropIndex, I: InfoIndex, A: ActionIndex, H: AsyncCommandHandler<P, I, A>
+    {
+        self.check_open()?;
+        for index in 0..N {
+            let Some((value, prop)) = self.slots[index].take().zip(P::from_byte(index as u8)) else {
+                continue;
+            };
+            let result = match self.handler.read_prop(prop).await {
+                Ok(previous) => self.handler.write_prop(prop, value).await.map(|_| previous),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(previous) => self.slots[index] = Some(previous),
+                Err(e) => {
+                    self.revert_async::<P, I, A>(index).await;
+                    return Err(e);
+                },
+            }
+        }
+        Ok(())
+    }
+
+    async fn revert_async<P, I, A>(&mut self, until: usize) where
+        P: PropIndex, I: InfoIndex, A: ActionIndex, H: AsyncCommandHandler<P, I, A>
+    {
+        for index in 0..until {
+            if let Some((prop, previous)) = P::from_byte(index as u8).zip(self.slots[index].take()) {
+                let _ = self.handler.write_prop(prop, previous).await;
+            }
+        }
+        self.close();
+    }
+}
+
+impl <P, I, A, H, const N: usize> CommandHandler<P, I, A> for TransactionHandler<H, N> where
+    P: PropIndex, I: InfoIndex, A: ActionIndex, H: CommandHandler<P, I, A>
+{
+    fn read_prop(&mut self, prop: P) -> Result<FieldValue, CommandError> {
+        match self.staged(prop) {
+            Some(value) => Ok(value),
+            None => self.handler.read_prop(prop),
+        }
+    }
+
+    fn write_prop(&mut self, prop: P, value: FieldValue) -> Result<(), CommandError> {
+        match self.stage(prop, value)? {
+            Some(value) => self.handler.write_prop(prop, value),
+            None => Ok(()),
+        }
+    }
+
+    fn read_row(&mut self, prop: P, row: u8) -> Result<Record, CommandError> {
+        self.touch();
+        self.handler.read_row(prop, row)
+    }
+
+    fn write_row(&mut self, prop: P, row: u8, record: Record) -> Result<(), CommandError> {
+        self.check_closed()?;
+        self.handler.write_row(prop, row, record)
+    }
+
+    fn delete_row(&mut self, prop: P, row: u8) -> Result<(), CommandError> {
+        self.check_closed()?;
+        self.handler.delete_row(prop, row)
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), CommandError> {
+        self.begin::<P>()
+    }
+
+    fn commit_transaction(&mut self) -> Result<(), CommandError> {
+        self.apply::<P, I, A>()?;
+        self.close();
+        Ok(())
+    }
+
+    fn commit_transaction_pending(&mut self, within: Duration) -> Result<(), CommandError> {
+        self.apply::<P, I, A>()?;
+        self.state = State::Pending(within);
+        self.idle = Duration::ZERO;
+        Ok(())
+    }
+
+    fn confirm_transaction(&mut self) -> Result<(), CommandError> {
+        if !self.is_pending() {
+            return Err(CommandError::NoTransaction);
+        }
+        self.close();
+        Ok(())
+    }
+
+    fn abort_transaction(&mut self) -> Result<(), CommandError> {
+        match self.state {
+            State::Open => self.close(),
+            State::Pending(_) => self.revert::<P, I, A>(N),
+            State::Closed => return Err(CommandError::NoTransaction),
+        }
+        Ok(())
+    }
+
+    fn read_info(&mut self, info: I) -> Result<FieldValue, CommandError> {
+        self.touch();
+        self.handler.read_info(info)
+    }
+
+    fn write_info(&mut self, info: I, value: FieldValue) -> Result<(), CommandError> {
+        self.touch();
+        self.handler.write_info(info, value)
+    }
+
+    fn do_action(&mut self, action: A) -> Result<(), CommandError> {
+        self.touch();
+        self.handler.do_action(action)
+    }
+
+    fn serial_number(&mut self) -> Option<SerialNumber> {
+        self.touch();
+        self.handler.serial_number()
+    }
+
+    fn noop(&mut self) {
+        self.touch();
+        self.handler.noop()
+    }
+
+    fn factory_reset(&mut self) -> Result<(), CommandError> {
+        self.check_settled()?;
+        self.handler.factory_reset()
+    }
+
+    fn chunk_buffer(&mut self) -> Option<&mut ChunkBuffer> {
+        self.touch();
+        self.handler.chunk_buffer()
+    }
+}
+
+impl <P, I, A, H, const N: usize> AsyncCommandHandler<P, I, A> for TransactionHandler<H, N> where
+    P: PropIndex, I: InfoIndex, A: ActionIndex, H: AsyncCommandHandler<P, I, A>
+{
+    async fn read_prop(&mut self, prop: P) -> Result<FieldValue, CommandError> {
+        match self.staged(prop) {
+            Some(value) => Ok(value),
+            None => self.handler.read_prop(prop).await,
+        }
+    }
+
+    async fn write_prop(&mut self, prop: P, value: FieldValue) -> Result<(), CommandError> {
+        match self.stage(prop, value)? {
+            Some(value) => self.handler.write_prop(prop, value).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn read_row(&mut self, prop: P, row: u8) -> Result<Record, CommandError> {
+        self.touch();
+        self.handler.read_row(prop, row).await
+    }
+
+    async fn write_row(&mut self, prop: P, row: u8, record: Record) -> Result<(), CommandError> {
+        self.check_closed()?;
+        self.handler.write_row(prop, row, record).await
+    }
+
+    async fn delete_row(&mut self, prop: P, row: u8) -> Result<(), CommandError> {
+        self.check_closed()?;
+        self.handler.delete_row(prop, row).await
+    }
+
+    async fn begin_transaction(&mut self) -> Result<(), CommandError> {
+        self.begin::<P>()
+    }
+
+    async fn commit_transaction(&mut self) -> Result<(), CommandError> {
+        self.apply_async::<P, I, A>().await?;
+        self.close();
+        Ok(())
+    }
+
+    async fn commit_transaction_pending(&mut self, within: Duration) -> Result<(), CommandError> {
+        self.apply_async::<P, I, A>().await?;
+        self.state = State::Pending(within);
+        self.idle = Duration::ZERO;
+        Ok(())
+    }
+
+    async fn confirm_transaction(&mut self) -> Result<(), CommandError> {
+        if !self.is_pending() {
+            return Err(CommandError::NoTransaction);
+        }
+        self.close();
+        Ok(())
+    }
+
+    async fn abort_transaction(&mut self) -> Result<(), CommandError> {
+        match self.state {
+            State::Open => self.close(),
+            State::Pending(_) => self.revert_async::<P, I, A>(N).await,
+            State::Closed => return Err(CommandError::NoTransaction),
+        }
+        Ok(())
+    }
+
+    async fn read_info(&mut self, info: I) -> Result<FieldValue, CommandError> {
+        self.touch();
+        self.handler.read_info(info).await
+    }
+
+    async fn write_info(&mut self, info: I, value: FieldValue) -> Result<(), CommandError> {
+        self.touch();
+        self.handler.write_info(info, value).await
+    }
+
+    async fn do_action(&mut self, action: A) -> Result<(), CommandError> {
+        self.touch();
+        self.handler.do_action(action).await
+    }
+
+    async fn serial_number(&mut self) -> Option<SerialNumber> {
+        self.touch();
+        self.handler.serial_number().await
+    }
+
+    async fn noop(&mut self) {
+        self.touch();
+        self.handler.noop().await
+    }
+
+    async fn factory_reset(&mut self) -> Result<(), CommandError> {
+        self.check_settled()?;
+        self.handler.factory_reset().await
+    }
+
+    fn chunk_buffer(&mut self) -> Option<&mut ChunkBuffer> {
+        self.touch();
+        self.handler.chunk_buffer()
+    }
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod test {
+    use core::time::Duration;
+
+    use crate::command::CommandError;
+    use crate::prelude::*;
+    use crate::store::{PropStore, RamStore, StoreHandler};
+    use crate::transaction::TransactionHandler;
+
+    use crate::config::test::fixture::*;
+
+    type Handler = TransactionHandler<StoreHandler<RamStore<4>, NoInfo>, 6>;
+
+    fn handler() -> Handler {
+        TransactionHandler::new(StoreHandler::new(RamStore::new(), NoInfo), Duration::from_secs(10))
+    }
+
+    fn dispatch<H: CommandHandler<PropField, InfoField, Action>>(handler: &mut H, command: &[u8]) -> [u8; 2] {
+        let res = CONF.dispatch(command, handler);
+        res.as_bytes()[0..2].try_into().unwrap()
+    }
+
+    #[test]
+    fn commit_applies_staged_writes() {
+        let mut handler = handler();
+        assert_eq!([1, 0], dispatch(&mut handler, b"tb"));
+        assert_eq!([1, 0], dispatch(&mut handler, b"w\x00home"));
+        assert_eq!([1, 0], dispatch(&mut handler, b"w\x04hunter22"));
+
+        // Reads see the staged values, the store doesn't
+        assert_eq!("home", handler.read_prop(PropField::Name).unwrap().get_text());
+        assert_eq!("", handler.handler.store.load(PropField::Name).unwrap().get_text());
+
+        assert_eq!([1, 0], dispatch(&mut handler, b"tc"));
+        assert!(!handler.is_open());
+        assert_eq!("home", handler.handler.store.load(PropField::Name).unwrap().get_text());
+        assert_eq!("hunter22", handler.handler.store.load(PropField::Pin).unwrap().get_text());
+        assert_eq!([0, CommandError::NoTransaction as u8], dispatch(&mut handler, b"tc"));
+    }
+
+    #[test]
+    fn abort_and_timeout_drop_staged_writes() {
+        let mut handler = handler();
+        dispatch(&mut handler, b"tb");
+        dispatch(&mut handler, b"w\x00home");
+        assert_eq!([1, 0], dispatch(&mut handler, b"tx"));
+        assert_eq!("", handler.read_prop(PropField::Name).unwrap().get_text());
+
+        dispatch(&mut handler, b"tb");
+        dispatch(&mut handler, b"w\x00home");
+        assert!(!handler.elapse(Duration::from_secs(6)));
+        // Every command restarts the timeout
+        dispatch(&mut handler, b"\0");
+        assert!(!handler.elapse(Duration::from_secs(6)));
+        dispatch(&mut handler, b"I");
+        assert!(!handler.elapse(Duration::from_secs(6)));
+        assert!(handler.elapse(Duration::from_secs(6)));
+        assert_eq!([0, CommandError::NoTransaction as u8], dispatch(&mut handler, b"tc"));
+        assert_eq!("", handler.read_prop(PropField::Name).unwrap().get_text());
+
+        // Writes outside a transaction are applied right away
+        assert_eq!([1, 0], dispatch(&mut handler, b"w\x00cafe"));
+        assert_eq!("cafe", handler.handler.store.load(PropField::Name).unwrap().get_text());
+    }
+
+    #[test]
+    fn pending_commit_reverts_unless_confirmed() {
+        let mut handler = handler();
+        dispatch(&mut handler, b"w\x00home");
+
+        dispatch(&mut handler, b"tb");
+        dispatch(&mut handler, b"w\x00office");
+        assert_eq!([1, 0], dispatch(&mut handler, b"tp\x05\0"));
+        assert!(handler.is_pending());
+        assert_eq!("office", handler.handler.store.load(PropField::Name).unwrap().get_text());
+        assert_eq!([0, CommandError::Failed as u8], dispatch(&mut handler, b"tb"));
+
+        // Commands don't extend the time to confirm
+        assert!(!handler.elapse(Duration::from_secs(3)));
+        dispatch(&mut handler, b"\0");
+        assert!(handler.elapse(Duration::from_secs(3)));
+        assert_eq!("home", handler.handler.store.load(PropField::Name).unwrap().get_text());
+        assert_eq!([0, CommandError::NoTransaction as u8], dispatch(&mut handler, b"tk"));
+
+        // A new transaction can start once the confirm time is over
+        assert_eq!([1, 0], dispatch(&mut handler, b"tb"));
+        dispatch(&mut handler, b"w\x00office");
+        dispatch(&mut handler, b"tp\x05\0");
+        assert_eq!([1, 0], dispatch(&mut handler, b"tk"));
+        assert!(!handler.elapse(Duration::from_secs(10)));
+        assert_eq!("office", handler.handler.store.load(PropField::Name).unwrap().get_text());
+
+        // Aborting a pending commit reverts it right away
+        dispatch(&mut handler, b"tb");
+        dispatch(&mut handler, b"w\x00cafe");
+        dispatch(&mut handler, b"tp\x05\0");
+        assert_eq!([1, 0], dispatch(&mut handler, b"tx"));
+        assert_eq!("office", handler.handler.store.load(PropField::Name).unwrap().get_text());
+    }
+
+    #[test]
+    fn factory_reset_waits_for_transactions() {
+        let mut handler = handler();
+        dispatch(&mut handler, b"w\x00home");
+        dispatch(&mut handler, b"w\x05\x03\0\0\0\0\0\0\0");
+
+        dispatch(&mut handler, b"tb");
+        dispatch(&mut handler, b"w\x00office");
+        assert_eq!([0, CommandError::Failed as u8], dispatch(&mut handler, b"Z"));
+        assert!(handler.is_open());
+        assert_eq!("office", handler.read_prop(PropField::Name).unwrap().get_text());
+
+        dispatch(&mut handler, b"tp\x05\0");
+        assert_eq!([0, CommandError::Failed as u8], dispatch(&mut handler, b"Z"));
+        assert!(handler.is_pending());
+        assert_eq!([1, 0], dispatch(&mut handler, b"tk"));
+        assert_eq!("office", handler.handler.store.load(PropField::Name).unwrap().get_text());
+        assert_eq!(3, handler.handler.store.load(PropField::Level).unwrap().get_integer());
+
+        assert_eq!([1, 0], dispatch(&mut handler, b"Z"));
+        assert_eq!("", handler.read_prop(PropField::Name).unwrap().get_text());
+        assert_eq!(7, handler.read_prop(PropField::Level).unwrap().get_integer());
+    }
+
+    #[test]
+    fn pending_commit_refuses_writes() {
+        let mut handler = handler();
+        dispatch(&mut handler, b"w\x00home");
+
+        dispatch(&mut handler, b"tb");
+        dispatch(&mut handler, b"w\x00office");
+        // The revert would run on the next tick
+        assert_eq!([0, CommandError::InvalidData as u8], dispatch(&mut handler, b"tp"));
+        assert_eq!([0, CommandError::InvalidData as u8], dispatch(&mut handler, b"tp\0\0"));
+        assert!(handler.is_open());
+
+        assert_eq!([1, 0], dispatch(&mut handler, b"tp\x05\0"));
+        assert_eq!([0, CommandError::Failed as u8], dispatch(&mut handler, b"w\x00cafe"));
+        assert_eq!([0, CommandError::Failed as u8], dispatch(&mut handler, b"z\x00"));
+        assert_eq!("office", handler.handler.store.load(PropField::Name).unwrap().get_text());
+
+        assert!(handler.elapse(Duration::from_secs(5)));
+        assert_eq!("home", handler.handler.store.load(PropField::Name).unwrap().get_text());
+        assert_eq!([1, 0], dispatch(&mut handler, b"w\x00cafe"));
+        assert_eq!("cafe", handler.handler.store.load(PropField::Name).unwrap().get_text());
+    }
+
+    #[test]
+    fn failed_commit_rolls_back() {
+        // The store fills up on the third prop
+        let mut handler: TransactionHandler<_, 6> = TransactionHandler::new(
+            StoreHandler::new(RamStore::<2>::new(), NoInfo), Duration::from_secs(10));
+        dispatch(&mut handler, b"w\x00home");
+
+        dispatch(&mut handler, b"tb");
+        dispatch(&mut handler, b"w\x00office");
+        dispatch(&mut handler, b"w\x04hunter22");
+        dispatch(&mut handler, b"w\x05\x03\0\0\0\0\0\0\0");
+        assert_eq!([0, CommandError::Failed as u8], dispatch(&mut handler, b"tc"));
+        assert!(!handler.is_open());
+
+        let store = &mut handler.handler.store;
+        assert_eq!("home", store.load(PropField::Name).unwrap().get_text());
+        assert_eq!("", store.load(PropField::Pin).unwrap().get_text());
+        assert_eq!(7, store.load(PropField::Level).unwrap().get_integer());
+    }
+}
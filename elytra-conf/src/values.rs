@@ -2,26 +2,115 @@ use core::prelude::rust_2024::{*};
 
 use num_enum::{TryFromPrimitive};
 
+use crate::color::Color;
+
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, strum::Display, TryFromPrimitive)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ValueType {
-    Text = 't' as u8,
-    Secret = 's' as u8,
-    Integer = 'i' as u8,
-    Status = 'c' as u8,
-    Bytes = 'b' as u8,
-    Options = 'o' as u8,
+    Text = b't',
+    Secret = b's',
+    Integer = b'i',
+    Status = b'c',
+    Bytes = b'b',
+    Options = b'o',
+    Bool = b'B',
+    Decimal = b'd',
+    Ipv4 = b'4',
+    Ipv6 = b'6',
+    /// Network prefix such as `192.168.1.10/24`, see [`net::cidr_bytes`](crate::net::cidr_bytes).
+    Cidr = b'/',
+    Mac = b'm',
+    Hostname = b'h',
+    /// Seconds since midnight, see [`TimeOfDay`](crate::time::TimeOfDay).
+    Time = b'T',
+    /// Days since 1970-01-01, see [`Date`](crate::time::Date).
+    Date = b'D',
+    /// Seconds since the Unix epoch in UTC, see [`DateTime`](crate::time::DateTime).
+    Timestamp = b'@',
+    /// Length of time in seconds.
+    Duration = b'P',
+    /// Red, green and blue bytes, see [`Color`].
+    Color = b'#',
+    /// Red, green, blue and alpha bytes, see [`Color`].
+    ColorAlpha = b'&',
+    /// Set of named bits stored as a `u64`, the names are the options of the field.
+    Flags = b'f',
+    /// Rows of records with a value for every column, see [`crate::table`]. The value of
+    /// the prop itself is the number of rows.
+    Table = b'[',
 }
 impl ValueType {
     pub(crate) fn is_options(&self) -> bool {
-        match self {
-            Self::Options => true,
-            _ => false
-        }
+        matches!(self, Self::Options)
+    }
+
+    /// Types stored as an `i64`, which share range and step constraints.
+    pub const fn is_integer(&self) -> bool {
+        matches!(self, Self::Integer | Self::Decimal | Self::Time | Self::Date | Self::Timestamp | Self::Duration)
+    }
+
+    /// Types holding UTF-8 text, which share length and [`TextFormat`](crate::text::TextFormat)
+    /// constraints.
+    pub const fn is_text(&self) -> bool {
+        matches!(self, Self::Text | Self::Secret | Self::Hostname)
+    }
+
+    /// Address types that are validated when written, see [`crate::net`].
+    pub(crate) fn is_network(&self) -> bool {
+        matches!(self, Self::Ipv4 | Self::Ipv6 | Self::Cidr | Self::Mac | Self::Hostname)
     }
 }
 
+/// Constraints and display hints of numeric values beyond their range.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NumberFormat {
+    /// Decimal places of [`ValueType::Decimal`] values, which are stored in units of `10^-scale`.
+    pub scale: u8,
+    /// Values are multiples of `step` counted from the start of the range, `0` allows any value.
+    pub step: u64,
+    /// Radix hosts display and enter the value in, `2`, `8`, `10` or `16`.
+    pub radix: u8,
+    /// The value and range are `u64` instead of `i64`.
+    pub unsigned: bool,
+    pub unit: Option<&'static str>,
+}
+
+impl NumberFormat {
+    pub const DEFAULT: Self = Self {
+        scale: 0,
+        step: 0,
+        radix: 10,
+        unsigned: false,
+        unit: None,
+    };
+
+    /// Encodes everything but the unit: scale, radix, unsigned (1 byte each) and step (8 bytes).
+    pub fn bits(&self) -> [u8; 11] {
+        let mut bits = [0u8; 11];
+        bits[0] = self.scale;
+        bits[1] = self.radix;
+        bits[2] = self.unsigned as u8;
+        bits[3..].copy_from_slice(&self.step.to_le_bytes());
+        bits
+    }
+}
+
+/// Largest number of decimal places of a [`ValueType::Decimal`] field.
+pub const MAX_SCALE: u8 = 9;
+
+/// `10^scale`, the number of stored units in one whole decimal value.
+pub const fn scale_factor(scale: u8) -> i64 {
+    10i64.pow(scale as u32)
+}
+
+/// Converts `value` to stored units of the given `scale`, rounding to the nearest unit.
+pub const fn decimal_units(value: f64, scale: u8) -> i64 {
+    let units = value * scale_factor(scale) as f64;
+    if units < 0.0 { (units - 0.5) as i64 } else { (units + 0.5) as i64 }
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DefaultValue {
@@ -30,6 +119,10 @@ pub enum DefaultValue {
     Integer(i64),
     Options(&'static [u16]),
     Bytes(&'static [u8]),
+    Bool(bool),
+    /// Decimal value in units of the field scale, `215` is `21.5` for a scale of 1.
+    Decimal(i64),
+    Color(Color),
 }
 
 impl From<&'static str> for DefaultValue {
@@ -44,6 +137,18 @@ impl From<i64> for DefaultValue {
     }
 }
 
+impl From<Color> for DefaultValue {
+    fn from(value: Color) -> Self {
+        Self::Color(value)
+    }
+}
+
+impl From<bool> for DefaultValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
 impl From<&'static [u8]> for DefaultValue {
     fn from(value: &'static [u8]) -> Self {
         Self::Bytes(value)
@@ -1,21 +1,24 @@
 use std::io::Write;
 
+use elytra_conf::{command::CommandError, config::VALUE_LENGTH, store::{PropStore, StoreBytes}};
+
 #[macro_export]
 macro_rules! elytra_wasm {
-    ( $e:expr ) => {
+    ( @export $conf:expr, $handler_ty:ty = $handler:expr, |$bytes:ident, $h:ident| $dispatch:block ) => {
 
         thread_local! {
             static ELYTRA_WASM_OUT: std::cell::Cell<[u64; 8]> = std::cell::Cell::new([0; 8]);
+            static ELYTRA_WASM_HANDLER: std::cell::RefCell<$handler_ty> = std::cell::RefCell::new($handler);
         }
 
         #[allow(unused)]
         #[unsafe(no_mangle)]
         pub extern "C" fn send(a: u64, b: u64, c: u64, d: u64, e: u64, f: u64, g: u64, h: u64) -> u8 {
             let in_bytes = elytra_wasm::unpack64([a, b, c, d, e, f, g, h]);
-            let res = match elytra_conf::command::Command::from_bytes(&in_bytes) {
-                Ok(command) => $e(command),
-                Err(e) => elytra_conf::command::CommandResponse::error(e)
-            };
+            let res = ELYTRA_WASM_HANDLER.with_borrow_mut(|$h| {
+                let $bytes = &in_bytes;
+                $dispatch
+            });
             if let Ok(res_bytes) = res.as_bytes().try_into() {
                 ELYTRA_WASM_OUT.set(elytra_wasm::pack64(res_bytes));
                 return 8;
@@ -31,8 +34,83 @@ macro_rules! elytra_wasm {
         }
 
     };
+    ( @tick |$h:ident, $elapsed:ident| $tick:block ) => {
+
+        /// Called by the host with the time passed since its previous call.
+        #[allow(unused)]
+        #[unsafe(no_mangle)]
+        pub extern "C" fn tick(millis: u64) {
+            ELYTRA_WASM_HANDLER.with_borrow_mut(|$h| {
+                let $elapsed = core::time::Duration::from_millis(millis);
+                $tick
+            });
+        }
+
+    };
+    ( $conf:expr, async $handler_ty:ty = $handler:expr, tick = |$h:ident, $elapsed:ident| $tick:block ) => {
+        $crate::elytra_wasm!($conf, async $handler_ty = $handler);
+        $crate::elytra_wasm!(@tick |$h, $elapsed| $tick);
+    };
+    ( $conf:expr, $handler_ty:ty = $handler:expr, tick = |$h:ident, $elapsed:ident| $tick:block ) => {
+        $crate::elytra_wasm!($conf, $handler_ty = $handler);
+        $crate::elytra_wasm!(@tick |$h, $elapsed| $tick);
+    };
+    ( $conf:expr, async $handler_ty:ty = $handler:expr ) => {
+        $crate::elytra_wasm!(@export $conf, $handler_ty = $handler, |bytes, handler| {
+            elytra_wasm::sync_await($conf.dispatch_async(bytes, handler))
+        });
+    };
+    ( $conf:expr, $handler_ty:ty = $handler:expr ) => {
+        $crate::elytra_wasm!(@export $conf, $handler_ty = $handler, |bytes, handler| {
+            $conf.dispatch(bytes, handler)
+        });
+    };
+}
+
+
+#[link(wasm_import_module = "elytra")]
+unsafe extern "C" {
+    fn store_load(key: u32, bytes: *mut u8) -> i32;
+    fn store_save(key: u32, bytes: *const u8) -> i32;
+    fn store_erase(key: u32) -> i32;
+    fn store_erase_all() -> i32;
 }
 
+/// [`PropStore`] that keeps values on the host running the module, using the
+/// `elytra.store_*` imports.
+///
+/// The imports return `1` when a value was found, `0` when it wasn't and a negative
+/// value on failure.
+#[derive(Default)]
+pub struct HostStore;
+
+fn host_result(res: i32) -> Result<bool, CommandError> {
+    if res < 0 {
+        Err(CommandError::Failed)
+    } else {
+        Ok(res > 0)
+    }
+}
+
+impl PropStore for HostStore {
+    fn load_key(&mut self, key: u32) -> Result<Option<StoreBytes>, CommandError> {
+        let mut bytes = [0u8; VALUE_LENGTH];
+        let found = host_result(unsafe { store_load(key, bytes.as_mut_ptr()) })?;
+        Ok(found.then_some(bytes))
+    }
+
+    fn save_key(&mut self, key: u32, bytes: &StoreBytes) -> Result<(), CommandError> {
+        host_result(unsafe { store_save(key, bytes.as_ptr()) }).map(|_| ())
+    }
+
+    fn erase_key(&mut self, key: u32) -> Result<(), CommandError> {
+        host_result(unsafe { store_erase(key) }).map(|_| ())
+    }
+
+    fn erase_all(&mut self) -> Result<(), CommandError> {
+        host_result(unsafe { store_erase_all() }).map(|_| ())
+    }
+}
 
 pub fn sync_await<T, F: Future<Output = T>>(fut: F) -> T {
     use std::task::Poll::*;
@@ -1,54 +1,49 @@
-use elytra_conf::{command::{CommandError, CommandResponse}, field::FieldValue, traits::*};
+use elytra_conf::{command::CommandError, field::FieldValue, handler::CommandHandler, identity::SerialNumber, store::Migration, time::{Date, DateTime, TimeOfDay}, traits::*};
 use log::debug;
-use crate::{Action, InfoField, MOCK_CONF, PropField, Section};
-type Command = elytra_conf::command::Command<Action, PropField, InfoField, Section>;
+use crate::{Action, InfoField, PropField};
 
-pub fn handle_command(command: Command) -> CommandResponse {
-    match command {
-        Command::ReadProp(p) => {
-            debug!("CMD: ReadProp: {:?}", p);
-            let bytes_from_store = [0u8; 64];
-            FieldValue::from_store(p.get_entry(), bytes_from_store).into()
-        },
-        Command::WriteProp((prop_field, field_value)) => {
-            debug!("CMD: WriteProp: {:?}", prop_field);
-            debug!(" => {:x?}", field_value);
-            CommandResponse::ok()
-        },
-        Command::ReadInfo(i) => {
-            debug!("CMD: ReadInfo: {:?}", i);
-            let mut fv = FieldValue::new(i.get_entry());
-            use InfoField::*;
-            match i {
-                WifiStatus => fv.set_status(3, "Performing dark rituals"),
-                FlashUUID => fv.set_bytes(&[0, 1, 2, 3, 4, 5, 6, 7]),
-                FlashJEDEC => fv.set_bytes(&[0x0a, 0xbc, 0xde, 0xf0]),
-                PicoROM => fv.set_text("ROM Version: 0 (BADC0FFE)"),
-                Time => fv.set_text("01:23"),
-            };
-            fv.into()
-        },
-        Command::WriteInfo((info_field, field_value)) => {
-            debug!("CMD: WriteInfo: {:?}", info_field);
-            debug!(" => {:x?}", field_value);
-            CommandResponse::error(CommandError::NotSupported)
-        },
-        Command::Query((entry_index, target)) => {
-            debug!("CMD: query: {:?} {:?}", entry_index, target);
-            MOCK_CONF.handle_query(entry_index, target).into()
-        },
-        Command::Action(action) => {
-            debug!("CMD: action: {:?}", action);
-            CommandResponse::ok()
-        },
-        Command::Meta => {
-            debug!("CMD: meta");
-            MOCK_CONF.handle_meta().into()
-        },
-        Command::Noop => {
-            debug!("CMD: noop");
-            CommandResponse::ok()
-        },
+/// Upgrades of the values stored by older firmware, one for each schema version. Add one
+/// and raise the config `version` when a prop changes meaning.
+pub const MIGRATIONS: [Migration; 0] = [];
+
+/// Handles everything but props, which are served from the store by `StoreHandler`.
+#[derive(Default)]
+pub struct ClockHandler;
+
+impl CommandHandler<PropField, InfoField, Action> for ClockHandler {
+    fn read_info(&mut self, info: InfoField) -> Result<FieldValue, CommandError> {
+        debug!("CMD: ReadInfo: {:?}", info);
+        let mut fv = FieldValue::new(info.get_entry());
+        use InfoField::*;
+        match info {
+            WifiStatus => fv.set_status(3, "Performing dark rituals"),
+            WifiMac => fv.set_mac([0x28, 0xcd, 0xc1, 0x0e, 0x4a, 0x17]),
+            FlashUUID => fv.set_bytes(&[0, 1, 2, 3, 4, 5, 6, 7]),
+            FlashJEDEC => fv.set_bytes(&[0x0a, 0xbc, 0xde, 0xf0]),
+            PicoROM => fv.set_text("ROM Version: 0 (BADC0FFE)"),
+            Time => fv.set_time(TimeOfDay::new(1, 23, 0)),
+            LastSync => fv.set_datetime(DateTime::new(Date::new(2024, 5, 1), TimeOfDay::new(0, 42, 17))),
+            Temperature => fv.set_decimal(21.5),
+        };
+        Ok(fv)
     }
 
-}
\ No newline at end of file
+    fn write_info(&mut self, info: InfoField, value: FieldValue) -> Result<(), CommandError> {
+        debug!("CMD: WriteInfo: {:?}", info);
+        debug!(" => {:x?}", value);
+        Err(CommandError::NotSupported)
+    }
+
+    fn do_action(&mut self, action: Action) -> Result<(), CommandError> {
+        debug!("CMD: action: {:?}", action);
+        Ok(())
+    }
+
+    fn serial_number(&mut self) -> Option<SerialNumber> {
+        Some(SerialNumber::uuid([0xe1, 0x7a, 0x00, 0x00, 0, 0, 0x40, 0, 0x80, 0, 0, 0, 0, 0, 0, 0x42]))
+    }
+
+    fn noop(&mut self) {
+        debug!("CMD: noop");
+    }
+}
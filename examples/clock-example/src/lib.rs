@@ -1,6 +1,8 @@
 #![feature(macro_metavar_expr)]
 
+use core::time::Duration;
 use elytra_conf::elytra;
+use elytra_conf::entry::EntryDesc;
 use elytra_conf::prelude::{*};
 use chrono_tz::TZ_VARIANTS;
 
@@ -18,12 +20,23 @@ impl OptionValueProvider for TimeZoneOpts {
     }
 }
 const TIME_ZONE_OPTS: TimeZoneOpts = TimeZoneOpts{};
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const SEGMENT_PALETTE: [&str; 4] = ["Red #ff3000", "Amber #ffb000", "Green #30ff60", "Ice blue #a0e0ff"];
+const KNOWN_NETWORK: [EntryDesc; 3] = [
+    prop("SSID").with_length(1, 32).as_entry(),
+    secret("Password").with_length(8, 63).with_chars(CharClass::Ascii).as_entry(),
+    integer("Priority").writable().with_range(0..9).with_default_integer(5).as_entry(),
+];
 
 elytra!( pub MOCK_CONF: MockConf {
+    identity: Identity::new("Elytra", "Clock", env!("CARGO_PKG_VERSION")).with_hardware("B"),
     info: InfoField {
         WifiStatus: status("Connection Status")
             .with_help("The current progress or result (failure or success)")
             .with_icon("wifi-sync"),
+        WifiMac: mac("MAC Address")
+            .with_help("The hardware address of the WiFi chip")
+            .readonly(),
 
         FlashUUID: bytes("Flash Unique ID", 8)
             .with_help("A unique identifier for the flash chip"),
@@ -31,28 +44,77 @@ elytra!( pub MOCK_CONF: MockConf {
             .with_help("The manufacturer flash chip designation"),
         PicoROM: info("Pico ROM")
             .with_help("The version of the Read Only firmware of the Pico"),
-        Time: info("Time")
-            .with_help("The current time, as would be displayed on the clock")
+        Time: time("Time")
+            .readonly()
+            .with_help("The current time, as would be displayed on the clock"),
+        LastSync: timestamp("Last Sync")
+            .with_help("When the time was last synced with the NTP server")
+            .readonly(),
+        Temperature: decimal("Temperature", 1)
+            .with_help("Temperature measured by the clock")
+            .with_unit("°C")
+            .readonly()
     },
     props: PropField {
         WifiNetwork: prop("Network (SSID)")
-            .with_help("The name the WiFi network to connect to"),
+            .with_help("The name the WiFi network to connect to")
+            .with_length(1, 32),
         WifiPassword: secret("Password")
-            .with_help("The password for the WiFi network"),
+            .with_help("The password for the WiFi network")
+            .with_length(8, 63)
+            .with_chars(CharClass::Ascii),
+        Hostname: hostname("Hostname")
+            .with_help("The name the clock announces on the network")
+            .with_default_text("elytra-clock"),
+        StaticIp: cidr("Static IP")
+            .with_help("Address and prefix length to use instead of DHCP, leave empty for DHCP"),
+        Gateway: ipv4("Gateway")
+            .with_help("The router used with a static IP"),
+        Dns: ipv4("DNS Server")
+            .with_help("The name server used with a static IP")
+            .with_default_bytes(&[1, 1, 1, 1]),
         BrightOffset: integer("Brightness Offset")
             .with_help("Adjustment of the display brightness auto value")
             .writable()
-            .with_range(-1500..1500),
-        Serial: integer("Serial number")
-            .with_help("The unique series number of your device")
-            .writable(),
+            .with_range(-1500..1500)
+            .with_step(100),
+        SegmentColor: color("Segment Colour")
+            .with_help("Colour of the lit display segments")
+            .with_suggestions(&SEGMENT_PALETTE)
+            .with_default_color(Color::hex(0xff3000)),
         TimeZone: prop("Timezone")
             .with_options(&TIME_ZONE_OPTS)
             .with_help("The timezone used for adjusting DST and displayed time offset")
             .with_default_text("Europe/Stockholm"),
-        NtpServer: prop("NTP Server")
-            .with_help("The Network Time Protocol server to query for the current time")
-            .with_default_text("ntp.se")
+        NtpServer: hostname("NTP Servers")
+            .with_help("The Network Time Protocol servers to query for the current time, in order")
+            .list(4)
+            .with_default_text("ntp.se"),
+        SyncInterval: duration("Sync Interval")
+            .with_help("How often the time is synced with the NTP server")
+            .writable()
+            .with_duration_range(Duration::from_secs(60), Duration::from_secs(24 * 60 * 60))
+            .with_step(60)
+            .with_default_duration(Duration::from_secs(60 * 60)),
+        AlarmTime: time("Alarm")
+            .with_help("Time of day the alarm goes off")
+            .writable()
+            .with_step(60)
+            .with_default_time(TimeOfDay::new(7, 30, 0)),
+        AlarmDays: flags("Alarm Days", &WEEKDAYS)
+            .with_help("Days of the week the alarm goes off")
+            .with_default_flags(0b0011111),
+        TempOffset: decimal("Temperature offset", 1)
+            .with_help("Calibration added to the measured temperature")
+            .with_unit("°C")
+            .writable()
+            .with_decimal_range(-5.0, 5.0)
+            .with_default_decimal(0.0),
+        Use24Hour: toggle("24-hour clock")
+            .with_help("Show the time as 13:00 instead of 1:00")
+            .with_default_bool(true),
+        KnownNetworks: table("Known Networks", &KNOWN_NETWORK, 8)
+            .with_help("Other networks to connect to when the main one is out of reach, highest priority first")
     },
     sections: Section {
         Wifi: section("WiFi")
@@ -79,25 +141,59 @@ elytra!( pub MOCK_CONF: MockConf {
         Section::Wifi: [
             Field::Info(InfoField::WifiStatus),
             Field::Prop(PropField::WifiNetwork),
-            Field::Prop(PropField::WifiPassword)
+            Field::Prop(PropField::WifiPassword),
+            Field::Prop(PropField::KnownNetworks),
+            Field::Info(InfoField::WifiMac),
+            Field::Prop(PropField::Hostname),
+            Field::Prop(PropField::StaticIp),
+            Field::Prop(PropField::Gateway),
+            Field::Prop(PropField::Dns)
         ],
         Section::Display: [
-            Field::Prop(PropField::BrightOffset)
+            Field::Prop(PropField::BrightOffset),
+            Field::Prop(PropField::SegmentColor)
         ],
         Section::Clock: [
             Field::Info(InfoField::Time),
+            Field::Info(InfoField::LastSync),
+            Field::Info(InfoField::Temperature),
             Field::Prop(PropField::NtpServer),
-            Field::Prop(PropField::TimeZone)
+            Field::Prop(PropField::SyncInterval),
+            Field::Prop(PropField::AlarmTime),
+            Field::Prop(PropField::AlarmDays),
+            Field::Prop(PropField::TimeZone),
+            Field::Prop(PropField::Use24Hour)
         ],
         Section::Hardware: [
-            Field::Prop(PropField::Serial),
             Field::Info(InfoField::FlashUUID),
             Field::Info(InfoField::FlashJEDEC),
-            Field::Info(InfoField::PicoROM)
+            Field::Info(InfoField::PicoROM),
+            Field::Prop(PropField::TempOffset)
         ]
     }
 }
 );
 
 #[cfg(target_arch = "wasm32")]
-elytra_wasm::elytra_wasm! (crate::handler::handle_command);
\ No newline at end of file
+type WasmHandler = elytra_conf::transaction::TransactionHandler<
+    elytra_conf::store::StoreHandler<elytra_wasm::HostStore, crate::handler::ClockHandler>,
+    { PropField::ENTRIES.len() }
+>;
+
+#[cfg(target_arch = "wasm32")]
+fn wasm_handler() -> WasmHandler {
+    let mut store = elytra_wasm::HostStore;
+    // Values saved by older firmware are upgraded before anything reads them
+    if let Err(e) = MOCK_CONF.migrate(&mut store, &handler::MIGRATIONS) {
+        log::warn!("migrating the stored values failed: {:?}", e);
+    }
+    elytra_conf::transaction::TransactionHandler::new(
+        elytra_conf::store::StoreHandler::new(store, crate::handler::ClockHandler),
+        Duration::from_secs(30)
+    )
+}
+
+#[cfg(target_arch = "wasm32")]
+elytra_wasm::elytra_wasm!(MOCK_CONF, WasmHandler = wasm_handler(), tick = |handler, elapsed| {
+    handler.elapse(elapsed);
+});